@@ -0,0 +1,32 @@
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+fn temporary_path(filename: &Path) -> PathBuf {
+    let mut path = OsString::from(filename.as_os_str());
+    path.push(".tmp");
+    PathBuf::from(path)
+}
+
+// Writes the contents to a temporary file alongside the destination and then
+// renames it into place, so that a crash part way through never leaves a
+// half-written file behind. The temporary file lives in the same directory so
+// that the rename doesn't cross filesystems.
+pub fn write_atomically(filename: &Path, contents: &[u8]) -> Result<(), String> {
+    let temporary = temporary_path(filename);
+
+    let written = File::create(&temporary).and_then(|mut file| {
+        file.write_all(contents)?;
+        file.sync_all()
+    });
+
+    if let Err(e) = written.and_then(|_| fs::rename(&temporary, filename)) {
+        // Don't leave the partial file lying around; we're already reporting
+        // an error so there's nothing useful to do if this fails too
+        let _ = fs::remove_file(&temporary);
+        return Err(e.to_string());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,49 @@
+use std::time::{Duration, Instant};
+use tracing::info;
+
+// How often the collected frame times are summarized in the log
+const REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+// Keeps track of how long each frame takes to update and draw, and
+// periodically logs a summary. Frame times stop before swapping buffers, so
+// they don't include time spent waiting for vsync, and show how much headroom
+// is left rather than the frame rate we actually end up running at. Drawing
+// is only handed off to the GPU by then, so time it spends catching up shows
+// up in the frame rate rather than the frame times.
+pub struct Benchmark {
+    interval_start: Instant,
+    frames: u32,
+    total: Duration,
+    worst: Duration,
+}
+
+impl Benchmark {
+    pub fn new() -> Self {
+        Self {
+            interval_start: Instant::now(),
+            frames: 0,
+            total: Duration::ZERO,
+            worst: Duration::ZERO,
+        }
+    }
+
+    pub fn record_frame(&mut self, frame_time: Duration) {
+        self.frames += 1;
+        self.total += frame_time;
+        self.worst = self.worst.max(frame_time);
+
+        let elapsed = self.interval_start.elapsed();
+        if elapsed >= REPORT_INTERVAL {
+            let average = self.total / self.frames;
+            info!(
+                "{} frames in {:.2?}: average {average:.2?}, worst {:.2?}, {:.1} fps",
+                self.frames,
+                elapsed,
+                self.worst,
+                self.frames as f64 / elapsed.as_secs_f64()
+            );
+
+            *self = Self::new();
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use clap::{ArgAction, Parser};
+use std::path::PathBuf;
+
+/// A desktop pet for your fursona.
+#[derive(Parser)]
+#[command(version, about)]
+pub struct Cli {
+    /// Path to the settings file, which may be JSON, TOML or YAML depending on
+    /// its extension. Defaults to `desktop-fursona/settings.json` inside the
+    /// platform's config directory (`$XDG_CONFIG_HOME` on Linux).
+    #[arg(short, long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Path to the file used to remember where everyone was between runs.
+    /// Defaults to `desktop-fursona/state.json` inside the platform's state
+    /// directory (`$XDG_STATE_HOME` on Linux).
+    #[arg(long, value_name = "FILE")]
+    pub state: Option<PathBuf>,
+
+    /// Restore the settings file from a backup and exit. Backups are taken
+    /// before every migration; 1 is the most recent.
+    #[arg(
+        long,
+        value_name = "N",
+        num_args = 0..=1,
+        default_missing_value = "1"
+    )]
+    pub rollback: Option<u32>,
+
+    /// Log more detail. Repeat for even more (-vv also times every frame).
+    /// `RUST_LOG` takes precedence when set.
+    #[arg(short, long, action = ArgAction::Count)]
+    pub verbose: u8,
+
+    /// Also write logs to daily rotated files in `desktop-fursona/logs` inside
+    /// the platform's state directory (`$XDG_STATE_HOME` on Linux).
+    #[arg(long)]
+    pub log_to_file: bool,
+
+    /// Print the JSON Schema for the settings file and exit.
+    #[arg(long)]
+    pub print_schema: bool,
+
+    /// Write the settings file out to FILE, in the format given by FILE's
+    /// extension (.json, .toml or .yaml), and exit. Image paths are copied
+    /// as-is, so keep FILE in the same directory.
+    #[arg(long, value_name = "FILE")]
+    pub convert: Option<PathBuf>,
+
+    /// Import the Shimeji-ee character whose images are in DIR into the
+    /// settings file as a new fursona named after DIR, and exit. Its
+    /// actions.xml and behaviors.xml are looked for in DIR's `conf` folder,
+    /// in DIR itself, or in Shimeji-ee's shared `conf` folder. The images are
+    /// copied in next to the settings file.
+    #[arg(long, value_name = "DIR")]
+    pub import_shimeji: Option<PathBuf>,
+
+    /// Run with N instances of your fursona (cycling through them if you have
+    /// more than one) and log how long each frame takes. Where everyone was
+    /// isn't restored or saved in this mode.
+    #[arg(long, value_name = "N")]
+    pub benchmark: Option<usize>,
+
+    /// Pretend a few windows are open and moving around, for trying out the
+    /// `platform` behavior without an X server.
+    #[arg(long)]
+    pub mock_windows: bool,
+}
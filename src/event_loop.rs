@@ -1,6 +1,6 @@
 mod time;
 
-use crate::stage::ViewportPoint;
+use crate::stage::PhysicalPoint;
 use device_query::{DeviceQuery, DeviceState, Keycode, MouseState};
 use time::Time;
 use winit::error::EventLoopError;
@@ -14,10 +14,13 @@ use winit::platform::macos::{ActivationPolicy, EventLoopBuilderExtMacOS};
 
 pub enum Event {
     Initialization,
-    MouseMove(ViewportPoint),
-    MouseDown(ViewportPoint),
+    // Mouse positions are reported in device pixels, relative to the desktop
+    MouseMove(PhysicalPoint),
+    MouseDown(PhysicalPoint),
     Update { delta_t_ms: u32 },
+    // In device pixels
     Resized { width: u32, height: u32 },
+    ScaleFactorChanged { scale_factor: f64 },
     Exit,
 }
 
@@ -47,8 +50,17 @@ impl EventLoop {
     where
         F: FnMut(Event),
     {
-        // Cheaply creates an empty DeviceState
-        let device_state = DeviceState::checked_new().unwrap();
+        // Cheaply creates an empty DeviceState. On macOS this fails when we
+        // haven't been given permission to read the mouse and keyboard.
+        #[cfg(target_os = "macos")]
+        let Some(device_state) = DeviceState::checked_new() else {
+            tracing::error!(
+                "Couldn't read the mouse and keyboard. Allow this app under Privacy & Security > Accessibility in System Settings, then start it again."
+            );
+            std::process::exit(-1);
+        };
+        #[cfg(not(target_os = "macos"))]
+        let device_state = DeviceState::new();
         let mut prev_mouse_state = MouseState::default();
 
         let mut time = Time::new();
@@ -73,14 +85,14 @@ impl EventLoop {
 
                 let mouse_state = device_state.get_mouse();
                 if mouse_state != prev_mouse_state {
-                    event_handler(Event::MouseMove(ViewportPoint {
+                    event_handler(Event::MouseMove(PhysicalPoint {
                         x: mouse_state.coords.0 as f32,
                         y: mouse_state.coords.1 as f32,
                     }));
 
                     let lmb = mouse_state.button_pressed.get(1).unwrap_or(&false);
                     if *lmb {
-                        event_handler(Event::MouseDown(ViewportPoint {
+                        event_handler(Event::MouseDown(PhysicalPoint {
                             x: mouse_state.coords.0 as f32,
                             y: mouse_state.coords.1 as f32,
                         }));
@@ -101,6 +113,9 @@ impl EventLoop {
                         });
                     }
                 }
+                WinitWindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                    event_handler(Event::ScaleFactorChanged { scale_factor });
+                }
                 WinitWindowEvent::RedrawRequested => {
                     event_handler(Event::Update {
                         delta_t_ms: time.delta_ms(),
@@ -1,18 +1,66 @@
 pub mod animation;
 pub mod behaviors;
 mod fursona_instance;
+pub mod model;
 pub mod rendering;
+pub mod skeleton;
 
+use crate::rendering::{Palette, SpriteEffects, TextureCache, TextureFilter};
 use crate::stage::Stage;
+use animation::{FrameRect, ProceduralMotionStyle};
+use behaviors::BehaviorKind;
+use std::collections::HashMap;
+use std::path::PathBuf;
 
-pub use fursona_instance::FursonaInstance;
+pub use fursona_instance::{FursonaInstance, FursonaInstanceError, FursonaInstanceState};
+
+pub struct FursonaKeyframe {
+    pub duration_ms: u32,
+    pub image: PathBuf,
+    // In the image's pixels, from its top-left corner
+    pub anchor: Option<(f32, f32)>,
+    pub hitbox: Option<FrameRect>,
+    pub events: Vec<String>,
+}
+
+pub struct FursonaModel {
+    pub path: PathBuf,
+    // How tall the model stands, in pixels before scaling
+    pub height: f32,
+}
 
 pub struct Fursona {
     pub name: String,
+    // By their names in `ANIMATION_NAMES`, leaving out any it doesn't have
+    pub animations: HashMap<&'static str, Vec<FursonaKeyframe>>,
+    // Animated instead of `animations` when set
+    pub skeleton: Option<PathBuf>,
+    // Animated instead of either of those when set
+    pub model: Option<FursonaModel>,
+    // Drawn at this many times the size of the images
+    pub scale: u32,
+    pub filter: TextureFilter,
+    // The index of the monitor to stay on, if any
+    pub monitor: Option<usize>,
+    pub behavior: BehaviorKind,
+    pub effects: SpriteEffects,
+    // Applied to every image as it's loaded
+    pub palette: Palette,
+    // How long switching between animations fades for, if at all
+    pub crossfade_ms: u32,
+    // How fast the fursona walks, and how fast the walk animations were made
+    // for, both in pixels per second
+    pub speed: f32,
+    pub walk_cycle_speed: f32,
+    pub procedural_motion: ProceduralMotionStyle,
 }
 
 impl Fursona {
-    pub fn make_instance(&self, stage: &Stage) -> FursonaInstance {
-        FursonaInstance::new(self, stage)
+    pub fn make_instance(
+        &self,
+        stage: &Stage,
+        texture_cache: &mut TextureCache,
+    ) -> Result<FursonaInstance, FursonaInstanceError> {
+        FursonaInstance::new(self, stage, texture_cache)
     }
 }
@@ -1,12 +1,100 @@
 pub mod animation_2d;
+pub mod animation_3d;
+mod animation_parameters;
+pub mod animation_skeletal;
+mod animation_state_machine;
+mod clip_playhead;
+mod easing;
+mod frame_rect;
+mod procedural_motion;
+mod procedural_motion_style;
+mod tween;
+
+use super::rendering::FursonaRenderer;
+use std::collections::HashMap;
+
+pub use animation_parameters::AnimationParameters;
+pub use animation_state_machine::AnimationStateMachine;
+pub use clip_playhead::ClipPlayhead;
+pub use easing::Easing;
+pub use frame_rect::FrameRect;
+pub use procedural_motion::ProceduralMotion;
+pub use procedural_motion_style::ProceduralMotionStyle;
+pub use tween::Tween;
 
 pub trait Animation {
     type ValidRenderer;
 
-    fn advance(&mut self, delta_t_ms: u32, renderer: &mut Self::ValidRenderer) -> ();
+    // Moves the animation along, returning the names of the events on every
+    // frame that was reached along the way, in order.
+    fn advance(&mut self, delta_t_ms: u32, renderer: &mut Self::ValidRenderer) -> Vec<String>;
     fn is_finished(&self) -> bool;
+    // How long it takes to play through once, as the keyframes were timed
+    fn duration_ms(&self) -> f32;
     fn intrinsic_dimensions(&self) -> (f32, f32);
+    // The point in the current frame that's kept in the same place on screen
+    // from one frame to the next.
+    fn anchor(&self) -> (f32, f32);
+    // The part of the current frame that counts as the fursona, for things
+    // like the mouse hovering over it. The whole frame when not set.
+    fn hitbox(&self) -> Option<FrameRect>;
     fn reset(&mut self) -> ();
+    // How fast to play, where 1.0 is as the keyframes were timed. Stays in
+    // effect across resets.
+    fn set_playback_rate(&mut self, rate: f32);
 }
 
 pub type AnimationConstructor<T> = dyn FnMut() -> Box<dyn Animation<ValidRenderer = T>>;
+
+pub type AnimDictionaryEntry<T> = Box<AnimationConstructor<T>>;
+
+// Every animation a fursona can have, by the names settings files,
+// skeletons and models use for them
+pub const ANIMATION_NAMES: [&str; 18] = [
+    "walk_left",
+    "walk_right",
+    "idle_left",
+    "idle_right",
+    "fall_left",
+    "fall_right",
+    "turn_left",
+    "turn_right",
+    "climb_left",
+    "climb_right",
+    "hang_left",
+    "hang_right",
+    "jump_start_left",
+    "jump_start_right",
+    "airborne_left",
+    "airborne_right",
+    "land_left",
+    "land_right",
+];
+
+// The animations a fursona has, by their names in `ANIMATION_NAMES`
+pub struct AnimationDictionary<T>
+where
+    T: FursonaRenderer,
+{
+    entries: HashMap<&'static str, AnimDictionaryEntry<T>>,
+}
+
+impl<T: FursonaRenderer> AnimationDictionary<T> {
+    pub fn new() -> Self {
+        Self {
+            entries: HashMap::new(),
+        }
+    }
+
+    pub fn insert(&mut self, name: &'static str, entry: AnimDictionaryEntry<T>) {
+        self.entries.insert(name, entry);
+    }
+
+    pub fn has(&self, name: &str) -> bool {
+        self.entries.contains_key(name)
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut AnimDictionaryEntry<T>> {
+        self.entries.get_mut(name)
+    }
+}
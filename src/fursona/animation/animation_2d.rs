@@ -1,12 +1,15 @@
-use std::rc::Rc;
-
-use glium::texture::CompressedTexture2d;
-
-use super::{super::rendering::renderer_2d::FursonaRenderer2D, Animation};
+use super::{super::rendering::renderer_2d::FursonaRenderer2D, Animation, FrameRect};
+use crate::rendering::AtlasRegion;
 
+#[derive(Clone)]
 pub struct Keyframe2D {
     pub duration_ms: u32,
-    pub image: Rc<CompressedTexture2d>,
+    pub image: AtlasRegion,
+    // In the image's pixels, from its top-left corner
+    pub anchor: (f32, f32),
+    pub hitbox: Option<FrameRect>,
+    // Reported back when this frame is reached
+    pub events: Vec<String>,
 }
 
 #[derive(Copy, Clone)]
@@ -24,6 +27,10 @@ enum AnimationState {
 pub struct Animation2D {
     keyframes: Vec<Keyframe2D>,
     state: AnimationState,
+    playback_rate: f32,
+    // The fraction of a millisecond left over after scaling the last delta by
+    // the playback rate, so that slow rates still add up over time
+    leftover_ms: f32,
 }
 
 impl Animation2D {
@@ -31,6 +38,8 @@ impl Animation2D {
         Self {
             keyframes,
             state: AnimationState::NotStarted,
+            playback_rate: 1.0,
+            leftover_ms: 0.0,
         }
     }
 
@@ -44,18 +53,39 @@ impl Animation2D {
             None
         }
     }
+
+    fn current_keyframe(&self) -> Option<&Keyframe2D> {
+        let index = match self.state {
+            AnimationState::NotStarted => 0,
+            AnimationState::Active(current) => current.index,
+            AnimationState::Finished => self.keyframes.len().checked_sub(1)?,
+        };
+
+        self.keyframes.get(index)
+    }
 }
 
 impl Animation for Animation2D {
     type ValidRenderer = FursonaRenderer2D;
 
-    fn advance(&mut self, delta_t_ms: u32, renderer: &mut Self::ValidRenderer) -> () {
+    fn advance(&mut self, delta_t_ms: u32, renderer: &mut Self::ValidRenderer) -> Vec<String> {
+        let mut events = Vec::new();
+
+        let scaled_ms = delta_t_ms as f32 * self.playback_rate + self.leftover_ms;
+        let delta_t_ms = scaled_ms.floor();
+        self.leftover_ms = scaled_ms - delta_t_ms;
+        let delta_t_ms = delta_t_ms as u32;
+
         let (current_frame, delta_t_ms) = match self.state {
             AnimationState::NotStarted => {
                 // We'll set the delta_t_ms to 0 here, because we're starting the
                 // animation -- there isn't a delta from the previous frame, because
                 // there wasn't a previous frame.
-                (self.get_fresh_frame(0), 0)
+                let first = self.get_fresh_frame(0);
+                if first.is_some() {
+                    events.extend(self.keyframes[0].events.iter().cloned());
+                }
+                (first, 0)
             }
             AnimationState::Active(current) => (Some(current), delta_t_ms),
             AnimationState::Finished => (None, 0),
@@ -67,11 +97,12 @@ impl Animation for Animation2D {
             Some(f) => f,
             None => {
                 self.state = AnimationState::Finished;
-                return;
+                return events;
             }
         };
 
-        // If the current frame would end, move to the next one
+        // If the current frame would end, move to the next one. Frames we
+        // skip straight past still get their events reported.
         let mut delta_t_ms = delta_t_ms;
         while delta_t_ms > current_frame.time_remaining {
             delta_t_ms -= current_frame.time_remaining;
@@ -80,9 +111,10 @@ impl Animation for Animation2D {
                 Some(f) => f,
                 None => {
                     self.state = AnimationState::Finished;
-                    return;
+                    return events;
                 }
             };
+            events.extend(self.keyframes[current_frame.index].events.iter().cloned());
         }
 
         // We now have THE current and correct frame. If this is different from
@@ -95,7 +127,8 @@ impl Animation for Animation2D {
 
         if should_update_state {
             self.state = AnimationState::Active(current_frame);
-            renderer.set_texture(self.keyframes[current_frame.index].image.clone());
+            let keyframe = &self.keyframes[current_frame.index];
+            renderer.set_texture(keyframe.image.clone(), keyframe.anchor);
         }
 
         // Decrease the remaining time on this frame, but otherwise no other
@@ -108,6 +141,8 @@ impl Animation for Animation2D {
                 _ => (),
             }
         }
+
+        events
     }
 
     fn is_finished(&self) -> bool {
@@ -117,14 +152,15 @@ impl Animation for Animation2D {
         }
     }
 
-    fn intrinsic_dimensions(&self) -> (f32, f32) {
-        let index = match self.state {
-            AnimationState::NotStarted => 0,
-            AnimationState::Active(current) => current.index,
-            AnimationState::Finished => self.keyframes.len() - 1,
-        };
+    fn duration_ms(&self) -> f32 {
+        self.keyframes
+            .iter()
+            .map(|keyframe| keyframe.duration_ms as f32)
+            .sum()
+    }
 
-        if let Some(keyframe) = self.keyframes.get(index) {
+    fn intrinsic_dimensions(&self) -> (f32, f32) {
+        if let Some(keyframe) = self.current_keyframe() {
             let (width, height) = keyframe.image.dimensions();
             return (width as f32, height as f32);
         }
@@ -132,7 +168,21 @@ impl Animation for Animation2D {
         (0.0, 0.0)
     }
 
+    fn anchor(&self) -> (f32, f32) {
+        self.current_keyframe()
+            .map_or((0.0, 0.0), |keyframe| keyframe.anchor)
+    }
+
+    fn hitbox(&self) -> Option<FrameRect> {
+        self.current_keyframe()?.hitbox.clone()
+    }
+
     fn reset(&mut self) {
         self.state = AnimationState::NotStarted;
+        self.leftover_ms = 0.0;
+    }
+
+    fn set_playback_rate(&mut self, rate: f32) {
+        self.playback_rate = rate.max(0.0);
     }
 }
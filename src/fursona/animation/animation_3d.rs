@@ -0,0 +1,67 @@
+use super::{
+    super::{model::Model, rendering::renderer_3d::FursonaRenderer3D},
+    Animation, ClipPlayhead, FrameRect,
+};
+use std::rc::Rc;
+
+// Plays one of a model's animations, posing the model for the renderer as it
+// goes.
+pub struct Animation3D {
+    model: Rc<Model>,
+    animation: usize,
+    // Which way the model is turned while this plays, in degrees
+    yaw: f32,
+    playhead: ClipPlayhead,
+}
+
+impl Animation3D {
+    pub fn new(model: Rc<Model>, animation: usize, yaw: f32) -> Self {
+        Self {
+            model,
+            animation,
+            yaw,
+            playhead: ClipPlayhead::new(),
+        }
+    }
+}
+
+impl Animation for Animation3D {
+    type ValidRenderer = FursonaRenderer3D;
+
+    fn advance(&mut self, delta_t_ms: u32, renderer: &mut Self::ValidRenderer) -> Vec<String> {
+        let (_, time_ms) = self.playhead.advance(delta_t_ms, self.duration_ms());
+
+        renderer.set_pose(self.model.pose(self.animation, time_ms / 1000.0, self.yaw));
+
+        // glTF has nowhere to put events
+        Vec::new()
+    }
+
+    fn is_finished(&self) -> bool {
+        self.playhead.is_finished(self.duration_ms())
+    }
+
+    fn duration_ms(&self) -> f32 {
+        self.model.duration(self.animation) * 1000.0
+    }
+
+    fn intrinsic_dimensions(&self) -> (f32, f32) {
+        self.model.dimensions()
+    }
+
+    fn anchor(&self) -> (f32, f32) {
+        self.model.anchor()
+    }
+
+    fn hitbox(&self) -> Option<FrameRect> {
+        None
+    }
+
+    fn reset(&mut self) {
+        self.playhead.reset();
+    }
+
+    fn set_playback_rate(&mut self, rate: f32) {
+        self.playhead.set_playback_rate(rate);
+    }
+}
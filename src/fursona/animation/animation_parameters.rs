@@ -0,0 +1,18 @@
+use crate::fursona::behaviors::{Facing, Grip};
+
+// What a behavior tells the animation state machine about what the fursona
+// is doing, which it uses to pick which animation to show.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnimationParameters {
+    // How fast the fursona is moving along whatever it's on, in pixels per
+    // second
+    pub speed: f32,
+    pub facing: Facing,
+    // False while jumping, falling, climbing or hanging
+    pub grounded: bool,
+    // Set while climbing or hanging
+    pub grip: Option<Grip>,
+    // Set from leaving the ground by jumping until landing again, as opposed
+    // to just falling
+    pub jumping: bool,
+}
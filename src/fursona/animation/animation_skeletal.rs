@@ -0,0 +1,69 @@
+use super::{
+    super::{rendering::renderer_skeletal::FursonaRendererSkeletal, skeleton::Skeleton},
+    Animation, ClipPlayhead, FrameRect,
+};
+use std::rc::Rc;
+
+// Plays one of a skeleton's animations, posing the skeleton for the renderer
+// as it goes.
+pub struct AnimationSkeletal {
+    skeleton: Rc<Skeleton>,
+    animation: usize,
+    mirrored: bool,
+    playhead: ClipPlayhead,
+}
+
+impl AnimationSkeletal {
+    // `mirrored` flips the skeleton horizontally, for facing the other way to
+    // how it was made
+    pub fn new(skeleton: Rc<Skeleton>, animation: usize, mirrored: bool) -> Self {
+        Self {
+            skeleton,
+            animation,
+            mirrored,
+            playhead: ClipPlayhead::new(),
+        }
+    }
+}
+
+impl Animation for AnimationSkeletal {
+    type ValidRenderer = FursonaRendererSkeletal;
+
+    fn advance(&mut self, delta_t_ms: u32, renderer: &mut Self::ValidRenderer) -> Vec<String> {
+        let (previous_ms, time_ms) = self.playhead.advance(delta_t_ms, self.duration_ms());
+        let time = time_ms / 1000.0;
+        renderer.set_pose(self.skeleton.pose(self.animation, time, self.mirrored));
+
+        self.skeleton
+            .animation(self.animation)
+            .events_between(previous_ms.map(|ms| ms / 1000.0), time)
+    }
+
+    fn is_finished(&self) -> bool {
+        self.playhead.is_finished(self.duration_ms())
+    }
+
+    fn duration_ms(&self) -> f32 {
+        self.skeleton.animation(self.animation).duration * 1000.0
+    }
+
+    fn intrinsic_dimensions(&self) -> (f32, f32) {
+        self.skeleton.dimensions()
+    }
+
+    fn anchor(&self) -> (f32, f32) {
+        self.skeleton.anchor(self.mirrored)
+    }
+
+    fn hitbox(&self) -> Option<FrameRect> {
+        None
+    }
+
+    fn reset(&mut self) {
+        self.playhead.reset();
+    }
+
+    fn set_playback_rate(&mut self, rate: f32) {
+        self.playhead.set_playback_rate(rate);
+    }
+}
@@ -0,0 +1,752 @@
+use super::{Animation, AnimationDictionary, AnimationParameters, FrameRect, ANIMATION_NAMES};
+use crate::fursona::{
+    behaviors::{Facing, Grip},
+    rendering::FursonaRenderer,
+};
+use tracing::debug;
+
+pub type AnimationCondition = dyn Fn(&AnimationParameters) -> bool;
+
+pub enum Playback {
+    // Starts over from the beginning whenever it finishes
+    Loop,
+    // Holds on the last frame once it finishes
+    Once,
+}
+
+pub enum TransitionStyle {
+    // Cut straight to the new animation
+    Immediate,
+    // Fade from the last frame shown into the new animation
+    Crossfade { duration_ms: u32 },
+    // Play the named state through once before moving on, like a turn
+    Through(&'static str),
+}
+
+struct AnimationState<T> {
+    name: &'static str,
+    animation: Box<dyn Animation<ValidRenderer = T>>,
+    playback: Playback,
+    // The speed the animation was made to be played at, when it should be
+    // played faster or slower to match how fast the fursona is moving
+    reference_speed: Option<f32>,
+}
+
+struct Transition {
+    // Applies from any state when not set
+    from: Option<usize>,
+    to: usize,
+    condition: Box<AnimationCondition>,
+    style: TransitionStyle,
+}
+
+// What the fursona is doing, as far as which animation to show goes
+#[derive(Clone, Copy, PartialEq)]
+enum Activity {
+    Walk,
+    Idle,
+    Fall,
+    Airborne,
+    Climb,
+    Hang,
+}
+
+struct Crossfade {
+    elapsed_ms: u32,
+    duration_ms: u32,
+}
+
+// Picks which of a fursona's animations to play. Behaviors describe what the
+// fursona is doing through `AnimationParameters`, and the first transition
+// whose condition matches them moves us on to a new state. Transitions are
+// checked in the order they were added.
+pub struct AnimationStateMachine<T: FursonaRenderer> {
+    states: Vec<AnimationState<T>>,
+    transitions: Vec<Transition>,
+    // Nothing is playing until the first time we're advanced
+    current: Option<usize>,
+    // Where we're headed once the current state finishes, when playing
+    // through an intermediate state
+    pending: Option<usize>,
+    crossfade: Option<Crossfade>,
+}
+
+impl<T: FursonaRenderer> AnimationStateMachine<T> {
+    pub fn new() -> Self {
+        Self {
+            states: Vec::new(),
+            transitions: Vec::new(),
+            current: None,
+            pending: None,
+            crossfade: None,
+        }
+    }
+
+    fn index_of(&self, name: &str) -> Option<usize> {
+        self.states.iter().position(|state| state.name == name)
+    }
+
+    pub fn has_state(&self, name: &str) -> bool {
+        self.index_of(name).is_some()
+    }
+
+    // How long the named state's animation takes to play through once, at
+    // its usual speed
+    pub fn duration_ms(&self, name: &str) -> Option<f32> {
+        let index = self.index_of(name)?;
+        Some(self.states[index].animation.duration_ms())
+    }
+
+    pub fn add_state(
+        &mut self,
+        name: &'static str,
+        animation: Box<dyn Animation<ValidRenderer = T>>,
+        playback: Playback,
+    ) {
+        self.states.push(AnimationState {
+            name,
+            animation,
+            playback,
+            reference_speed: None,
+        });
+    }
+
+    // Plays the named state faster or slower depending on how fast the
+    // fursona is moving, at normal speed when it's moving at
+    // `reference_speed`. Like walking, so that feet don't slide.
+    pub fn sync_to_speed(&mut self, name: &str, reference_speed: f32) {
+        if reference_speed <= 0.0 {
+            return;
+        }
+
+        if let Some(index) = self.index_of(name) {
+            self.states[index].reference_speed = Some(reference_speed);
+        }
+    }
+
+    // Both states need to have been added already, along with any state
+    // played through on the way. Transitions to states that don't exist are
+    // ignored, so that callers don't need to check which animations a
+    // fursona actually has.
+    pub fn add_transition(
+        &mut self,
+        from: Option<&str>,
+        to: &str,
+        condition: Box<AnimationCondition>,
+        style: TransitionStyle,
+    ) {
+        let from = match from {
+            Some(from) => match self.index_of(from) {
+                Some(index) => Some(index),
+                None => return,
+            },
+            None => None,
+        };
+        let to = match self.index_of(to) {
+            Some(to) => to,
+            None => return,
+        };
+        if let TransitionStyle::Through(via) = style {
+            if !self.has_state(via) {
+                return;
+            }
+        }
+
+        self.transitions.push(Transition {
+            from,
+            to,
+            condition,
+            style,
+        });
+    }
+
+    // The standard set of states for a fursona, using whichever of the
+    // animations it has. Walking is the fallback for everything else, so
+    // there's always something to show.
+    pub fn standard(
+        anims: &mut AnimationDictionary<T>,
+        crossfade_ms: u32,
+        walk_cycle_speed: f32,
+    ) -> Self {
+        let mut machine = Self::new();
+
+        // Turning, jumping and landing are played once on the way to
+        // something else, and everything else loops
+        let played_once = ["turn_", "jump_start_", "land_"];
+        for name in ANIMATION_NAMES {
+            if let Some(constructor) = anims.get_mut(name) {
+                let playback = match played_once.iter().any(|prefix| name.starts_with(prefix)) {
+                    true => Playback::Once,
+                    false => Playback::Loop,
+                };
+                machine.add_state(name, constructor(), playback);
+            }
+        }
+        machine.sync_to_speed("walk_left", walk_cycle_speed);
+        machine.sync_to_speed("walk_right", walk_cycle_speed);
+
+        // Only use animations other than walking if they're there for both
+        // directions, so that we never end up facing the wrong way
+        let has_both = |name: &str| {
+            machine.has_state(&format!("{name}_left"))
+                && machine.has_state(&format!("{name}_right"))
+        };
+        let has_idle = has_both("idle");
+        let has_turn = has_both("turn");
+        let has_fall = has_both("fall");
+        let has_climb = has_both("climb");
+        let has_hang = has_both("hang");
+        let has_jump_start = has_both("jump_start");
+        let has_airborne = has_both("airborne");
+        let has_land = has_both("land");
+        let change_style = || match crossfade_ms {
+            0 => TransitionStyle::Immediate,
+            duration_ms => TransitionStyle::Crossfade { duration_ms },
+        };
+        // Plays the intermediate state when there is one, and changes as
+        // usual otherwise
+        let through = |via: &'static str, is_available: bool| match is_available {
+            true => TransitionStyle::Through(via),
+            false => change_style(),
+        };
+
+        // Settles on one activity for each set of parameters, falling back
+        // on the nearest thing the fursona has an animation for, so that only
+        // one state ever matches
+        let activity = move |p: &AnimationParameters| {
+            if p.grounded {
+                return match p.speed == 0.0 && has_idle {
+                    true => Activity::Idle,
+                    false => Activity::Walk,
+                };
+            }
+
+            match p.grip {
+                Some(Grip::Wall) if has_climb => Activity::Climb,
+                Some(Grip::Ceiling) if has_hang => Activity::Hang,
+                _ if p.jumping && has_airborne => Activity::Airborne,
+                _ if has_fall => Activity::Fall,
+                _ => Activity::Walk,
+            }
+        };
+
+        for (
+            facing,
+            [walk, idle, fall, turn, climb, hang, jump_start, airborne, land],
+            [opposite_walk, opposite_idle],
+        ) in [
+            (
+                Facing::Left,
+                [
+                    "walk_left",
+                    "idle_left",
+                    "fall_left",
+                    "turn_left",
+                    "climb_left",
+                    "hang_left",
+                    "jump_start_left",
+                    "airborne_left",
+                    "land_left",
+                ],
+                ["walk_right", "idle_right"],
+            ),
+            (
+                Facing::Right,
+                [
+                    "walk_right",
+                    "idle_right",
+                    "fall_right",
+                    "turn_right",
+                    "climb_right",
+                    "hang_right",
+                    "jump_start_right",
+                    "airborne_right",
+                    "land_right",
+                ],
+                ["walk_left", "idle_left"],
+            ),
+        ] {
+            let is = move |target: Activity| -> Box<AnimationCondition> {
+                Box::new(move |p| p.facing == facing && activity(p) == target)
+            };
+
+            // Turning around on the ground plays the turn animation first,
+            // when there is one
+            for from in [opposite_walk, opposite_idle] {
+                for (to, target) in [(walk, Activity::Walk), (idle, Activity::Idle)] {
+                    let is_target = is(target);
+                    machine.add_transition(
+                        Some(from),
+                        to,
+                        Box::new(move |p| p.grounded && is_target(p)),
+                        through(turn, has_turn),
+                    );
+                }
+            }
+
+            // Jumps get going and come back down with animations of their own,
+            // when there are some
+            if has_jump_start {
+                for from in [walk, idle, opposite_walk, opposite_idle] {
+                    machine.add_transition(
+                        Some(from),
+                        airborne,
+                        is(Activity::Airborne),
+                        TransitionStyle::Through(jump_start),
+                    );
+                }
+            }
+            if has_land {
+                for from in [airborne, fall] {
+                    for (to, target) in [(idle, Activity::Idle), (walk, Activity::Walk)] {
+                        machine.add_transition(
+                            Some(from),
+                            to,
+                            is(target),
+                            TransitionStyle::Through(land),
+                        );
+                    }
+                }
+            }
+
+            for (to, target) in [
+                (climb, Activity::Climb),
+                (hang, Activity::Hang),
+                (airborne, Activity::Airborne),
+                (fall, Activity::Fall),
+                (idle, Activity::Idle),
+                (walk, Activity::Walk),
+            ] {
+                machine.add_transition(None, to, is(target), change_style());
+            }
+        }
+
+        machine
+    }
+
+    fn enter(&mut self, index: usize) {
+        if let Some(current) = self.current {
+            debug!(
+                "changing {} to {}",
+                self.states[current].name, self.states[index].name
+            );
+        }
+
+        self.current = Some(index);
+        self.states[index].animation.reset();
+    }
+
+    // Returns the events of every frame reached along the way, including
+    // those of any state we moved into
+    pub fn advance(
+        &mut self,
+        delta_t_ms: u32,
+        renderer: &mut T,
+        parameters: &AnimationParameters,
+    ) -> Vec<String> {
+        // Intermediate states always play through to the end before we
+        // consider going anywhere else
+        if self.pending.is_none() {
+            let transition = self.transitions.iter().find(|transition| {
+                transition
+                    .from
+                    .is_none_or(|from| Some(from) == self.current)
+                    && Some(transition.to) != self.current
+                    && (transition.condition)(parameters)
+            });
+
+            match transition {
+                // The first state we enter has nothing to transition from
+                Some(transition) if self.current.is_none() => self.enter(transition.to),
+                Some(transition) => match transition.style {
+                    TransitionStyle::Immediate => self.enter(transition.to),
+                    TransitionStyle::Crossfade { duration_ms } => {
+                        let to = transition.to;
+                        renderer.start_crossfade();
+                        self.crossfade = Some(Crossfade {
+                            elapsed_ms: 0,
+                            duration_ms,
+                        });
+                        self.enter(to);
+                    }
+                    TransitionStyle::Through(via) => {
+                        // `add_transition` only keeps these when the
+                        // intermediate state exists
+                        if let Some(via) = self.index_of(via) {
+                            self.pending = Some(transition.to);
+                            self.enter(via);
+                        }
+                    }
+                },
+                None => (),
+            }
+        }
+
+        let current = match self.current {
+            Some(current) => current,
+            None => return Vec::new(),
+        };
+
+        let state = &mut self.states[current];
+        if let Some(reference_speed) = state.reference_speed {
+            state
+                .animation
+                .set_playback_rate(parameters.speed / reference_speed);
+        }
+        let mut events = state.animation.advance(delta_t_ms, renderer);
+
+        if state.animation.is_finished() {
+            if let Some(next) = self.pending.take() {
+                self.enter(next);
+                events.extend(self.states[next].animation.advance(0, renderer));
+            } else if let Playback::Loop = state.playback {
+                state.animation.reset();
+                events.extend(state.animation.advance(0, renderer));
+            }
+        }
+
+        if let Some(crossfade) = &mut self.crossfade {
+            crossfade.elapsed_ms += delta_t_ms;
+            let progress = crossfade.elapsed_ms as f32 / crossfade.duration_ms as f32;
+            renderer.set_crossfade_progress(progress.min(1.0));
+            if progress >= 1.0 {
+                self.crossfade = None;
+            }
+        }
+
+        events
+    }
+
+    fn current_animation(&self) -> Option<&dyn Animation<ValidRenderer = T>> {
+        // Before we've started, the first state is as good a guess as any
+        self.states
+            .get(self.current.unwrap_or(0))
+            .map(|state| state.animation.as_ref())
+    }
+
+    // The size of whatever animation is currently playing
+    pub fn intrinsic_dimensions(&self) -> (f32, f32) {
+        self.current_animation()
+            .map_or((0.0, 0.0), |animation| animation.intrinsic_dimensions())
+    }
+
+    pub fn anchor(&self) -> (f32, f32) {
+        self.current_animation()
+            .map_or((0.0, 0.0), |animation| animation.anchor())
+    }
+
+    pub fn hitbox(&self) -> Option<FrameRect> {
+        self.current_animation()?.hitbox()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rendering::{Renderer, RendererRect, SpriteEffects, Transform2D};
+
+    const FRAME_MS: u32 = 16;
+    const ANIMATION_MS: u32 = 100;
+
+    struct TestRenderer {
+        effects: SpriteEffects,
+        // Whichever animation advanced last
+        showing: &'static str,
+        // How far through fading out the previous frame we are, while we are
+        crossfade: Option<f32>,
+    }
+
+    impl FursonaRenderer for TestRenderer {
+        fn draw(&self, _renderer: &mut Renderer, _rect: RendererRect) {}
+
+        fn effects_mut(&mut self) -> &mut SpriteEffects {
+            &mut self.effects
+        }
+
+        fn set_transform(&mut self, _transform: Transform2D) {}
+
+        fn start_crossfade(&mut self) {
+            self.crossfade = Some(0.0);
+        }
+
+        fn set_crossfade_progress(&mut self, progress: f32) {
+            self.crossfade = (progress < 1.0).then_some(progress);
+        }
+    }
+
+    // Shows its name for ANIMATION_MS, with an event named after it whenever
+    // it starts
+    struct TestAnimation {
+        name: &'static str,
+        elapsed_ms: Option<u32>,
+    }
+
+    impl Animation for TestAnimation {
+        type ValidRenderer = TestRenderer;
+
+        fn advance(&mut self, delta_t_ms: u32, renderer: &mut TestRenderer) -> Vec<String> {
+            renderer.showing = self.name;
+            match &mut self.elapsed_ms {
+                None => {
+                    self.elapsed_ms = Some(0);
+                    vec![self.name.to_owned()]
+                }
+                Some(elapsed_ms) => {
+                    *elapsed_ms = (*elapsed_ms + delta_t_ms).min(ANIMATION_MS);
+                    Vec::new()
+                }
+            }
+        }
+
+        fn is_finished(&self) -> bool {
+            self.elapsed_ms == Some(ANIMATION_MS)
+        }
+
+        fn duration_ms(&self) -> f32 {
+            ANIMATION_MS as f32
+        }
+
+        fn intrinsic_dimensions(&self) -> (f32, f32) {
+            (40.0, 60.0)
+        }
+
+        fn anchor(&self) -> (f32, f32) {
+            (20.0, 60.0)
+        }
+
+        fn hitbox(&self) -> Option<FrameRect> {
+            None
+        }
+
+        fn reset(&mut self) {
+            self.elapsed_ms = None;
+        }
+
+        fn set_playback_rate(&mut self, _rate: f32) {}
+    }
+
+    fn animation(name: &'static str) -> Box<dyn Animation<ValidRenderer = TestRenderer>> {
+        Box::new(TestAnimation {
+            name,
+            elapsed_ms: None,
+        })
+    }
+
+    fn standard(names: &[&'static str], crossfade_ms: u32) -> AnimationStateMachine<TestRenderer> {
+        let mut anims = AnimationDictionary::new();
+        for &name in names {
+            anims.insert(name, Box::new(move || animation(name)));
+        }
+        AnimationStateMachine::standard(&mut anims, crossfade_ms, 100.0)
+    }
+
+    fn renderer() -> TestRenderer {
+        TestRenderer {
+            effects: SpriteEffects::new(),
+            showing: "",
+            crossfade: None,
+        }
+    }
+
+    fn walking(facing: Facing) -> AnimationParameters {
+        AnimationParameters {
+            speed: 100.0,
+            facing,
+            grounded: true,
+            grip: None,
+            jumping: false,
+        }
+    }
+
+    fn standing(facing: Facing) -> AnimationParameters {
+        AnimationParameters {
+            speed: 0.0,
+            ..walking(facing)
+        }
+    }
+
+    fn jumping(facing: Facing) -> AnimationParameters {
+        AnimationParameters {
+            grounded: false,
+            jumping: true,
+            ..walking(facing)
+        }
+    }
+
+    #[test]
+    fn starts_in_the_state_the_parameters_match() {
+        let mut machine = standard(&["walk_left", "walk_right", "idle_left", "idle_right"], 100);
+        let mut renderer = renderer();
+
+        let events = machine.advance(FRAME_MS, &mut renderer, &standing(Facing::Left));
+        assert_eq!(renderer.showing, "idle_left");
+        assert_eq!(events, ["idle_left"]);
+        // There's nothing to fade out yet
+        assert_eq!(renderer.crossfade, None);
+    }
+
+    #[test]
+    fn falls_back_on_walking_without_other_animations() {
+        let mut machine = standard(&["walk_left", "walk_right"], 0);
+        let mut renderer = renderer();
+
+        machine.advance(FRAME_MS, &mut renderer, &standing(Facing::Right));
+        assert_eq!(renderer.showing, "walk_right");
+        machine.advance(FRAME_MS, &mut renderer, &jumping(Facing::Left));
+        assert_eq!(renderer.showing, "walk_left");
+    }
+
+    #[test]
+    fn turns_around_through_the_turn_animation() {
+        let names = ["walk_left", "walk_right", "turn_left", "turn_right"];
+        let mut machine = standard(&names, 0);
+        let mut renderer = renderer();
+        machine.advance(FRAME_MS, &mut renderer, &walking(Facing::Right));
+
+        let events = machine.advance(FRAME_MS, &mut renderer, &walking(Facing::Left));
+        assert_eq!(renderer.showing, "turn_left");
+        assert_eq!(events, ["turn_left"]);
+
+        // The turn plays through to the end before walking the new way
+        let mut events = Vec::new();
+        while renderer.showing == "turn_left" {
+            events = machine.advance(FRAME_MS, &mut renderer, &walking(Facing::Left));
+        }
+        assert_eq!(renderer.showing, "walk_left");
+        assert_eq!(events, ["walk_left"]);
+    }
+
+    #[test]
+    fn finishes_turning_before_changing_its_mind() {
+        let names = ["walk_left", "walk_right", "turn_left", "turn_right"];
+        let mut machine = standard(&names, 0);
+        let mut renderer = renderer();
+        machine.advance(FRAME_MS, &mut renderer, &walking(Facing::Right));
+        machine.advance(FRAME_MS, &mut renderer, &walking(Facing::Left));
+
+        // Turning back midway still finishes the turn we started, and walks
+        // where it was headed, before turning around again
+        let mut shown = vec![renderer.showing];
+        for _ in 0..20 {
+            machine.advance(FRAME_MS, &mut renderer, &walking(Facing::Right));
+            if shown.last() != Some(&renderer.showing) {
+                shown.push(renderer.showing);
+            }
+        }
+        assert_eq!(
+            shown,
+            ["turn_left", "walk_left", "turn_right", "walk_right"]
+        );
+    }
+
+    #[test]
+    fn jumps_and_lands_through_their_animations() {
+        let names = [
+            "walk_left",
+            "walk_right",
+            "jump_start_right",
+            "jump_start_left",
+            "airborne_left",
+            "airborne_right",
+            "land_left",
+            "land_right",
+        ];
+        let mut machine = standard(&names, 0);
+        let mut renderer = renderer();
+        machine.advance(FRAME_MS, &mut renderer, &walking(Facing::Right));
+
+        let mut shown = vec![renderer.showing];
+        for parameters in [jumping(Facing::Right), walking(Facing::Right)] {
+            for _ in 0..20 {
+                machine.advance(FRAME_MS, &mut renderer, &parameters);
+                if shown.last() != Some(&renderer.showing) {
+                    shown.push(renderer.showing);
+                }
+            }
+        }
+        assert_eq!(
+            shown,
+            [
+                "walk_right",
+                "jump_start_right",
+                "airborne_right",
+                "land_right",
+                "walk_right"
+            ]
+        );
+    }
+
+    #[test]
+    fn loops_start_over_when_they_finish() {
+        let mut machine = AnimationStateMachine::new();
+        machine.add_state("walk_right", animation("walk_right"), Playback::Loop);
+        machine.add_transition(
+            None,
+            "walk_right",
+            Box::new(|_| true),
+            TransitionStyle::Immediate,
+        );
+        let mut renderer = renderer();
+
+        let mut events = Vec::new();
+        for _ in 0..10 {
+            events.extend(machine.advance(FRAME_MS, &mut renderer, &walking(Facing::Right)));
+        }
+        assert_eq!(renderer.showing, "walk_right");
+        assert_eq!(events, ["walk_right", "walk_right"]);
+    }
+
+    #[test]
+    fn once_holds_on_the_end_when_it_finishes() {
+        let mut machine = AnimationStateMachine::new();
+        machine.add_state("land_right", animation("land_right"), Playback::Once);
+        machine.add_transition(
+            None,
+            "land_right",
+            Box::new(|_| true),
+            TransitionStyle::Immediate,
+        );
+        let mut renderer = renderer();
+
+        let mut events = Vec::new();
+        for _ in 0..20 {
+            events.extend(machine.advance(FRAME_MS, &mut renderer, &walking(Facing::Right)));
+        }
+        assert_eq!(renderer.showing, "land_right");
+        assert_eq!(events, ["land_right"]);
+    }
+
+    #[test]
+    fn crossfades_into_new_states() {
+        let mut machine = standard(&["walk_left", "walk_right", "idle_left", "idle_right"], 100);
+        let mut renderer = renderer();
+        machine.advance(FRAME_MS, &mut renderer, &walking(Facing::Right));
+
+        machine.advance(FRAME_MS, &mut renderer, &standing(Facing::Right));
+        assert_eq!(renderer.showing, "idle_right");
+        assert_eq!(renderer.crossfade, Some(0.16));
+
+        machine.advance(FRAME_MS, &mut renderer, &standing(Facing::Right));
+        assert_eq!(renderer.crossfade, Some(0.32));
+
+        // Then it's over, and the previous frame is let go of
+        for _ in 0..5 {
+            machine.advance(FRAME_MS, &mut renderer, &standing(Facing::Right));
+        }
+        assert_eq!(renderer.crossfade, None);
+        assert!(machine.crossfade.is_none());
+    }
+
+    #[test]
+    fn changing_again_midway_starts_a_new_crossfade() {
+        let mut machine = standard(&["walk_left", "walk_right", "idle_left", "idle_right"], 100);
+        let mut renderer = renderer();
+        machine.advance(FRAME_MS, &mut renderer, &walking(Facing::Right));
+        machine.advance(FRAME_MS, &mut renderer, &standing(Facing::Right));
+        machine.advance(FRAME_MS, &mut renderer, &standing(Facing::Right));
+
+        machine.advance(FRAME_MS, &mut renderer, &walking(Facing::Right));
+        assert_eq!(renderer.showing, "walk_right");
+        assert_eq!(renderer.crossfade, Some(0.16));
+    }
+}
@@ -0,0 +1,79 @@
+// How far into a clip we are, for animations that play continuously over a
+// length of time rather than frame by frame, like skeletal and 3D ones.
+pub struct ClipPlayhead {
+    // In milliseconds. Not set until we've started.
+    time_ms: Option<f32>,
+    playback_rate: f32,
+}
+
+impl ClipPlayhead {
+    pub fn new() -> Self {
+        Self {
+            time_ms: None,
+            playback_rate: 1.0,
+        }
+    }
+
+    // Moves along, stopping at the end of a clip `duration_ms` long. Returns
+    // where we were before, if we'd started, and where we are now.
+    pub fn advance(&mut self, delta_t_ms: u32, duration_ms: f32) -> (Option<f32>, f32) {
+        // Like with frames, starting the clip doesn't use up any time
+        let previous_ms = self.time_ms;
+        let time_ms = match previous_ms {
+            Some(time_ms) => time_ms + delta_t_ms as f32 * self.playback_rate,
+            None => 0.0,
+        };
+        let time_ms = time_ms.min(duration_ms);
+        self.time_ms = Some(time_ms);
+
+        (previous_ms, time_ms)
+    }
+
+    pub fn is_finished(&self, duration_ms: f32) -> bool {
+        self.time_ms.is_some_and(|time_ms| time_ms >= duration_ms)
+    }
+
+    pub fn reset(&mut self) {
+        self.time_ms = None;
+    }
+
+    pub fn set_playback_rate(&mut self, rate: f32) {
+        self.playback_rate = rate.max(0.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn starting_takes_no_time() {
+        let mut playhead = ClipPlayhead::new();
+        assert_eq!(playhead.advance(100, 1000.0), (None, 0.0));
+        assert_eq!(playhead.advance(100, 1000.0), (Some(0.0), 100.0));
+    }
+
+    #[test]
+    fn stops_at_the_end() {
+        let mut playhead = ClipPlayhead::new();
+        playhead.advance(0, 250.0);
+        assert!(!playhead.is_finished(250.0));
+        assert_eq!(playhead.advance(400, 250.0), (Some(0.0), 250.0));
+        assert!(playhead.is_finished(250.0));
+
+        playhead.reset();
+        assert!(!playhead.is_finished(250.0));
+    }
+
+    #[test]
+    fn plays_at_the_playback_rate() {
+        let mut playhead = ClipPlayhead::new();
+        playhead.set_playback_rate(0.5);
+        playhead.advance(0, 1000.0);
+        assert_eq!(playhead.advance(100, 1000.0).1, 50.0);
+
+        // Never backwards
+        playhead.set_playback_rate(-1.0);
+        assert_eq!(playhead.advance(100, 1000.0).1, 50.0);
+    }
+}
@@ -0,0 +1,45 @@
+use std::f32::consts::PI;
+
+// Shapes how a value moves from start to end over time. Each takes how far
+// through we are, from 0.0 to 1.0, and gives how far along the value should
+// be, which starts at 0.0 and ends at 1.0.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Easing {
+    Linear,
+    // Slow at both ends
+    EaseInOut,
+    // Overshoots the end a little before settling back, for some bounce
+    EaseOutBack,
+    // Shoots past the end and wobbles either side of it before settling, like
+    // something on a spring
+    Spring,
+    // Goes out to 1.0 halfway through and back to 0.0 by the end, for
+    // something that happens and then wears off
+    Pulse,
+}
+
+// How far EaseOutBack overshoots. This value overshoots by about 10%.
+const BACK_OVERSHOOT: f32 = 1.70158;
+// How quickly springs settle down, and how many times they wobble
+const SPRING_DAMPING: f32 = 6.0;
+const SPRING_WOBBLES: f32 = 1.5;
+
+impl Easing {
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+            Easing::EaseOutBack => {
+                let t = t - 1.0;
+                1.0 + (BACK_OVERSHOOT + 1.0) * t * t * t + BACK_OVERSHOOT * t * t
+            }
+            // Snap to the end, since the wobbling never quite dies out
+            Easing::Spring if t >= 1.0 => 1.0,
+            Easing::Spring => {
+                1.0 - (-SPRING_DAMPING * t).exp() * (t * SPRING_WOBBLES * 2.0 * PI).cos()
+            }
+            Easing::Pulse => (t * PI).sin(),
+        }
+    }
+}
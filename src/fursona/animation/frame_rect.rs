@@ -0,0 +1,9 @@
+// A rect within a single frame of an animation, measured in the frame's own
+// pixels from its top-left corner, before any scaling.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FrameRect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
@@ -0,0 +1,167 @@
+use super::{AnimationParameters, Easing, ProceduralMotionStyle, Tween};
+use crate::fursona::behaviors::Facing;
+use crate::rendering::Transform2D;
+use std::f32::consts::PI;
+
+// A walk cycle is a step with each foot, and we bob once per step
+const STEPS_PER_WALK_CYCLE: f32 = 2.0;
+// How far the fursona travels for each bob, in screen pixels, when there's no
+// walk cycle to go by
+const DEFAULT_STRIDE_PIXELS: f32 = 48.0;
+const BREATH_PERIOD_MS: f32 = 3000.0;
+const LANDING_MS: u32 = 350;
+const TURN_MS: u32 = 300;
+// How long it takes to ease into or out of walking, breathing or falling
+const BLEND_MS: f32 = 200.0;
+
+// Moves closer to the target, getting all the way there over `BLEND_MS`
+fn approach(weight: f32, target: f32, delta_t_ms: u32) -> f32 {
+    let step = delta_t_ms as f32 / BLEND_MS;
+    if weight < target {
+        (weight + step).min(target)
+    } else {
+        (weight - step).max(target)
+    }
+}
+
+// Livens up animations with motion worked out from what the fursona is
+// doing, rather than drawn into the art: bobbing while walking, squashing
+// on landing and stretching while falling, leaning into turns and breathing
+// while standing still. Works with any kind of animation, since it all comes
+// down to a transform around the anchor.
+pub struct ProceduralMotion {
+    style: ProceduralMotionStyle,
+    // How far the fursona travels for each bob, in screen pixels
+    stride_pixels: f32,
+    // How much of each effect is in play, from 0.0 to 1.0, so that they ease
+    // in and out rather than snapping
+    walk_weight: f32,
+    idle_weight: f32,
+    air_weight: f32,
+    // How far through a bob and a breath we are, from 0.0 to 1.0
+    bob_phase: f32,
+    breath_phase: f32,
+    landing: Option<Tween>,
+    // Along with which way to lean
+    turn: Option<(Tween, f32)>,
+    last: Option<AnimationParameters>,
+}
+
+impl ProceduralMotion {
+    // `walk_cycle_ms` is how long the walk animation takes to play through
+    // when walking at `walk_cycle_speed`, so that bobs keep in step with it
+    pub fn new(
+        style: ProceduralMotionStyle,
+        walk_cycle_ms: Option<f32>,
+        walk_cycle_speed: f32,
+    ) -> Self {
+        let stride_pixels = walk_cycle_ms
+            .map(|ms| walk_cycle_speed * ms / 1000.0 / STEPS_PER_WALK_CYCLE)
+            .filter(|&stride| stride > 0.0)
+            .unwrap_or(DEFAULT_STRIDE_PIXELS);
+
+        Self {
+            style,
+            stride_pixels,
+            walk_weight: 0.0,
+            idle_weight: 0.0,
+            air_weight: 0.0,
+            bob_phase: 0.0,
+            breath_phase: 0.0,
+            landing: None,
+            turn: None,
+            last: None,
+        }
+    }
+
+    pub fn advance(&mut self, delta_t_ms: u32, parameters: &AnimationParameters) {
+        let walking = parameters.grounded && parameters.speed > 0.0;
+        let idle = parameters.grounded && parameters.speed == 0.0;
+        // Climbing and hanging don't count as being in the air
+        let airborne =
+            |parameters: &AnimationParameters| !parameters.grounded && parameters.grip.is_none();
+        let targets = [walking, idle, airborne(parameters)].map(|on| if on { 1.0 } else { 0.0 });
+        self.walk_weight = approach(self.walk_weight, targets[0], delta_t_ms);
+        self.idle_weight = approach(self.idle_weight, targets[1], delta_t_ms);
+        self.air_weight = approach(self.air_weight, targets[2], delta_t_ms);
+
+        let seconds = delta_t_ms as f32 / 1000.0;
+        self.bob_phase = (self.bob_phase + parameters.speed * seconds / self.stride_pixels).fract();
+        self.breath_phase = (self.breath_phase + delta_t_ms as f32 / BREATH_PERIOD_MS).fract();
+
+        if let Some(last) = &self.last {
+            if airborne(last) && parameters.grounded {
+                self.landing = Some(Tween::new(LANDING_MS, Easing::EaseOutBack));
+            }
+            if last.facing != parameters.facing {
+                let lean = match parameters.facing {
+                    Facing::Left => -1.0,
+                    Facing::Right => 1.0,
+                };
+                self.turn = Some((Tween::new(TURN_MS, Easing::Pulse), lean));
+            }
+        }
+
+        if let Some(landing) = &mut self.landing {
+            landing.advance(delta_t_ms);
+            if landing.is_finished() {
+                self.landing = None;
+            }
+        }
+        if let Some((turn, _)) = &mut self.turn {
+            turn.advance(delta_t_ms);
+            if turn.is_finished() {
+                self.turn = None;
+            }
+        }
+
+        self.last = Some(parameters.clone());
+    }
+
+    // In the frame's pixels, moving the frame around its anchor
+    pub fn transform(&self, anchor: (f32, f32)) -> Transform2D {
+        let style = &self.style;
+        let ease = |weight: f32| Easing::EaseInOut.apply(weight);
+
+        // Up at the middle of each step, and back down as the foot lands
+        let bob = -style.bob * ease(self.walk_weight) * (self.bob_phase * PI).sin();
+        let breath = style.breathe
+            * ease(self.idle_weight)
+            * (0.5 - 0.5 * (self.breath_phase * 2.0 * PI).cos());
+        // Squashed as we land, springing back past normal before settling,
+        // and stretched out while in the air
+        let landing = self
+            .landing
+            .as_ref()
+            .map_or(0.0, |landing| 1.0 - landing.value());
+        let squash = style.squash * (landing - ease(self.air_weight));
+        let tilt = self
+            .turn
+            .as_ref()
+            .map_or(0.0, |(turn, lean)| style.tilt * lean * turn.value());
+
+        let (anchor_x, anchor_y) = anchor;
+        Transform2D::translation(anchor_x, anchor_y + bob)
+            * Transform2D::rotation(tilt)
+            * Transform2D::scale(1.0 + squash, (1.0 + breath) * (1.0 - squash))
+            * Transform2D::translation(-anchor_x, -anchor_y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bobs_in_step_with_the_walk_cycle() {
+        // Two steps a second at 240 pixels a second
+        let motion = ProceduralMotion::new(ProceduralMotionStyle::default(), Some(1000.0), 240.0);
+        assert_eq!(motion.stride_pixels, 120.0);
+
+        for walk_cycle_ms in [None, Some(0.0)] {
+            let motion =
+                ProceduralMotion::new(ProceduralMotionStyle::default(), walk_cycle_ms, 240.0);
+            assert_eq!(motion.stride_pixels, DEFAULT_STRIDE_PIXELS);
+        }
+    }
+}
@@ -0,0 +1,15 @@
+// How strongly a fursona moves on top of its animations. Everything at zero
+// leaves the animations as they are.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ProceduralMotionStyle {
+    // How far to bob up with each step while walking, in the art's pixels
+    pub bob: f32,
+    // How much to squash on landing and stretch while falling, as a fraction
+    // of the fursona's height
+    pub squash: f32,
+    // How far to lean into turns, in degrees
+    pub tilt: f32,
+    // How much to grow and shrink with each breath while standing still, as
+    // a fraction of the fursona's height
+    pub breathe: f32,
+}
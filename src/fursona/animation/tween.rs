@@ -0,0 +1,37 @@
+use super::Easing;
+
+// Something that plays out over a set amount of time, following an easing
+// curve.
+#[derive(Clone, Debug)]
+pub struct Tween {
+    elapsed_ms: u32,
+    duration_ms: u32,
+    easing: Easing,
+}
+
+impl Tween {
+    pub fn new(duration_ms: u32, easing: Easing) -> Self {
+        Self {
+            elapsed_ms: 0,
+            duration_ms,
+            easing,
+        }
+    }
+
+    pub fn advance(&mut self, delta_t_ms: u32) {
+        self.elapsed_ms = (self.elapsed_ms + delta_t_ms).min(self.duration_ms);
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.elapsed_ms >= self.duration_ms
+    }
+
+    // How far along the eased value is
+    pub fn value(&self) -> f32 {
+        if self.duration_ms == 0 {
+            return self.easing.apply(1.0);
+        }
+        self.easing
+            .apply(self.elapsed_ms as f32 / self.duration_ms as f32)
+    }
+}
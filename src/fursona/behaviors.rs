@@ -1,44 +1,119 @@
+pub mod climb;
+pub mod jump;
+mod motion;
 pub mod pace;
+pub mod platform;
 
-use super::{animation::AnimationConstructor, rendering::FursonaRenderer};
-use crate::stage::{Stage, ViewportPoint, ViewportRect};
+use super::{
+    animation::{AnimationDictionary, AnimationParameters},
+    rendering::FursonaRenderer,
+};
+use crate::rendering::SpriteEffects;
+use crate::stage::{ViewportPoint, WalkableSpan};
+use crate::surfaces::Surface;
 
-type AnimDictionaryEntry<T> = Box<AnimationConstructor<T>>;
+// How fast fursona fall whenever they leave the ground
+const GRAVITY_PIXELS_PER_SECOND_SQUARED: f32 = 2400.0;
 
-pub struct AnimationDictionary<T>
-where
-    T: FursonaRenderer,
-{
-    pub walk_right: Option<AnimDictionaryEntry<T>>,
-    pub walk_left: Option<AnimDictionaryEntry<T>>,
+// Which behavior a fursona should use
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BehaviorKind {
+    Pace,
+    Platform,
+    Climb,
+    Jump,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Facing {
+    Left,
+    Right,
+}
+
+// What the fursona is holding on to, while off the ground. Which wall it's
+// on is whichever one it's facing.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Grip {
+    Wall,
+    Ceiling,
 }
 
 pub struct BehaviorContext<'a> {
+    // Where the anchor of the current frame is, which is usually its feet
     pub position: ViewportPoint,
-    pub stage: &'a Stage,
+    // The size of the frame currently being shown, already scaled
+    pub dimensions: (f32, f32),
+    // Where the anchor is within that frame, from its top-left corner and
+    // already scaled
+    pub anchor: (f32, f32),
+    // Where on screen the fursona is currently free to walk
+    pub span: WalkableSpan,
+    // Other application windows, from the bottom of the stack to the top
+    pub surfaces: &'a [Surface],
+    // The events of every animation frame reached since the last advance,
+    // such as "footstep"
+    pub events: &'a [String],
+    // How the fursona is usually drawn, as its settings have it
+    pub effects: &'a SpriteEffects,
+}
+
+impl BehaviorContext<'_> {
+    // How far below the anchor the bottom of the frame is
+    fn below_anchor(&self) -> f32 {
+        self.dimensions.1 - self.anchor.1
+    }
+
+    // How far left and right the anchor can go while keeping the whole frame
+    // on screen
+    fn x_range(&self) -> (f32, f32) {
+        let (width, _) = self.dimensions;
+        let (anchor_x, _) = self.anchor;
+        (
+            self.span.left() + anchor_x,
+            self.span.right() - (width - anchor_x),
+        )
+    }
+}
+
+// Falls for `delta_t` seconds, with positive velocities heading down as with
+// viewport coordinates. Returns the new velocity, and where the feet are now.
+fn fall_step(velocity_y: f32, feet_y: f32, delta_t: f32) -> (f32, f32) {
+    let velocity_y = velocity_y + GRAVITY_PIXELS_PER_SECOND_SQUARED * delta_t;
+    (velocity_y, feet_y + velocity_y * delta_t)
 }
 
 pub struct BehaviorResult {
-    pub bounding_box: ViewportRect,
+    // Where the anchor should be next
+    pub position: ViewportPoint,
+    // Picks which animation is shown
+    pub parameters: AnimationParameters,
+    // Replaces the fursona's usual effects until the next advance, when set
+    pub effects: Option<SpriteEffects>,
 }
 
-pub trait Behavior<T: FursonaRenderer> {
-    fn new(anims: &mut AnimationDictionary<T>) -> Option<Self>
+// Decides where a fursona goes. Behaviors don't play animations themselves;
+// they describe what the fursona is doing through the result's parameters,
+// and the animation state machine takes it from there.
+pub trait Behavior {
+    // `speed` is how fast the fursona walks, in pixels per second
+    fn new<T: FursonaRenderer>(anims: &AnimationDictionary<T>, speed: f32) -> Option<Self>
     where
         Self: Sized;
 
-    fn advance(
-        &mut self,
-        delta_t_ms: u32,
-        renderer: &mut T,
-        context: BehaviorContext,
-    ) -> BehaviorResult;
+    // A stable identifier for this behavior, used when saving and restoring
+    // state across restarts.
+    fn name(&self) -> &'static str;
+
+    fn facing(&self) -> Facing;
+    fn set_facing(&mut self, facing: Facing);
+
+    fn advance(&mut self, delta_t_ms: u32, context: BehaviorContext) -> BehaviorResult;
 }
 
-pub trait BehaviorPreview<T: FursonaRenderer> {
+pub trait BehaviorPreview {
     // Given a set of the animations available, determines whether this
     // behavior is possible to run or not.
     // This isn't REQUIRED to run, but this will be a predictor of whether
     // `Behavior::new` will return None or Some.
-    fn is_possible(anims: &AnimationDictionary<T>) -> bool;
+    fn is_possible<T: FursonaRenderer>(anims: &AnimationDictionary<T>) -> bool;
 }
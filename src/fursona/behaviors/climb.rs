@@ -0,0 +1,317 @@
+use super::{
+    super::{
+        animation::{AnimationDictionary, AnimationParameters},
+        rendering::FursonaRenderer,
+    },
+    fall_step, Behavior, BehaviorContext, BehaviorPreview, BehaviorResult, Facing, Grip,
+};
+use crate::{rendering::SpriteEffects, stage::ViewportPoint};
+use tracing::debug;
+
+// How fast we climb and move along the top, compared with walking
+const CLIMB_SPEED_RATIO: f32 = 0.5;
+// How much of the screen's width to hang along the top for before dropping
+const HANG_FRACTION: f32 = 0.4;
+
+enum Phase {
+    // Along the bottom of the screen
+    Walking,
+    // Up whichever edge of the screen we're facing
+    Climbing,
+    // Along the top of the screen, with how much further to go before
+    // letting go
+    Hanging { remaining: f32 },
+    // Positive velocities are heading down, as with viewport coordinates
+    Falling { velocity_y: f32 },
+}
+
+// Walks along the bottom of the screen until it reaches an edge, then climbs
+// up it, hangs along the top for a while and drops back down.
+pub struct ClimbBehavior {
+    direction: Facing,
+    // How fast we walk, in pixels per second
+    speed: f32,
+    phase: Phase,
+    // Without hang animations, we let go at the top of the edge instead
+    can_hang: bool,
+}
+
+impl BehaviorPreview for ClimbBehavior {
+    fn is_possible<T: FursonaRenderer>(anims: &AnimationDictionary<T>) -> bool {
+        anims.has("walk_left")
+            && anims.has("walk_right")
+            && anims.has("climb_left")
+            && anims.has("climb_right")
+    }
+}
+
+impl Behavior for ClimbBehavior {
+    fn new<T: FursonaRenderer>(anims: &AnimationDictionary<T>, speed: f32) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        if !Self::is_possible(anims) {
+            return None;
+        }
+
+        Some(Self {
+            direction: Facing::Right,
+            speed,
+            // Wherever we start out, we'll drop down to the bottom first
+            phase: Phase::Falling { velocity_y: 0.0 },
+            can_hang: anims.has("hang_left") && anims.has("hang_right"),
+        })
+    }
+
+    fn name(&self) -> &'static str {
+        "climb"
+    }
+
+    fn facing(&self) -> Facing {
+        self.direction
+    }
+
+    fn set_facing(&mut self, facing: Facing) {
+        self.direction = facing;
+    }
+
+    fn advance(&mut self, delta_t_ms: u32, context: BehaviorContext) -> BehaviorResult {
+        let delta_t = delta_t_ms as f32 / 1000.0;
+        let (_, anchor_y) = context.anchor;
+        let below_anchor = context.below_anchor();
+        let (left, right) = context.x_range();
+        let x = context.position.x.clamp(left, right);
+        let feet_y = context.position.y + below_anchor;
+        let floor_y = context.span.bottom_at(x);
+
+        let walk_dist = delta_t * self.speed;
+        let climb_dist = walk_dist * CLIMB_SPEED_RATIO;
+        let step = |x: f32, dist: f32, direction: Facing| match direction {
+            Facing::Left => (x - dist).clamp(left, right),
+            Facing::Right => (x + dist).clamp(left, right),
+        };
+        let is_at_edge = |x: f32, direction: Facing| match direction {
+            Facing::Left => x <= left,
+            Facing::Right => x >= right,
+        };
+
+        let (x, y) = match &mut self.phase {
+            Phase::Walking => {
+                let x = step(x, walk_dist, self.direction);
+                if is_at_edge(x, self.direction) {
+                    debug!("climbing the {:?} edge", self.direction);
+                    self.phase = Phase::Climbing;
+                }
+
+                (x, floor_y - below_anchor)
+            }
+            Phase::Climbing => {
+                // Until the top of the frame reaches the top of the screen
+                let top_y = context.span.top_at(x) + anchor_y;
+                let y = (context.position.y - climb_dist).max(top_y);
+                if y <= top_y {
+                    // Then head back the way we came, along the top or down
+                    // to the bottom
+                    self.direction = match self.direction {
+                        Facing::Left => Facing::Right,
+                        Facing::Right => Facing::Left,
+                    };
+                    self.phase = match self.can_hang {
+                        true => Phase::Hanging {
+                            remaining: (context.span.right() - context.span.left()) * HANG_FRACTION,
+                        },
+                        false => Phase::Falling { velocity_y: 0.0 },
+                    };
+                }
+
+                (x, y)
+            }
+            Phase::Hanging { remaining } => {
+                let top = context.span.top_at(x);
+                let next_x = step(x, climb_dist, self.direction);
+                *remaining -= climb_dist;
+
+                // Let go once we've gone far enough, or when we run out of
+                // ceiling, whether at the other edge or where a shorter
+                // monitor starts
+                if *remaining <= 0.0
+                    || is_at_edge(next_x, self.direction)
+                    || context.span.top_at(next_x) != top
+                {
+                    self.phase = Phase::Falling { velocity_y: 0.0 };
+                }
+
+                (next_x, top + anchor_y)
+            }
+            Phase::Falling { velocity_y } => {
+                let (next_velocity_y, next_feet_y) = fall_step(*velocity_y, feet_y, delta_t);
+                *velocity_y = next_velocity_y;
+
+                if next_feet_y >= floor_y {
+                    self.phase = Phase::Walking;
+                    (x, floor_y - below_anchor)
+                } else {
+                    (x, next_feet_y - below_anchor)
+                }
+            }
+        };
+
+        let grip = match self.phase {
+            Phase::Climbing => Some(Grip::Wall),
+            Phase::Hanging { .. } => Some(Grip::Ceiling),
+            Phase::Walking | Phase::Falling { .. } => None,
+        };
+
+        BehaviorResult {
+            position: ViewportPoint { x, y },
+            parameters: AnimationParameters {
+                speed: match grip {
+                    Some(_) => self.speed * CLIMB_SPEED_RATIO,
+                    None => self.speed,
+                },
+                facing: self.direction,
+                grounded: matches!(self.phase, Phase::Walking),
+                grip,
+                jumping: false,
+            },
+            // Shadows are cast on the ground, not the walls or ceiling
+            effects: grip.map(|_| SpriteEffects {
+                shadow: None,
+                ..context.effects.clone()
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stage::{MonitorArea, ViewportRect, WalkableSpan};
+
+    const FRAME_MS: u32 = 16;
+    // A 1280x720 screen, and a 40x60 frame anchored at its feet
+    const FLOOR_Y: f32 = 720.0;
+    const DIMENSIONS: (f32, f32) = (40.0, 60.0);
+    const ANCHOR: (f32, f32) = (20.0, 60.0);
+    // As far right as the anchor goes
+    const RIGHT_EDGE: f32 = 1260.0;
+    // Where the anchor is with the top of the frame at the top of the screen
+    const TOP_Y: f32 = 60.0;
+
+    fn behavior(phase: Phase, can_hang: bool) -> ClimbBehavior {
+        ClimbBehavior {
+            direction: Facing::Right,
+            speed: 100.0,
+            phase,
+            can_hang,
+        }
+    }
+
+    fn advance(behavior: &mut ClimbBehavior, position: ViewportPoint) -> BehaviorResult {
+        let span = WalkableSpan::new(vec![MonitorArea {
+            name: None,
+            rect: ViewportRect {
+                x: 0.0,
+                y: 0.0,
+                width: 1280.0,
+                height: FLOOR_Y,
+            },
+        }]);
+
+        behavior.advance(
+            FRAME_MS,
+            BehaviorContext {
+                position,
+                dimensions: DIMENSIONS,
+                anchor: ANCHOR,
+                span,
+                surfaces: &[],
+                events: &[],
+                effects: &SpriteEffects::new(),
+            },
+        )
+    }
+
+    // Keeps going until the phase changes, or we've given up
+    fn advance_until_next_phase(
+        behavior: &mut ClimbBehavior,
+        mut position: ViewportPoint,
+    ) -> BehaviorResult {
+        let phase = std::mem::discriminant(&behavior.phase);
+        loop {
+            let result = advance(behavior, position);
+            if std::mem::discriminant(&behavior.phase) != phase {
+                return result;
+            }
+            position = result.position;
+        }
+    }
+
+    #[test]
+    fn climbs_the_edge_it_walks_into() {
+        let mut behavior = behavior(Phase::Walking, true);
+
+        let result = advance(
+            &mut behavior,
+            ViewportPoint {
+                x: RIGHT_EDGE - 1.0,
+                y: FLOOR_Y,
+            },
+        );
+        assert!(matches!(behavior.phase, Phase::Climbing));
+        assert_eq!(result.position.x, RIGHT_EDGE);
+
+        // Gripping the wall on the way up, without a shadow on it
+        let result = advance(&mut behavior, result.position);
+        assert!(result.position.y < FLOOR_Y);
+        assert!(matches!(result.parameters.grip, Some(Grip::Wall)));
+        assert!(result
+            .effects
+            .is_some_and(|effects| effects.shadow.is_none()));
+    }
+
+    #[test]
+    fn climbs_to_the_top_then_hangs_back_the_way_it_came() {
+        let mut behavior = behavior(Phase::Climbing, true);
+
+        let result = advance_until_next_phase(
+            &mut behavior,
+            ViewportPoint {
+                x: RIGHT_EDGE,
+                y: FLOOR_Y,
+            },
+        );
+        assert_eq!(result.position.y, TOP_Y);
+        assert!(matches!(behavior.phase, Phase::Hanging { .. }));
+        assert_eq!(behavior.facing(), Facing::Left);
+
+        // Along the ceiling, until it's gone far enough to let go
+        let result = advance_until_next_phase(&mut behavior, result.position);
+        assert_eq!(result.position.y, TOP_Y);
+        assert!((RIGHT_EDGE - result.position.x - 1280.0 * HANG_FRACTION).abs() < 1.0);
+        assert!(matches!(behavior.phase, Phase::Falling { .. }));
+    }
+
+    #[test]
+    fn lets_go_at_the_top_and_falls_to_the_bottom_without_hang_animations() {
+        let mut behavior = behavior(Phase::Climbing, false);
+
+        let result = advance_until_next_phase(
+            &mut behavior,
+            ViewportPoint {
+                x: RIGHT_EDGE,
+                y: FLOOR_Y,
+            },
+        );
+        assert_eq!(result.position.y, TOP_Y);
+        assert!(matches!(behavior.phase, Phase::Falling { .. }));
+
+        let result = advance_until_next_phase(&mut behavior, result.position);
+        assert_eq!(
+            (result.position.x, result.position.y),
+            (RIGHT_EDGE, FLOOR_Y)
+        );
+        assert!(matches!(behavior.phase, Phase::Walking));
+        assert!(result.parameters.grounded);
+    }
+}
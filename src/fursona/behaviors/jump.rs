@@ -0,0 +1,293 @@
+use super::{
+    super::{
+        animation::{AnimationDictionary, AnimationParameters},
+        rendering::FursonaRenderer,
+    },
+    fall_step,
+    motion::{jump_duration_ms, Motion},
+    Behavior, BehaviorContext, BehaviorPreview, BehaviorResult, Facing,
+};
+use crate::stage::ViewportPoint;
+use tracing::debug;
+
+// How far ahead each leap lands, and how far above the higher end it peaks.
+// How long it takes is up to gravity.
+const LEAP_DISTANCE: f32 = 200.0;
+const LEAP_HEIGHT: f32 = 96.0;
+// How long to walk between leaps
+const LEAP_INTERVAL_MS: u32 = 3000;
+
+// Walks along the bottom of the screen, leaping forward every so often and
+// turning around at the edges.
+pub struct JumpBehavior {
+    direction: Facing,
+    // How fast we walk, in pixels per second
+    speed: f32,
+    // How long until the next leap
+    until_leap_ms: u32,
+    leap: Option<Motion>,
+    // Set while dropping to the ground rather than leaping. Positive
+    // velocities are heading down, as with viewport coordinates.
+    fall_velocity_y: Option<f32>,
+}
+
+impl BehaviorPreview for JumpBehavior {
+    fn is_possible<T: FursonaRenderer>(anims: &AnimationDictionary<T>) -> bool {
+        anims.has("walk_left")
+            && anims.has("walk_right")
+            && anims.has("airborne_left")
+            && anims.has("airborne_right")
+    }
+}
+
+impl JumpBehavior {
+    fn result(&self, x: f32, y: f32) -> BehaviorResult {
+        BehaviorResult {
+            position: ViewportPoint { x, y },
+            parameters: AnimationParameters {
+                speed: self.speed,
+                facing: self.direction,
+                grounded: self.leap.is_none() && self.fall_velocity_y.is_none(),
+                grip: None,
+                jumping: self.leap.is_some(),
+            },
+            effects: None,
+        }
+    }
+}
+
+impl Behavior for JumpBehavior {
+    fn new<T: FursonaRenderer>(anims: &AnimationDictionary<T>, speed: f32) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        if !Self::is_possible(anims) {
+            return None;
+        }
+
+        Some(Self {
+            direction: Facing::Right,
+            speed,
+            until_leap_ms: LEAP_INTERVAL_MS,
+            leap: None,
+            // Wherever we start out, we'll drop down to the bottom first
+            fall_velocity_y: Some(0.0),
+        })
+    }
+
+    fn name(&self) -> &'static str {
+        "jump"
+    }
+
+    fn facing(&self) -> Facing {
+        self.direction
+    }
+
+    fn set_facing(&mut self, facing: Facing) {
+        self.direction = facing;
+    }
+
+    fn advance(&mut self, delta_t_ms: u32, context: BehaviorContext) -> BehaviorResult {
+        let delta_t = delta_t_ms as f32 / 1000.0;
+        let below_anchor = context.below_anchor();
+        let (left, right) = context.x_range();
+        let feet_y = context.position.y + below_anchor;
+
+        // Leaps take us along their own path until they're over
+        if let Some(leap) = &mut self.leap {
+            let feet = leap.advance(delta_t_ms);
+            if leap.is_finished() {
+                self.leap = None;
+                self.until_leap_ms = LEAP_INTERVAL_MS;
+            }
+
+            return self.result(feet.x.clamp(left, right), feet.y - below_anchor);
+        }
+
+        let x = context.position.x.clamp(left, right);
+        let floor_y = context.span.bottom_at(x);
+
+        if let Some(velocity_y) = self.fall_velocity_y {
+            let (velocity_y, next_feet_y) = fall_step(velocity_y, feet_y, delta_t);
+
+            self.fall_velocity_y = (next_feet_y < floor_y).then_some(velocity_y);
+            return self.result(x, next_feet_y.min(floor_y) - below_anchor);
+        }
+
+        // Walk, turning around if we hit the edge of the screen
+        let dist = delta_t * self.speed;
+        let x = match self.direction {
+            Facing::Left => x - dist,
+            Facing::Right => x + dist,
+        };
+        let x = x.clamp(left, right);
+
+        let updated_direction = match self.direction {
+            Facing::Left if x <= left => Facing::Right,
+            Facing::Right if x >= right => Facing::Left,
+            direction => direction,
+        };
+        if updated_direction != self.direction {
+            debug!("changing {:?} to {updated_direction:?}", self.direction);
+            self.direction = updated_direction;
+        }
+
+        // Then leap ahead when it's time, as far as the edge allows
+        self.until_leap_ms = self.until_leap_ms.saturating_sub(delta_t_ms);
+        if self.until_leap_ms == 0 {
+            let target_x = match self.direction {
+                Facing::Left => x - LEAP_DISTANCE,
+                Facing::Right => x + LEAP_DISTANCE,
+            };
+            let target_x = target_x.clamp(left, right);
+            let from = ViewportPoint {
+                x,
+                y: context.span.bottom_at(x),
+            };
+            let to = ViewportPoint {
+                x: target_x,
+                y: context.span.bottom_at(target_x),
+            };
+
+            let duration_ms = jump_duration_ms(from.y - to.y, LEAP_HEIGHT);
+            self.leap = Some(Motion::jump(from, to, LEAP_HEIGHT, duration_ms));
+        }
+
+        self.result(x, context.span.bottom_at(x) - below_anchor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        rendering::SpriteEffects,
+        stage::{MonitorArea, ViewportRect, WalkableSpan},
+    };
+
+    const FRAME_MS: u32 = 16;
+    // A 1280x720 screen, and a 40x60 frame anchored at its feet
+    const FLOOR_Y: f32 = 720.0;
+    const DIMENSIONS: (f32, f32) = (40.0, 60.0);
+    const ANCHOR: (f32, f32) = (20.0, 60.0);
+    // As far right as the anchor goes
+    const RIGHT_EDGE: f32 = 1260.0;
+
+    // Walking along the floor, about to leap
+    fn behavior() -> JumpBehavior {
+        JumpBehavior {
+            direction: Facing::Right,
+            speed: 100.0,
+            until_leap_ms: FRAME_MS,
+            leap: None,
+            fall_velocity_y: None,
+        }
+    }
+
+    fn advance(behavior: &mut JumpBehavior, position: ViewportPoint) -> BehaviorResult {
+        let span = WalkableSpan::new(vec![MonitorArea {
+            name: None,
+            rect: ViewportRect {
+                x: 0.0,
+                y: 0.0,
+                width: 1280.0,
+                height: FLOOR_Y,
+            },
+        }]);
+
+        behavior.advance(
+            FRAME_MS,
+            BehaviorContext {
+                position,
+                dimensions: DIMENSIONS,
+                anchor: ANCHOR,
+                span,
+                surfaces: &[],
+                events: &[],
+                effects: &SpriteEffects::new(),
+            },
+        )
+    }
+
+    #[test]
+    fn drops_to_the_floor_first() {
+        let mut behavior = JumpBehavior {
+            until_leap_ms: LEAP_INTERVAL_MS,
+            fall_velocity_y: Some(0.0),
+            ..behavior()
+        };
+
+        let mut position = ViewportPoint { x: 100.0, y: 300.0 };
+        for _ in 0..100 {
+            position = advance(&mut behavior, position).position;
+        }
+        assert_eq!(position.y, FLOOR_Y);
+        assert!(behavior.fall_velocity_y.is_none());
+    }
+
+    #[test]
+    fn leaps_when_it_is_time() {
+        let mut behavior = behavior();
+
+        let result = advance(
+            &mut behavior,
+            ViewportPoint {
+                x: 100.0,
+                y: FLOOR_Y,
+            },
+        );
+        assert!(result.parameters.jumping);
+        assert!(!result.parameters.grounded);
+
+        // Then up off the ground
+        let result = advance(&mut behavior, result.position);
+        assert!(result.position.y < FLOOR_Y);
+    }
+
+    #[test]
+    fn lands_a_leap_ahead_as_long_as_gravity_says() {
+        let mut behavior = behavior();
+        let mut result = advance(
+            &mut behavior,
+            ViewportPoint {
+                x: 100.0,
+                y: FLOOR_Y,
+            },
+        );
+        let takeoff_x = result.position.x;
+
+        let mut elapsed_ms = 0;
+        let mut highest = FLOOR_Y;
+        while behavior.leap.is_some() {
+            result = advance(&mut behavior, result.position);
+            elapsed_ms += FRAME_MS;
+            highest = highest.min(result.position.y);
+        }
+
+        assert!((result.position.x - (takeoff_x + LEAP_DISTANCE)).abs() < 0.001);
+        assert!((result.position.y - FLOOR_Y).abs() < 0.001);
+        assert!((highest - (FLOOR_Y - LEAP_HEIGHT)).abs() < 1.0);
+        assert!(result.parameters.grounded && !result.parameters.jumping);
+
+        let duration_ms = jump_duration_ms(0.0, LEAP_HEIGHT);
+        assert!(elapsed_ms >= duration_ms && elapsed_ms < duration_ms + FRAME_MS);
+        assert_eq!(behavior.until_leap_ms, LEAP_INTERVAL_MS);
+    }
+
+    #[test]
+    fn leaps_no_further_than_the_edge() {
+        let mut behavior = behavior();
+        let mut result = advance(
+            &mut behavior,
+            ViewportPoint {
+                x: RIGHT_EDGE - 60.0,
+                y: FLOOR_Y,
+            },
+        );
+        while behavior.leap.is_some() {
+            result = advance(&mut behavior, result.position);
+        }
+
+        assert!((result.position.x - RIGHT_EDGE).abs() < 0.001);
+    }
+}
@@ -0,0 +1,191 @@
+use super::GRAVITY_PIXELS_PER_SECOND_SQUARED;
+use crate::fursona::animation::{Easing, Tween};
+use crate::stage::ViewportPoint;
+
+fn lerp(from: &ViewportPoint, to: &ViewportPoint, t: f32) -> ViewportPoint {
+    ViewportPoint {
+        x: from.x + (to.x - from.x) * t,
+        y: from.y + (to.y - from.y) * t,
+    }
+}
+
+// How long a jump under gravity takes to rise to its peak, `height` pixels
+// above whichever end is higher, and fall back down to somewhere `rise`
+// pixels above where it started. Pair it with the same rise and height given
+// to `Motion::jump`.
+pub fn jump_duration_ms(rise: f32, height: f32) -> u32 {
+    let peak = rise.max(0.0) + height.max(0.0);
+    let fall_seconds =
+        |height: f32| (2.0 * height.max(0.0) / GRAVITY_PIXELS_PER_SECOND_SQUARED).sqrt();
+    ((fall_seconds(peak) + fall_seconds(peak - rise)) * 1000.0) as u32
+}
+
+// Movement along a path over a set amount of time, for behaviors that move
+// somewhere in particular rather than walking at a steady pace. Every path
+// is a cubic Bezier curve, which covers straight lines and jump arcs too.
+// Positions are whatever the behavior wants them to be, usually where the
+// feet are.
+#[derive(Clone, Debug)]
+pub struct Motion {
+    // The start, two control points and the end
+    points: [ViewportPoint; 4],
+    tween: Tween,
+}
+
+impl Motion {
+    // Along the curve from the first point to the last, pulled towards the
+    // two in between. The easing decides how the time is spent along it.
+    pub fn bezier(points: [ViewportPoint; 4], duration_ms: u32, easing: Easing) -> Self {
+        Self {
+            points,
+            tween: Tween::new(duration_ms, easing),
+        }
+    }
+
+    // In a straight line. Easings that overshoot, like springs, carry on past
+    // the end before coming back.
+    pub fn move_to(
+        from: ViewportPoint,
+        to: ViewportPoint,
+        duration_ms: u32,
+        easing: Easing,
+    ) -> Self {
+        let points = [
+            from.clone(),
+            lerp(&from, &to, 1.0 / 3.0),
+            lerp(&from, &to, 2.0 / 3.0),
+            to,
+        ];
+        Self::bezier(points, duration_ms, easing)
+    }
+
+    // In a parabola, like a jump under gravity, peaking `height` pixels above
+    // whichever end is higher. Moves across at a steady speed.
+    pub fn jump(from: ViewportPoint, to: ViewportPoint, height: f32, duration_ms: u32) -> Self {
+        // Working upwards from the start: how far up the end is, and how far
+        // up the peak is
+        let rise = from.y - to.y;
+        let peak = rise.max(0.0) + height.max(0.0);
+
+        // The middle control point of the quadratic curve through both ends
+        // that just reaches the peak
+        let control = ViewportPoint {
+            x: (from.x + to.x) / 2.0,
+            y: from.y - (peak + (peak * (peak - rise)).sqrt()),
+        };
+
+        // The same curve as a cubic
+        let points = [
+            from.clone(),
+            lerp(&from, &control, 2.0 / 3.0),
+            lerp(&to, &control, 2.0 / 3.0),
+            to,
+        ];
+        Self::bezier(points, duration_ms, Easing::Linear)
+    }
+
+    // Moves along, returning where we are now
+    pub fn advance(&mut self, delta_t_ms: u32) -> ViewportPoint {
+        self.tween.advance(delta_t_ms);
+        self.position()
+    }
+
+    pub fn position(&self) -> ViewportPoint {
+        let t = self.tween.value();
+        let [p0, p1, p2, p3] = &self.points;
+        let a = lerp(p0, p1, t);
+        let b = lerp(p1, p2, t);
+        let c = lerp(p2, p3, t);
+        lerp(&lerp(&a, &b, t), &lerp(&b, &c, t), t)
+    }
+
+    pub fn end(&self) -> &ViewportPoint {
+        &self.points[3]
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.tween.is_finished()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Where the jump goes, sampled every few milliseconds
+    fn path(mut motion: Motion) -> Vec<ViewportPoint> {
+        let mut points = vec![motion.position()];
+        while !motion.is_finished() {
+            points.push(motion.advance(5));
+        }
+        points
+    }
+
+    fn highest(points: &[ViewportPoint]) -> f32 {
+        points.iter().map(|point| point.y).fold(f32::MAX, f32::min)
+    }
+
+    #[test]
+    fn jumps_start_and_end_where_they_were_asked_to() {
+        let points = path(Motion::jump(
+            ViewportPoint { x: 10.0, y: 500.0 },
+            ViewportPoint { x: 160.0, y: 420.0 },
+            40.0,
+            600,
+        ));
+
+        let (start, end) = (&points[0], &points[points.len() - 1]);
+        assert_eq!((start.x, start.y), (10.0, 500.0));
+        assert!((end.x - 160.0).abs() < 0.001 && (end.y - 420.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn jumps_up_peak_above_the_end() {
+        let points = path(Motion::jump(
+            ViewportPoint { x: 0.0, y: 500.0 },
+            ViewportPoint { x: 100.0, y: 400.0 },
+            40.0,
+            600,
+        ));
+        assert!((highest(&points) - 360.0).abs() < 0.5);
+    }
+
+    #[test]
+    fn jumps_down_peak_above_the_start() {
+        let points = path(Motion::jump(
+            ViewportPoint { x: 0.0, y: 400.0 },
+            ViewportPoint {
+                x: -100.0,
+                y: 500.0,
+            },
+            40.0,
+            600,
+        ));
+        assert!((highest(&points) - 360.0).abs() < 0.5);
+    }
+
+    #[test]
+    fn jumps_move_across_at_a_steady_speed() {
+        let mut motion = Motion::jump(
+            ViewportPoint { x: 0.0, y: 0.0 },
+            ViewportPoint { x: 100.0, y: 0.0 },
+            50.0,
+            1000,
+        );
+        assert!((motion.advance(250).x - 25.0).abs() < 0.001);
+        assert!((motion.advance(250).x - 50.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn jumps_take_as_long_as_gravity_says() {
+        // Straight up to the peak and back down again
+        let expected = 2.0 * (2.0 * 40.0 / GRAVITY_PIXELS_PER_SECOND_SQUARED).sqrt();
+        assert_eq!(jump_duration_ms(0.0, 40.0), (expected * 1000.0) as u32);
+
+        // Higher ledges and higher peaks take longer, and dropping down takes
+        // longer than landing on the same level
+        assert!(jump_duration_ms(100.0, 40.0) > jump_duration_ms(0.0, 40.0));
+        assert!(jump_duration_ms(0.0, 96.0) > jump_duration_ms(0.0, 40.0));
+        assert!(jump_duration_ms(-100.0, 40.0) > jump_duration_ms(0.0, 40.0));
+    }
+}
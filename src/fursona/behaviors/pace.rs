@@ -1,124 +1,193 @@
 use super::{
-    super::{animation::Animation, rendering::FursonaRenderer},
-    AnimationDictionary, Behavior, BehaviorContext, BehaviorPreview, BehaviorResult,
+    super::{
+        animation::{AnimationDictionary, AnimationParameters},
+        rendering::FursonaRenderer,
+    },
+    Behavior, BehaviorContext, BehaviorPreview, BehaviorResult, Facing,
 };
-use crate::stage::ViewportRect;
+use crate::stage::ViewportPoint;
+use tracing::debug;
 
-const SPEED_PIXELS_PER_SECOND: u32 = 240;
-const MILLISECONDS_PER_PIXEL: f32 = (SPEED_PIXELS_PER_SECOND as f32) / 1000.0;
+// The event walk animations fire when a foot comes down
+const FOOTSTEP_EVENT: &str = "footstep";
 
-#[derive(Debug, PartialEq)]
-enum PaceDirection {
-    Left,
-    Right,
+pub struct PaceBehavior {
+    direction: Facing,
+    // In pixels per second
+    speed: f32,
+    // Once the walk animation has shown it has footsteps, we only turn around
+    // on one, rather than partway through a stride
+    has_footsteps: bool,
 }
 
-pub struct PaceBehavior<T: FursonaRenderer> {
-    direction: PaceDirection,
-    walk_left: Box<dyn Animation<ValidRenderer = T>>,
-    walk_right: Box<dyn Animation<ValidRenderer = T>>,
-}
-
-impl<T: FursonaRenderer> BehaviorPreview<T> for PaceBehavior<T> {
-    fn is_possible(anims: &AnimationDictionary<T>) -> bool {
-        anims.walk_left.is_some() && anims.walk_right.is_some()
+impl BehaviorPreview for PaceBehavior {
+    fn is_possible<T: FursonaRenderer>(anims: &AnimationDictionary<T>) -> bool {
+        anims.has("walk_left") && anims.has("walk_right")
     }
 }
 
-impl<T: FursonaRenderer> Behavior<T> for PaceBehavior<T> {
-    fn new(anims: &mut AnimationDictionary<T>) -> Option<Self>
+impl Behavior for PaceBehavior {
+    fn new<T: FursonaRenderer>(anims: &AnimationDictionary<T>, speed: f32) -> Option<Self>
     where
         Self: Sized,
     {
-        let walk_left = if let Some(anim) = &mut anims.walk_left {
-            anim()
-        } else {
-            return None;
-        };
-
-        let walk_right = if let Some(anim) = &mut anims.walk_right {
-            anim()
-        } else {
+        if !Self::is_possible(anims) {
             return None;
-        };
+        }
 
         Some(Self {
-            direction: PaceDirection::Right,
-            walk_left,
-            walk_right,
+            direction: Facing::Right,
+            speed,
+            has_footsteps: false,
         })
     }
 
-    fn advance(
-        &mut self,
-        delta_t_ms: u32,
-        renderer: &mut T,
-        context: BehaviorContext,
-    ) -> BehaviorResult {
+    fn name(&self) -> &'static str {
+        "pace"
+    }
+
+    fn facing(&self) -> Facing {
+        self.direction
+    }
+
+    fn set_facing(&mut self, facing: Facing) {
+        self.direction = facing;
+    }
+
+    fn advance(&mut self, delta_t_ms: u32, context: BehaviorContext) -> BehaviorResult {
         // Determine our boundaries
-        let left = context.stage.viewport.left();
-        let anim = match self.direction {
-            PaceDirection::Left => &self.walk_left,
-            PaceDirection::Right => &self.walk_right,
-        };
-        let right = context.stage.viewport.right() - anim.intrinsic_dimensions().0;
+        let (_, anchor_y) = context.anchor;
+        let (left, right) = context.x_range();
 
         // Determine what our new x position should be
-        let dist = (delta_t_ms as f32) * MILLISECONDS_PER_PIXEL;
+        let dist = (delta_t_ms as f32) / 1000.0 * self.speed;
         let x = match self.direction {
-            PaceDirection::Left => context.position.x - dist,
-            PaceDirection::Right => context.position.x + dist,
+            Facing::Left => context.position.x - dist,
+            Facing::Right => context.position.x + dist,
         };
         let x = x.clamp(left, right);
 
-        // If we've reached the edge, then we'll swap directions
+        let footstep = context.events.iter().any(|event| event == FOOTSTEP_EVENT);
+        self.has_footsteps |= footstep;
+
+        // If we've reached the edge, then we'll swap directions, as soon as
+        // our foot is down
+        let can_turn = footstep || !self.has_footsteps;
         let updated_direction = match self.direction {
-            PaceDirection::Left => {
-                if x <= left {
-                    PaceDirection::Right
+            Facing::Left => {
+                if x <= left && can_turn {
+                    Facing::Right
                 } else {
-                    PaceDirection::Left
+                    Facing::Left
                 }
             }
-            PaceDirection::Right => {
-                if x >= right {
-                    PaceDirection::Left
+            Facing::Right => {
+                if x >= right && can_turn {
+                    Facing::Left
                 } else {
-                    PaceDirection::Right
+                    Facing::Right
                 }
             }
         };
 
-        let did_change = updated_direction != self.direction;
-        if did_change {
-            println!(
-                "[pace] changing {:?} to {updated_direction:?}",
-                self.direction
-            );
+        if updated_direction != self.direction {
+            debug!("changing {:?} to {updated_direction:?}", self.direction);
             self.direction = updated_direction;
         }
 
-        // Update our animation
-        let anim = match self.direction {
-            PaceDirection::Left => &mut self.walk_left,
-            PaceDirection::Right => &mut self.walk_right,
-        };
-
-        if did_change {
-            anim.reset();
-        }
-
-        anim.advance(delta_t_ms, renderer);
-
         // Return the result
-        let (width, height) = anim.intrinsic_dimensions();
         BehaviorResult {
-            bounding_box: ViewportRect {
+            position: ViewportPoint {
                 x,
-                y: context.position.y,
-                width,
-                height,
+                // Monitors can be different heights, so keep to the top of
+                // whichever one we're over
+                y: context.span.top_at(x) + anchor_y,
+            },
+            parameters: AnimationParameters {
+                speed: self.speed,
+                facing: self.direction,
+                grounded: true,
+                grip: None,
+                jumping: false,
             },
+            effects: None,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        rendering::SpriteEffects,
+        stage::{MonitorArea, ViewportRect, WalkableSpan},
+    };
+
+    const FRAME_MS: u32 = 16;
+    // A 40x60 frame anchored at its feet, on a 1280x720 screen
+    const DIMENSIONS: (f32, f32) = (40.0, 60.0);
+    const ANCHOR: (f32, f32) = (20.0, 60.0);
+    // As far right as the anchor goes
+    const RIGHT_EDGE: f32 = 1260.0;
+
+    fn advance(behavior: &mut PaceBehavior, x: f32, events: &[String]) -> f32 {
+        let span = WalkableSpan::new(vec![MonitorArea {
+            name: None,
+            rect: ViewportRect {
+                x: 0.0,
+                y: 0.0,
+                width: 1280.0,
+                height: 720.0,
+            },
+        }]);
+
+        behavior
+            .advance(
+                FRAME_MS,
+                BehaviorContext {
+                    position: ViewportPoint { x, y: 60.0 },
+                    dimensions: DIMENSIONS,
+                    anchor: ANCHOR,
+                    span,
+                    surfaces: &[],
+                    events,
+                    effects: &SpriteEffects::new(),
+                },
+            )
+            .position
+            .x
+    }
+
+    fn pace() -> PaceBehavior {
+        PaceBehavior {
+            direction: Facing::Right,
+            speed: 100.0,
+            has_footsteps: false,
+        }
+    }
+
+    #[test]
+    fn turns_around_at_the_edge() {
+        let mut behavior = pace();
+
+        assert_eq!(advance(&mut behavior, RIGHT_EDGE, &[]), RIGHT_EDGE);
+        assert_eq!(behavior.facing(), Facing::Left);
+        assert!(advance(&mut behavior, RIGHT_EDGE, &[]) < RIGHT_EDGE);
+    }
+
+    #[test]
+    fn waits_for_a_footstep_to_turn_around() {
+        let mut behavior = pace();
+        let footstep = [FOOTSTEP_EVENT.to_owned()];
+
+        // Stepping along the way shows the walk has footsteps
+        advance(&mut behavior, 600.0, &footstep);
+        assert_eq!(behavior.facing(), Facing::Right);
+
+        // So at the edge we wait for the next one
+        assert_eq!(advance(&mut behavior, RIGHT_EDGE, &[]), RIGHT_EDGE);
+        assert_eq!(behavior.facing(), Facing::Right);
+        advance(&mut behavior, RIGHT_EDGE, &footstep);
+        assert_eq!(behavior.facing(), Facing::Left);
+    }
+}
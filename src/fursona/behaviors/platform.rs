@@ -0,0 +1,461 @@
+use super::{
+    super::{
+        animation::{AnimationDictionary, AnimationParameters, Easing},
+        rendering::FursonaRenderer,
+    },
+    fall_step,
+    motion::{jump_duration_ms, Motion},
+    Behavior, BehaviorContext, BehaviorPreview, BehaviorResult, Facing,
+    GRAVITY_PIXELS_PER_SECOND_SQUARED,
+};
+use crate::{stage::ViewportPoint, surfaces::Surface};
+use tracing::debug;
+
+const JUMP_PIXELS_PER_SECOND: f32 = 1000.0;
+// How far above the higher end of a jump it peaks
+const JUMP_APEX: f32 = 40.0;
+// The highest ledge a jump can reach: as high as jumping straight up would
+// take us, less the room to clear the ledge
+const JUMP_HEIGHT: f32 = JUMP_PIXELS_PER_SECOND * JUMP_PIXELS_PER_SECOND
+    / (2.0 * GRAVITY_PIXELS_PER_SECOND_SQUARED)
+    - JUMP_APEX;
+// How far ahead to look for something to jump up onto
+const JUMP_REACH: f32 = 160.0;
+// How far past the edge of a window to aim for when jumping onto it
+const JUMP_LANDING_INSET: f32 = 24.0;
+// How far a window's top can drift before we notice it's moved
+const FOOTING_TOLERANCE: f32 = 0.5;
+// How far a window can move up or down at once and still carry us along,
+// rather than leaving us to fall
+const RIDE_DISTANCE: f32 = 48.0;
+const SETTLE_MS: u32 = 300;
+
+#[derive(Debug)]
+enum Footing {
+    // The bottom of the screen
+    Floor,
+    // The top of another application's window
+    Surface(u64),
+    // On the way to the top of another application's window
+    Jumping { motion: Motion, onto: u64 },
+    // Positive velocities are heading down, as with viewport coordinates
+    Air { velocity_y: f32 },
+}
+
+// Walks along the bottom of the screen and the tops of other application
+// windows, jumping up onto windows it comes across, and falling when the one
+// it's on moves, closes, or runs out.
+pub struct PlatformBehavior {
+    direction: Facing,
+    // How fast we walk, in pixels per second
+    speed: f32,
+    footing: Footing,
+    // Catching up with the window we're standing on after it's moved
+    settle: Option<Motion>,
+}
+
+impl BehaviorPreview for PlatformBehavior {
+    fn is_possible<T: FursonaRenderer>(anims: &AnimationDictionary<T>) -> bool {
+        anims.has("walk_left") && anims.has("walk_right")
+    }
+}
+
+// Whether we can stand on the surface with our anchor at the given x position
+fn is_over(surface: &Surface, x: f32) -> bool {
+    x >= surface.left() && x <= surface.right()
+}
+
+impl PlatformBehavior {
+    // A window up ahead that's low enough to jump onto, if there is one
+    fn find_jump_target<'a>(
+        &self,
+        surfaces: &'a [Surface],
+        x: f32,
+        feet_y: f32,
+    ) -> Option<&'a Surface> {
+        surfaces.iter().find(|surface| {
+            let is_ahead = match self.direction {
+                Facing::Left => surface.right() < x && surface.right() >= x - JUMP_REACH,
+                Facing::Right => surface.left() > x && surface.left() <= x + JUMP_REACH,
+            };
+            let is_reachable = surface.top() < feet_y && surface.top() >= feet_y - JUMP_HEIGHT;
+
+            is_ahead && is_reachable
+        })
+    }
+
+    // Sets off for a spot just past the near edge of the window, with our
+    // feet currently at the given point
+    fn jump_onto(&mut self, surface: &Surface, x: f32, feet_y: f32) {
+        let landing_x = match self.direction {
+            Facing::Left => (surface.right() - JUMP_LANDING_INSET).max(surface.left()),
+            Facing::Right => (surface.left() + JUMP_LANDING_INSET).min(surface.right()),
+        };
+        let motion = Motion::jump(
+            ViewportPoint { x, y: feet_y },
+            ViewportPoint {
+                x: landing_x,
+                y: surface.top(),
+            },
+            JUMP_APEX,
+            jump_duration_ms(feet_y - surface.top(), JUMP_APEX),
+        );
+
+        self.settle = None;
+        self.footing = Footing::Jumping {
+            motion,
+            onto: surface.id,
+        };
+    }
+
+    fn result(&self, x: f32, y: f32) -> BehaviorResult {
+        BehaviorResult {
+            position: ViewportPoint { x, y },
+            parameters: AnimationParameters {
+                speed: self.speed,
+                facing: self.direction,
+                grounded: matches!(self.footing, Footing::Floor | Footing::Surface(_)),
+                grip: None,
+                jumping: matches!(self.footing, Footing::Jumping { .. }),
+            },
+            effects: None,
+        }
+    }
+}
+
+impl Behavior for PlatformBehavior {
+    fn new<T: FursonaRenderer>(anims: &AnimationDictionary<T>, speed: f32) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        if !Self::is_possible(anims) {
+            return None;
+        }
+
+        Some(Self {
+            direction: Facing::Right,
+            speed,
+            // Wherever we start out, we'll drop down to something to stand on
+            footing: Footing::Air { velocity_y: 0.0 },
+            settle: None,
+        })
+    }
+
+    fn name(&self) -> &'static str {
+        "platform"
+    }
+
+    fn facing(&self) -> Facing {
+        self.direction
+    }
+
+    fn set_facing(&mut self, facing: Facing) {
+        self.direction = facing;
+    }
+
+    fn advance(&mut self, delta_t_ms: u32, context: BehaviorContext) -> BehaviorResult {
+        let delta_t = delta_t_ms as f32 / 1000.0;
+        let below_anchor = context.below_anchor();
+        let (left, right) = context.x_range();
+        let feet_y = context.position.y + below_anchor;
+
+        // Jumps take us along their own path until they're over
+        if let Footing::Jumping { motion, onto } = &mut self.footing {
+            let feet = motion.advance(delta_t_ms);
+            let x = feet.x.clamp(left, right);
+
+            if motion.is_finished() {
+                // Land if the window is still about where we were aiming,
+                // and otherwise fall from here
+                let onto = *onto;
+                let landed = context.surfaces.iter().any(|surface| {
+                    surface.id == onto
+                        && is_over(surface, x)
+                        && (surface.top() - feet.y).abs() <= RIDE_DISTANCE
+                });
+                self.footing = match landed {
+                    true => Footing::Surface(onto),
+                    false => Footing::Air { velocity_y: 0.0 },
+                };
+            }
+
+            return self.result(x, feet.y - below_anchor);
+        }
+
+        // Walk, turning around if we hit the edge of the screen
+        let dist = delta_t * self.speed;
+        let x = match self.direction {
+            Facing::Left => context.position.x - dist,
+            Facing::Right => context.position.x + dist,
+        };
+        let x = x.clamp(left, right);
+
+        let updated_direction = match self.direction {
+            Facing::Left if x <= left => Facing::Right,
+            Facing::Right if x >= right => Facing::Left,
+            direction => direction,
+        };
+
+        let floor_y = context.span.bottom_at(x);
+
+        // Make sure whatever we're standing on is still there
+        if let Footing::Surface(id) = self.footing {
+            let surface = context
+                .surfaces
+                .iter()
+                .find(|surface| surface.id == id && is_over(surface, x));
+            // Where we'll be once we've caught up with any earlier move
+            let settled_y = self.settle.as_ref().map_or(feet_y, |settle| settle.end().y);
+
+            match surface {
+                Some(surface) if (surface.top() - settled_y).abs() <= FOOTING_TOLERANCE => (),
+                // Windows being moved carry us along, springing after them
+                Some(surface) if (surface.top() - settled_y).abs() <= RIDE_DISTANCE => {
+                    self.settle = Some(Motion::move_to(
+                        ViewportPoint { x, y: feet_y },
+                        ViewportPoint {
+                            x,
+                            y: surface.top(),
+                        },
+                        SETTLE_MS,
+                        Easing::Spring,
+                    ));
+                }
+                // Rather than walking off the edge, jump if there's
+                // somewhere to jump to
+                _ => {
+                    self.settle = None;
+                    match self.find_jump_target(context.surfaces, x, feet_y) {
+                        Some(target) => self.jump_onto(target, x, feet_y),
+                        None => self.footing = Footing::Air { velocity_y: 0.0 },
+                    }
+                }
+            }
+        }
+
+        let feet_y = match &self.footing {
+            Footing::Floor => {
+                // Jump up onto any windows we come across
+                if let Some(target) = self.find_jump_target(context.surfaces, x, floor_y) {
+                    self.jump_onto(target, x, floor_y);
+                }
+
+                floor_y
+            }
+            Footing::Surface(_) => match &mut self.settle {
+                Some(settle) => {
+                    let settled_y = settle.advance(delta_t_ms).y;
+                    if settle.is_finished() {
+                        self.settle = None;
+                    }
+                    settled_y
+                }
+                None => feet_y,
+            },
+            // Just set off, and we'll start moving next time
+            Footing::Jumping { .. } => feet_y,
+            Footing::Air { velocity_y } => {
+                let (velocity_y, next_feet_y) = fall_step(*velocity_y, feet_y, delta_t);
+
+                // Only land on things we're coming down onto from above. The
+                // topmost window is checked first, since it's the one in view.
+                let landing = if velocity_y > 0.0 {
+                    context.surfaces.iter().rev().find(|surface| {
+                        is_over(surface, x)
+                            && surface.top() >= feet_y
+                            && surface.top() <= next_feet_y
+                    })
+                } else {
+                    None
+                };
+
+                if let Some(surface) = landing {
+                    self.footing = Footing::Surface(surface.id);
+                    surface.top()
+                } else if next_feet_y >= floor_y {
+                    self.footing = Footing::Floor;
+                    floor_y
+                } else {
+                    self.footing = Footing::Air { velocity_y };
+                    next_feet_y
+                }
+            }
+        };
+
+        if updated_direction != self.direction {
+            debug!("changing {:?} to {updated_direction:?}", self.direction);
+            self.direction = updated_direction;
+        }
+
+        self.result(x, feet_y - below_anchor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        rendering::SpriteEffects,
+        stage::{MonitorArea, ViewportRect, WalkableSpan},
+    };
+
+    const FRAME_MS: u32 = 16;
+    // A 1280x720 screen, and a 40x60 frame anchored at its feet
+    const FLOOR_Y: f32 = 720.0;
+    const DIMENSIONS: (f32, f32) = (40.0, 60.0);
+    const ANCHOR: (f32, f32) = (20.0, 60.0);
+
+    fn behavior(footing: Footing) -> PlatformBehavior {
+        PlatformBehavior {
+            direction: Facing::Right,
+            speed: 100.0,
+            footing,
+            settle: None,
+        }
+    }
+
+    fn window(id: u64, x: f32, y: f32, width: f32) -> Surface {
+        Surface {
+            id,
+            rect: ViewportRect {
+                x,
+                y,
+                width,
+                height: FLOOR_Y - y,
+            },
+        }
+    }
+
+    fn advance(
+        behavior: &mut PlatformBehavior,
+        position: ViewportPoint,
+        surfaces: &[Surface],
+    ) -> ViewportPoint {
+        let span = WalkableSpan::new(vec![MonitorArea {
+            name: None,
+            rect: ViewportRect {
+                x: 0.0,
+                y: 0.0,
+                width: 1280.0,
+                height: FLOOR_Y,
+            },
+        }]);
+
+        behavior
+            .advance(
+                FRAME_MS,
+                BehaviorContext {
+                    position,
+                    dimensions: DIMENSIONS,
+                    anchor: ANCHOR,
+                    span,
+                    surfaces,
+                    events: &[],
+                    effects: &SpriteEffects::new(),
+                },
+            )
+            .position
+    }
+
+    // Keeps going until we're standing on something, or have given up
+    fn advance_until_grounded(
+        behavior: &mut PlatformBehavior,
+        mut position: ViewportPoint,
+        surfaces: &[Surface],
+    ) -> ViewportPoint {
+        for _ in 0..200 {
+            position = advance(behavior, position, surfaces);
+            if matches!(behavior.footing, Footing::Floor | Footing::Surface(_)) {
+                break;
+            }
+        }
+        position
+    }
+
+    #[test]
+    fn lands_on_windows_below() {
+        let surfaces = [window(1, 100.0, 400.0, 400.0)];
+        let mut behavior = behavior(Footing::Air { velocity_y: 0.0 });
+
+        let position = advance_until_grounded(
+            &mut behavior,
+            ViewportPoint { x: 200.0, y: 100.0 },
+            &surfaces,
+        );
+        assert!(matches!(behavior.footing, Footing::Surface(1)));
+        assert_eq!(position.y, 400.0);
+    }
+
+    #[test]
+    fn falls_through_to_the_floor_without_windows() {
+        let mut behavior = behavior(Footing::Air { velocity_y: 0.0 });
+
+        let position =
+            advance_until_grounded(&mut behavior, ViewportPoint { x: 200.0, y: 100.0 }, &[]);
+        assert!(matches!(behavior.footing, Footing::Floor));
+        assert_eq!(position.y, FLOOR_Y);
+    }
+
+    #[test]
+    fn falls_when_the_window_closes() {
+        let surfaces = [window(1, 100.0, 400.0, 400.0)];
+        let mut behavior = behavior(Footing::Surface(1));
+
+        let position = advance(
+            &mut behavior,
+            ViewportPoint { x: 200.0, y: 400.0 },
+            &surfaces,
+        );
+        assert!(matches!(behavior.footing, Footing::Surface(1)));
+
+        // Nothing to jump to either, so down we go
+        let position = advance(&mut behavior, position, &[]);
+        assert!(matches!(behavior.footing, Footing::Air { .. }));
+
+        let position = advance_until_grounded(&mut behavior, position, &[]);
+        assert!(matches!(behavior.footing, Footing::Floor));
+        assert_eq!(position.y, FLOOR_Y);
+    }
+
+    #[test]
+    fn jumps_onto_windows_within_reach() {
+        let surfaces = [window(2, 200.0, FLOOR_Y - 100.0, 300.0)];
+        let mut behavior = behavior(Footing::Floor);
+
+        let position = advance(
+            &mut behavior,
+            ViewportPoint {
+                x: 100.0,
+                y: FLOOR_Y,
+            },
+            &surfaces,
+        );
+        assert!(matches!(behavior.footing, Footing::Jumping { onto: 2, .. }));
+
+        let position = advance_until_grounded(&mut behavior, position, &surfaces);
+        assert!(matches!(behavior.footing, Footing::Surface(2)));
+        assert_eq!(position.y, FLOOR_Y - 100.0);
+        assert!(position.x >= 200.0 + JUMP_LANDING_INSET);
+    }
+
+    #[test]
+    fn walks_past_windows_out_of_reach() {
+        let surfaces = [
+            // Too high
+            window(1, 200.0, FLOOR_Y - JUMP_HEIGHT - 10.0, 100.0),
+            // Too far ahead
+            window(2, 100.0 + JUMP_REACH + 10.0, FLOOR_Y - 50.0, 100.0),
+        ];
+        let mut behavior = behavior(Footing::Floor);
+
+        advance(
+            &mut behavior,
+            ViewportPoint {
+                x: 100.0,
+                y: FLOOR_Y,
+            },
+            &surfaces,
+        );
+        assert!(matches!(behavior.footing, Footing::Floor));
+    }
+}
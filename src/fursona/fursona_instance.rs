@@ -1,92 +1,551 @@
 use super::{
-    animation::animation_2d::{Animation2D, Keyframe2D},
-    behaviors::{pace::PaceBehavior, AnimationDictionary, Behavior, BehaviorContext},
-    rendering::{renderer_2d::FursonaRenderer2D, FursonaRenderer},
-    Fursona,
+    animation::{
+        animation_2d::{Animation2D, Keyframe2D},
+        animation_3d::Animation3D,
+        animation_skeletal::AnimationSkeletal,
+        AnimDictionaryEntry, AnimationConstructor, AnimationDictionary, AnimationStateMachine,
+        FrameRect, ProceduralMotion, ANIMATION_NAMES,
+    },
+    behaviors::{
+        climb::ClimbBehavior, jump::JumpBehavior, pace::PaceBehavior, platform::PlatformBehavior,
+        Behavior, BehaviorContext, BehaviorKind, Facing,
+    },
+    model::{Model, ModelError, FACING_YAW},
+    rendering::{
+        renderer_2d::FursonaRenderer2D, renderer_3d::FursonaRenderer3D,
+        renderer_skeletal::FursonaRendererSkeletal, FursonaRenderer,
+    },
+    skeleton::{Skeleton, SkeletonError},
+    Fursona, FursonaKeyframe,
 };
 use crate::{
-    rendering::TextureCache,
-    stage::{Stage, ViewportPoint, ViewportRect},
+    rendering::{Color, Palette, SpriteEffects, TextureCache, TextureError},
+    stage::{Stage, ViewportPoint, ViewportRect, WalkableSpan},
+    surfaces::Surface,
 };
+use std::rc::Rc;
+use thiserror::Error;
+use tracing::{debug, instrument, warn};
+
+const HOVER_OUTLINE_COLOR: Color = Color::WHITE;
+
+#[derive(Debug, Error)]
+pub enum FursonaInstanceError {
+    #[error("none of the behaviors can run with the animations provided")]
+    NoPossibleBehavior,
+    #[error(transparent)]
+    Texture(#[from] TextureError),
+    #[error(transparent)]
+    Skeleton(#[from] SkeletonError),
+    #[error(transparent)]
+    Model(#[from] ModelError),
+}
 
 enum FursonaInstanceRendering {
     TwoD {
-        behavior: Box<dyn Behavior<FursonaRenderer2D>>,
+        animations: AnimationStateMachine<FursonaRenderer2D>,
         renderer: FursonaRenderer2D,
     },
+    Skeletal {
+        animations: AnimationStateMachine<FursonaRendererSkeletal>,
+        renderer: FursonaRendererSkeletal,
+    },
+    ThreeD {
+        animations: AnimationStateMachine<FursonaRenderer3D>,
+        renderer: FursonaRenderer3D,
+    },
+}
+
+// The parts of an instance that survive a restart.
+pub struct FursonaInstanceState {
+    pub name: String,
+    pub position: ViewportPoint,
+    pub behavior: String,
+    pub facing: Facing,
 }
 
 pub struct FursonaInstance {
+    name: String,
+    // Where the anchor of the current frame is, which is usually its feet
     position: ViewportPoint,
-    width: f32,
-    height: f32,
+    scale: f32,
+    monitor: Option<usize>,
+    behavior: Box<dyn Behavior>,
     rendering: FursonaInstanceRendering,
+    motion: ProceduralMotion,
+    // How the fursona is drawn unless its behavior says otherwise
+    effects: SpriteEffects,
+    hovered: bool,
+    // Reached by the animations during the last update, for the behavior to
+    // react to on the next one
+    events: Vec<String>,
+}
+
+fn make_animation_2d(
+    keyframes: Option<&Vec<FursonaKeyframe>>,
+    palette: &Palette,
+    stage: &Stage,
+    texture_cache: &mut TextureCache,
+) -> Result<Option<Box<AnimationConstructor<FursonaRenderer2D>>>, TextureError> {
+    let keyframes = match keyframes {
+        Some(keyframes) => keyframes,
+        None => return Ok(None),
+    };
+
+    // Load the textures up front, so that each constructed animation shares
+    // the same images rather than loading them again
+    let keyframes = keyframes
+        .iter()
+        .map(|keyframe| {
+            let image = texture_cache.get(&stage.display, keyframe.image.to_owned(), palette)?;
+            // Unless told otherwise, assume the feet are at the middle of the
+            // bottom edge
+            let anchor = keyframe.anchor.unwrap_or_else(|| {
+                let (width, height) = image.dimensions();
+                (width as f32 / 2.0, height as f32)
+            });
+            Ok(Keyframe2D {
+                duration_ms: keyframe.duration_ms,
+                image,
+                anchor,
+                hitbox: keyframe.hitbox.clone(),
+                events: keyframe.events.clone(),
+            })
+        })
+        .collect::<Result<Vec<Keyframe2D>, TextureError>>()?;
+
+    Ok(Some(Box::new(move || {
+        Box::new(Animation2D::new(keyframes.clone()))
+    })))
+}
+
+// Skeletons are usually made facing right, with one animation for both
+// directions. So when there's no animation called `walk_left`, say, `walk`
+// is used mirrored instead, and likewise `walk` as it is for `walk_right`.
+fn make_animation_skeletal(
+    skeleton: &Rc<Skeleton>,
+    name: &str,
+) -> Option<Box<AnimationConstructor<FursonaRendererSkeletal>>> {
+    let (animation, mirrored) = match skeleton.animation_index(name) {
+        Some(animation) => (animation, false),
+        None => match name.strip_suffix("_left") {
+            Some(base) => (skeleton.animation_index(base)?, true),
+            None => (
+                skeleton.animation_index(name.strip_suffix("_right")?)?,
+                false,
+            ),
+        },
+    };
+
+    let skeleton = skeleton.clone();
+    Some(Box::new(move || {
+        Box::new(AnimationSkeletal::new(
+            skeleton.clone(),
+            animation,
+            mirrored,
+        ))
+    }))
+}
+
+// Models are turned to face left or right, so one animation does for both
+// directions: `walk` is used for `walk_left` and `walk_right` unless the
+// model has those too.
+fn make_animation_3d(
+    model: &Rc<Model>,
+    name: &str,
+) -> Option<Box<AnimationConstructor<FursonaRenderer3D>>> {
+    let (base, yaw) = match name.strip_suffix("_left") {
+        Some(base) => (base, -FACING_YAW),
+        None => (name.strip_suffix("_right").unwrap_or(name), FACING_YAW),
+    };
+    let animation = model
+        .animation_index(name)
+        .or_else(|| model.animation_index(base))?;
+
+    let model = model.clone();
+    Some(Box::new(move || {
+        Box::new(Animation3D::new(model.clone(), animation, yaw))
+    }))
+}
+
+fn make_behavior<T: FursonaRenderer>(
+    fursona: &Fursona,
+    anims: &AnimationDictionary<T>,
+) -> Result<Box<dyn Behavior>, FursonaInstanceError> {
+    Ok(match fursona.behavior {
+        BehaviorKind::Pace => Box::new(
+            PaceBehavior::new(anims, fursona.speed)
+                .ok_or(FursonaInstanceError::NoPossibleBehavior)?,
+        ),
+        BehaviorKind::Platform => Box::new(
+            PlatformBehavior::new(anims, fursona.speed)
+                .ok_or(FursonaInstanceError::NoPossibleBehavior)?,
+        ),
+        BehaviorKind::Climb => Box::new(
+            ClimbBehavior::new(anims, fursona.speed)
+                .ok_or(FursonaInstanceError::NoPossibleBehavior)?,
+        ),
+        BehaviorKind::Jump => Box::new(
+            JumpBehavior::new(anims, fursona.speed)
+                .ok_or(FursonaInstanceError::NoPossibleBehavior)?,
+        ),
+    })
+}
+
+// Makes whichever of the animations the fursona has, along with the behavior
+// and state machine to go with them, however they end up being drawn
+fn make_animations<T: FursonaRenderer>(
+    fursona: &Fursona,
+    mut make_animation: impl FnMut(
+        &'static str,
+    ) -> Result<Option<AnimDictionaryEntry<T>>, FursonaInstanceError>,
+) -> Result<(Box<dyn Behavior>, AnimationStateMachine<T>), FursonaInstanceError> {
+    let mut anims = AnimationDictionary::new();
+    for name in ANIMATION_NAMES {
+        if let Some(entry) = make_animation(name)? {
+            anims.insert(name, entry);
+        }
+    }
+
+    let behavior = make_behavior(fursona, &anims)?;
+    let animations =
+        AnimationStateMachine::standard(&mut anims, fursona.crossfade_ms, fursona.walk_cycle_speed);
+    Ok((behavior, animations))
 }
 
 impl FursonaInstance {
-    pub fn new(_fursona: &Fursona, stage: &Stage) -> Self {
-        let mut texture_cache = TextureCache::new(stage);
-
-        let right = texture_cache.get("./jack_by_nal_cinnamonspots.png");
-        let left = texture_cache.get("./jack_by_nal_cinnamonspots_flipped.png");
-        let mut anim_dictionary = AnimationDictionary {
-            walk_left: Some(Box::new(move || {
-                Box::new(Animation2D::new(vec![Keyframe2D {
-                    duration_ms: 2000,
-                    image: right.clone(),
-                }]))
-            })),
-            walk_right: Some(Box::new(move || {
-                Box::new(Animation2D::new(vec![Keyframe2D {
-                    duration_ms: 2000,
-                    image: left.clone(),
-                }]))
-            })),
+    pub fn new(
+        fursona: &Fursona,
+        stage: &Stage,
+        texture_cache: &mut TextureCache,
+    ) -> Result<Self, FursonaInstanceError> {
+        let (behavior, rendering) = match (&fursona.model, &fursona.skeleton) {
+            (Some(model), _) => {
+                let model = Rc::new(Model::load(
+                    &model.path,
+                    model.height,
+                    &stage.display,
+                    &fursona.palette,
+                )?);
+                let (behavior, animations) =
+                    make_animations(fursona, |name| Ok(make_animation_3d(&model, name)))?;
+                let rendering = FursonaInstanceRendering::ThreeD {
+                    animations,
+                    renderer: FursonaRenderer3D::new(
+                        model.clone(),
+                        fursona.filter,
+                        fursona.effects.clone(),
+                    ),
+                };
+                (behavior, rendering)
+            }
+            (None, Some(path)) => {
+                let skeleton = Rc::new(Skeleton::load(
+                    path,
+                    &stage.display,
+                    texture_cache,
+                    &fursona.palette,
+                )?);
+                let (behavior, animations) =
+                    make_animations(fursona, |name| Ok(make_animation_skeletal(&skeleton, name)))?;
+                let rendering = FursonaInstanceRendering::Skeletal {
+                    animations,
+                    renderer: FursonaRendererSkeletal::new(
+                        skeleton.clone(),
+                        fursona.filter,
+                        fursona.effects.clone(),
+                    ),
+                };
+                (behavior, rendering)
+            }
+            (None, None) => {
+                let (behavior, animations) = make_animations(fursona, |name| {
+                    let keyframes = fursona.animations.get(name);
+                    Ok(make_animation_2d(
+                        keyframes,
+                        &fursona.palette,
+                        stage,
+                        texture_cache,
+                    )?)
+                })?;
+                let rendering = FursonaInstanceRendering::TwoD {
+                    animations,
+                    renderer: FursonaRenderer2D::new(fursona.filter, fursona.effects.clone()),
+                };
+                (behavior, rendering)
+            }
+        };
+
+        let monitors = stage.viewport.monitors();
+        let monitor = match fursona.monitor {
+            Some(monitor) if monitor >= monitors.len() => {
+                warn!(
+                    "{} is pinned to monitor {}, but there are only {}",
+                    fursona.name,
+                    monitor + 1,
+                    monitors.len()
+                );
+                None
+            }
+            monitor => monitor,
         };
+        let start = &monitors[monitor.unwrap_or(0)];
 
-        FursonaInstance {
+        // For bobbing in step with the walk
+        let walk_cycle_ms = match &rendering {
+            FursonaInstanceRendering::TwoD { animations, .. } => {
+                animations.duration_ms("walk_right")
+            }
+            FursonaInstanceRendering::Skeletal { animations, .. } => {
+                animations.duration_ms("walk_right")
+            }
+            FursonaInstanceRendering::ThreeD { animations, .. } => {
+                animations.duration_ms("walk_right")
+            }
+        };
+
+        let mut instance = FursonaInstance {
+            name: fursona.name.to_owned(),
             position: ViewportPoint {
-                x: stage.viewport.left() as f32,
-                y: stage.viewport.top() as f32,
-            },
-            width: 0.0,  // TODO
-            height: 0.0, // TODO
-            rendering: FursonaInstanceRendering::TwoD {
-                behavior: Box::new(PaceBehavior::new(&mut anim_dictionary).unwrap()),
-                renderer: FursonaRenderer2D::new(&stage),
+                x: start.left(),
+                y: start.top(),
             },
+            scale: fursona.scale as f32,
+            monitor,
+            behavior,
+            rendering,
+            motion: ProceduralMotion::new(
+                fursona.procedural_motion.clone(),
+                walk_cycle_ms,
+                fursona.walk_cycle_speed,
+            ),
+            effects: fursona.effects.clone(),
+            hovered: false,
+            events: Vec::new(),
+        };
+        // Starting from the top-left corner, with all of the first frame on
+        // screen
+        instance.move_within_span(instance.position.clone(), stage);
+
+        Ok(instance)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn state(&self) -> FursonaInstanceState {
+        let behavior = &self.behavior;
+        FursonaInstanceState {
+            name: self.name.to_owned(),
+            position: self.position.clone(),
+            behavior: behavior.name().to_owned(),
+            facing: behavior.facing(),
+        }
+    }
+
+    // Where this fursona is currently free to walk, given where it is now
+    fn walkable_span(&self, stage: &Stage) -> WalkableSpan {
+        let viewport = &stage.viewport;
+        match self.monitor {
+            Some(monitor) => viewport.walkable_span(monitor, true),
+            None => viewport.walkable_span(viewport.monitor_at(&self.position), false),
+        }
+    }
+
+    pub fn restore_state(&mut self, state: &FursonaInstanceState, stage: &Stage) {
+        // The monitors may have changed since the state was saved, so make
+        // sure we don't come back somewhere we can't be seen
+        self.move_within_span(state.position.clone(), stage);
+
+        // Only carry over behavior-specific state if we're still running the
+        // same behavior that was saved
+        if self.behavior.name() == state.behavior {
+            self.behavior.set_facing(state.facing);
+        }
+    }
+
+    // Moves to `position`, or as close to it as keeps the whole of the current
+    // frame within the walkable span
+    fn move_within_span(&mut self, position: ViewportPoint, stage: &Stage) {
+        // Which span that is can depend on where we are
+        self.position = position;
+        let span = self.walkable_span(stage);
+        let ((width, height), (anchor_x, anchor_y)) = self.frame_layout();
+
+        // Frames too big to fit are kept to the left and top edges
+        let x = self
+            .position
+            .x
+            .min(span.right() - (width - anchor_x))
+            .max(span.left() + anchor_x);
+        let y = self
+            .position
+            .y
+            .min(span.bottom_at(x) - (height - anchor_y))
+            .max(span.top_at(x) + anchor_y);
+        self.position = ViewportPoint { x, y };
+    }
+
+    // The size of the current frame and where its anchor is within it, as
+    // the animation has them
+    fn intrinsic_layout(&self) -> ((f32, f32), (f32, f32)) {
+        match &self.rendering {
+            FursonaInstanceRendering::TwoD { animations, .. } => {
+                (animations.intrinsic_dimensions(), animations.anchor())
+            }
+            FursonaInstanceRendering::Skeletal { animations, .. } => {
+                (animations.intrinsic_dimensions(), animations.anchor())
+            }
+            FursonaInstanceRendering::ThreeD { animations, .. } => {
+                (animations.intrinsic_dimensions(), animations.anchor())
+            }
         }
     }
 
+    // The same, but scaled up to how they're drawn
+    fn frame_layout(&self) -> ((f32, f32), (f32, f32)) {
+        let ((width, height), (anchor_x, anchor_y)) = self.intrinsic_layout();
+        (
+            (width * self.scale, height * self.scale),
+            (anchor_x * self.scale, anchor_y * self.scale),
+        )
+    }
+
+    // Where the current frame is drawn, placed so that its anchor is at our
+    // position
     pub fn bounding_box(&self) -> ViewportRect {
+        let ((width, height), (anchor_x, anchor_y)) = self.frame_layout();
         ViewportRect {
-            x: self.position.x,
-            y: self.position.y,
-            width: self.width,
-            height: self.height,
+            x: self.position.x - anchor_x,
+            y: self.position.y - anchor_y,
+            width,
+            height,
         }
     }
 
+    // The part of the screen that counts as the fursona, for hit testing
+    pub fn hitbox(&self) -> ViewportRect {
+        let hitbox = match &self.rendering {
+            FursonaInstanceRendering::TwoD { animations, .. } => animations.hitbox(),
+            FursonaInstanceRendering::Skeletal { animations, .. } => animations.hitbox(),
+            FursonaInstanceRendering::ThreeD { animations, .. } => animations.hitbox(),
+        };
+
+        let ((width, height), anchor) = self.intrinsic_layout();
+        let hitbox = hitbox.unwrap_or(FrameRect {
+            x: 0.0,
+            y: 0.0,
+            width,
+            height,
+        });
+
+        // Wherever the procedural motion has moved it to, in the frame's
+        // pixels
+        let transform = self.motion.transform(anchor);
+        let corners = [
+            (hitbox.x, hitbox.y),
+            (hitbox.x + hitbox.width, hitbox.y),
+            (hitbox.x, hitbox.y + hitbox.height),
+            (hitbox.x + hitbox.width, hitbox.y + hitbox.height),
+        ]
+        .map(|(x, y)| transform.apply(x, y));
+        let left = corners
+            .iter()
+            .map(|&(x, _)| x)
+            .fold(f32::INFINITY, f32::min);
+        let right = corners
+            .iter()
+            .map(|&(x, _)| x)
+            .fold(f32::NEG_INFINITY, f32::max);
+        let top = corners
+            .iter()
+            .map(|&(_, y)| y)
+            .fold(f32::INFINITY, f32::min);
+        let bottom = corners
+            .iter()
+            .map(|&(_, y)| y)
+            .fold(f32::NEG_INFINITY, f32::max);
+
+        let bounding_box = self.bounding_box();
+        ViewportRect {
+            x: bounding_box.x + left * self.scale,
+            y: bounding_box.y + top * self.scale,
+            width: (right - left) * self.scale,
+            height: (bottom - top) * self.scale,
+        }
+    }
+
+    fn renderer_mut(&mut self) -> &mut dyn FursonaRenderer {
+        match &mut self.rendering {
+            FursonaInstanceRendering::TwoD { renderer, .. } => renderer,
+            FursonaInstanceRendering::Skeletal { renderer, .. } => renderer,
+            FursonaInstanceRendering::ThreeD { renderer, .. } => renderer,
+        }
+    }
+
+    // Outlines the fursona while the mouse is over it
+    pub fn set_hovered(&mut self, hovered: bool) {
+        self.hovered = hovered;
+        self.renderer_mut().effects_mut().outline = hovered.then_some(HOVER_OUTLINE_COLOR);
+    }
+
     pub fn renderer(&self) -> &dyn FursonaRenderer {
         match &self.rendering {
             FursonaInstanceRendering::TwoD { renderer, .. } => renderer,
+            FursonaInstanceRendering::Skeletal { renderer, .. } => renderer,
+            FursonaInstanceRendering::ThreeD { renderer, .. } => renderer,
         }
     }
 
-    pub fn update<'a>(&mut self, delta_t_ms: u32, stage: &'a Stage) -> () {
+    #[instrument(level = "trace", skip_all, fields(name = %self.name))]
+    pub fn update(&mut self, delta_t_ms: u32, stage: &Stage, surfaces: &[Surface]) {
+        let (dimensions, anchor) = self.frame_layout();
+
         // Process the current behavior
         let behavior_context = BehaviorContext {
             position: self.position.clone(),
-            stage,
+            dimensions,
+            anchor,
+            span: self.walkable_span(stage),
+            surfaces,
+            events: &self.events,
+            effects: &self.effects,
         };
-        let result = match &mut self.rendering {
-            FursonaInstanceRendering::TwoD { behavior, renderer } => {
-                behavior.advance(delta_t_ms, renderer, behavior_context)
-            }
+        let result = self.behavior.advance(delta_t_ms, behavior_context);
+
+        // Whatever the behavior wants drawn, the hover outline still shows
+        let mut effects = result.effects.unwrap_or_else(|| self.effects.clone());
+        if self.hovered {
+            effects.outline = Some(HOVER_OUTLINE_COLOR);
+        }
+        *self.renderer_mut().effects_mut() = effects;
+
+        // Then let the animations catch up with what it's doing
+        self.events = match &mut self.rendering {
+            FursonaInstanceRendering::TwoD {
+                animations,
+                renderer,
+            } => animations.advance(delta_t_ms, renderer, &result.parameters),
+            FursonaInstanceRendering::Skeletal {
+                animations,
+                renderer,
+            } => animations.advance(delta_t_ms, renderer, &result.parameters),
+            FursonaInstanceRendering::ThreeD {
+                animations,
+                renderer,
+            } => animations.advance(delta_t_ms, renderer, &result.parameters),
         };
+        for event in &self.events {
+            debug!("{} reached a {event} frame", self.name);
+        }
+
+        // With procedural motion on top
+        self.motion.advance(delta_t_ms, &result.parameters);
+        let (_, anchor) = self.intrinsic_layout();
+        let transform = self.motion.transform(anchor);
+        self.renderer_mut().set_transform(transform);
 
-        self.position = result.bounding_box.position();
-        self.width = result.bounding_box.width;
-        self.height = result.bounding_box.height;
+        self.position = result.position;
     }
 }
@@ -0,0 +1,497 @@
+mod model_animation;
+mod model_error;
+mod model_node;
+mod model_pose;
+mod model_primitive;
+mod model_skin;
+mod model_vertex;
+mod node_transform;
+
+use crate::rendering::{AlphaMode, Color, Palette, TextureError, Transform3D};
+use glium::texture::{RawImage2d, Texture2d};
+use glium::Display;
+use gltf::animation::util::ReadOutputs;
+use gltf::animation::Interpolation;
+use gltf::mesh::Mode;
+use glutin::surface::WindowSurface;
+use image::RgbaImage;
+use std::path::Path;
+use std::rc::Rc;
+use tracing::warn;
+
+use model_animation::{ChannelInterpolation, ChannelProperty, ModelAnimation, ModelChannel};
+pub use model_error::ModelError;
+use model_node::ModelNode;
+pub use model_pose::ModelPose;
+use model_primitive::ModelPrimitive;
+use model_skin::ModelSkin;
+use model_vertex::ModelVertex;
+use node_transform::NodeTransform;
+
+// How far models turn towards the way they're walking, in degrees. Turning
+// all the way side-on tends to hide too much of them.
+pub const FACING_YAW: f32 = 60.0;
+
+// How much further than the rest pose animations are allowed to reach
+// towards or away from the viewer before they're cut off
+const DEPTH_SLACK: f32 = 2.0;
+
+// A 3D character with skinned meshes and the animations that move its
+// joints, loaded from glTF 2.0. Models are seen from the front, looking down
+// their -z axis with y up, the way glTF defines them, and are turned to face
+// left or right as they walk.
+pub struct Model {
+    nodes: Vec<ModelNode>,
+    // Every node, with parents before their children
+    order: Vec<usize>,
+    primitives: Vec<ModelPrimitive>,
+    skins: Vec<ModelSkin>,
+    animations: Vec<ModelAnimation>,
+    pixels_per_unit: f32,
+    // Around the rest pose facing either way, as [left, bottom, right, top]
+    // in model units
+    bounds: [f32; 4],
+    // How far from the origin the model can reach towards or away from the
+    // viewer, in model units
+    depth: f32,
+}
+
+// Converts a decoded image to RGBA and uploads it. Images in formats that
+// don't fit in eight bits per channel are skipped.
+fn upload_image(
+    display: &Display<WindowSurface>,
+    image: &gltf::image::Data,
+    palette: &Palette,
+) -> Result<Option<Rc<Texture2d>>, TextureError> {
+    use gltf::image::Format;
+
+    let pixels: Vec<u8> = match image.format {
+        Format::R8G8B8A8 => image.pixels.clone(),
+        Format::R8G8B8 => image
+            .pixels
+            .chunks_exact(3)
+            .flat_map(|pixel| [pixel[0], pixel[1], pixel[2], 255])
+            .collect(),
+        Format::R8G8 => image
+            .pixels
+            .chunks_exact(2)
+            .flat_map(|pixel| [pixel[0], pixel[0], pixel[0], pixel[1]])
+            .collect(),
+        Format::R8 => image
+            .pixels
+            .iter()
+            .flat_map(|&value| [value, value, value, 255])
+            .collect(),
+        format => {
+            warn!("Skipping a texture in the unsupported {format:?} format");
+            return Ok(None);
+        }
+    };
+
+    let mut rgba = match RgbaImage::from_raw(image.width, image.height, pixels) {
+        Some(rgba) => rgba,
+        None => {
+            warn!("Skipping a texture that's smaller than it says it is");
+            return Ok(None);
+        }
+    };
+    palette.apply(&mut rgba);
+
+    // glTF's texture coordinates start from the first row of the image, as
+    // OpenGL's do, so there's no need to flip it
+    let dimensions = rgba.dimensions();
+    let raw = RawImage2d::from_raw_rgba(rgba.into_raw(), dimensions);
+    Ok(Some(Rc::new(Texture2d::new(display, raw)?)))
+}
+
+impl Model {
+    // `height` is how tall the model's rest pose is drawn, in pixels.
+    // Textures are colored with the palette as they're loaded.
+    pub fn load(
+        path: &Path,
+        height: f32,
+        display: &Display<WindowSurface>,
+        palette: &Palette,
+    ) -> Result<Self, ModelError> {
+        let (document, buffers, images) =
+            gltf::import(path).map_err(|source| ModelError::Import {
+                path: path.to_owned(),
+                source,
+            })?;
+        let buffer = |buffer: gltf::Buffer| Some(&buffers[buffer.index()][..]);
+
+        let textures = images
+            .iter()
+            .map(|image| upload_image(display, image, palette))
+            .collect::<Result<Vec<_>, TextureError>>()?;
+
+        let mut nodes: Vec<ModelNode> = document
+            .nodes()
+            .map(|node| {
+                let (translation, rotation, scale) = node.transform().decomposed();
+                ModelNode {
+                    parent: None,
+                    rest: NodeTransform {
+                        translation,
+                        rotation,
+                        scale,
+                    },
+                }
+            })
+            .collect();
+        for node in document.nodes() {
+            for child in node.children() {
+                nodes[child.index()].parent = Some(node.index());
+            }
+        }
+
+        // Go down from the roots so that every parent is placed before its
+        // children
+        let mut order = Vec::with_capacity(nodes.len());
+        let mut stack: Vec<gltf::Node> = document
+            .nodes()
+            .filter(|node| nodes[node.index()].parent.is_none())
+            .collect();
+        while let Some(node) = stack.pop() {
+            order.push(node.index());
+            stack.extend(node.children());
+        }
+
+        let mut primitives = Vec::new();
+        for node in document.nodes() {
+            let mesh = match node.mesh() {
+                Some(mesh) => mesh,
+                None => continue,
+            };
+
+            for primitive in mesh.primitives() {
+                if primitive.mode() != Mode::Triangles {
+                    warn!(
+                        "Skipping part of mesh {}, since {:?} aren't supported",
+                        mesh.index(),
+                        primitive.mode()
+                    );
+                    continue;
+                }
+
+                let reader = primitive.reader(buffer);
+                let positions: Vec<[f32; 3]> = match reader.read_positions() {
+                    Some(positions) => positions.collect(),
+                    None => continue,
+                };
+
+                let material = primitive.material();
+                let pbr = material.pbr_metallic_roughness();
+                let base_color = pbr.base_color_texture();
+
+                let normals: Vec<[f32; 3]> = reader
+                    .read_normals()
+                    .map(|normals| normals.collect())
+                    .unwrap_or_default();
+                let tex_coords: Vec<[f32; 2]> = base_color
+                    .as_ref()
+                    .and_then(|info| reader.read_tex_coords(info.tex_coord()))
+                    .map(|tex_coords| tex_coords.into_f32().collect())
+                    .unwrap_or_default();
+                let joints: Vec<[u16; 4]> = reader
+                    .read_joints(0)
+                    .map(|joints| joints.into_u16().collect())
+                    .unwrap_or_default();
+                let weights: Vec<[f32; 4]> = reader
+                    .read_weights(0)
+                    .map(|weights| weights.into_f32().collect())
+                    .unwrap_or_default();
+
+                let vertices = positions
+                    .iter()
+                    .enumerate()
+                    .map(|(index, position)| ModelVertex {
+                        position: *position,
+                        normal: normals.get(index).copied().unwrap_or([0.0, 0.0, 1.0]),
+                        tex_coords: tex_coords.get(index).copied().unwrap_or([0.0, 0.0]),
+                        joints: joints.get(index).copied().unwrap_or([0; 4]),
+                        weights: weights.get(index).copied().unwrap_or([0.0; 4]),
+                    })
+                    .collect();
+
+                let indices = match reader.read_indices() {
+                    Some(indices) => indices.into_u32().collect(),
+                    None => (0..positions.len() as u32).collect(),
+                };
+
+                let [r, g, b, a] = pbr.base_color_factor();
+                primitives.push(ModelPrimitive {
+                    node: node.index(),
+                    // Only meshes with joints to move them are skinned
+                    skin: node
+                        .skin()
+                        .filter(|_| !joints.is_empty() && !weights.is_empty())
+                        .map(|skin| skin.index()),
+                    vertices,
+                    indices,
+                    texture: base_color.and_then(|info| {
+                        textures
+                            .get(info.texture().source().index())
+                            .cloned()
+                            .flatten()
+                    }),
+                    color: Color(r, g, b, a),
+                    alpha: match material.alpha_mode() {
+                        gltf::material::AlphaMode::Opaque => AlphaMode::Opaque,
+                        gltf::material::AlphaMode::Mask => {
+                            AlphaMode::Mask(material.alpha_cutoff().unwrap_or(0.5))
+                        }
+                        gltf::material::AlphaMode::Blend => AlphaMode::Blend,
+                    },
+                });
+            }
+        }
+
+        let skins = document
+            .skins()
+            .map(|skin| {
+                let joints: Vec<usize> = skin.joints().map(|joint| joint.index()).collect();
+                let inverse_binds = match skin.reader(buffer).read_inverse_bind_matrices() {
+                    Some(matrices) => matrices.map(|columns| Transform3D { columns }).collect(),
+                    None => vec![Transform3D::IDENTITY; joints.len()],
+                };
+                ModelSkin {
+                    joints,
+                    inverse_binds,
+                }
+            })
+            .collect();
+
+        let mut animations = Vec::new();
+        for animation in document.animations() {
+            let mut duration: f32 = 0.0;
+            let mut channels = Vec::new();
+            let mut is_simplified = false;
+            for channel in animation.channels() {
+                let reader = channel.reader(buffer);
+                let times: Vec<f32> = match reader.read_inputs() {
+                    Some(times) => times.collect(),
+                    None => continue,
+                };
+                let (property, mut values): (ChannelProperty, Vec<[f32; 4]>) =
+                    match reader.read_outputs() {
+                        Some(ReadOutputs::Translations(translations)) => (
+                            ChannelProperty::Translation,
+                            translations.map(|[x, y, z]| [x, y, z, 0.0]).collect(),
+                        ),
+                        Some(ReadOutputs::Rotations(rotations)) => {
+                            (ChannelProperty::Rotation, rotations.into_f32().collect())
+                        }
+                        Some(ReadOutputs::Scales(scales)) => (
+                            ChannelProperty::Scale,
+                            scales.map(|[x, y, z]| [x, y, z, 0.0]).collect(),
+                        ),
+                        // Morph targets aren't supported
+                        _ => continue,
+                    };
+
+                let interpolation = match channel.sampler().interpolation() {
+                    Interpolation::Linear => ChannelInterpolation::Linear,
+                    Interpolation::Step => ChannelInterpolation::Step,
+                    // Each keyframe comes with tangents either side of its
+                    // value. Only the value is kept, and it's blended in a
+                    // straight line.
+                    Interpolation::CubicSpline => {
+                        is_simplified = true;
+                        values = values.chunks_exact(3).map(|key| key[1]).collect();
+                        ChannelInterpolation::Linear
+                    }
+                };
+
+                duration = times
+                    .iter()
+                    .fold(duration, |duration, &at| duration.max(at));
+                channels.push(ModelChannel {
+                    node: channel.target().node().index(),
+                    property,
+                    interpolation,
+                    times,
+                    values,
+                });
+            }
+
+            let name = animation.name().unwrap_or_default();
+            if is_simplified {
+                warn!(
+                    "Animation {name} eases between keyframes with cubic splines, which aren't \
+                     supported, so it'll move in straight lines between them instead"
+                );
+            }
+
+            animations.push(ModelAnimation {
+                name: name.to_owned(),
+                duration,
+                channels,
+            });
+        }
+
+        let mut model = Self {
+            nodes,
+            order,
+            primitives,
+            skins,
+            animations,
+            pixels_per_unit: 1.0,
+            bounds: [0.0; 4],
+            depth: 1.0,
+        };
+        model.measure(height);
+        Ok(model)
+    }
+
+    // Fits the frame around the rest pose facing either way, scaled so that
+    // it's `height` pixels tall
+    fn measure(&mut self, height: f32) {
+        let rest = ModelPose {
+            nodes: self.nodes.iter().map(|node| node.rest.clone()).collect(),
+            yaw: 0.0,
+        };
+        let world = self.node_transforms(&rest);
+
+        let mut bounds: Option<[f32; 4]> = None;
+        let mut depth: f32 = 0.0;
+        for primitive in self.primitives.iter() {
+            for (position, _) in self.posed_vertices(primitive, &world) {
+                let [x, y, z] = position;
+                depth = depth.max((x * x + y * y + z * z).sqrt());
+
+                for yaw in [-FACING_YAW, FACING_YAW] {
+                    let [x, y, _] = Transform3D::rotation_y(yaw).apply_point(position);
+                    let [left, bottom, right, top] = bounds.get_or_insert([x, y, x, y]);
+                    *left = left.min(x);
+                    *bottom = bottom.min(y);
+                    *right = right.max(x);
+                    *top = top.max(y);
+                }
+            }
+        }
+
+        self.bounds = bounds.unwrap_or([0.0; 4]);
+        self.depth = (depth * DEPTH_SLACK).max(f32::EPSILON);
+        let [_, bottom, _, top] = self.bounds;
+        if top > bottom {
+            self.pixels_per_unit = height / (top - bottom);
+        }
+    }
+
+    // Animations are matched regardless of case, since exporters tend to
+    // capitalize them
+    pub fn animation_index(&self, name: &str) -> Option<usize> {
+        self.animations
+            .iter()
+            .position(|animation| animation.name.eq_ignore_ascii_case(name))
+    }
+
+    // In seconds
+    pub fn duration(&self, animation: usize) -> f32 {
+        self.animations[animation].duration
+    }
+
+    // Where everything is `time` seconds into the animation, turned to `yaw`
+    pub fn pose(&self, animation: usize, time: f32, yaw: f32) -> ModelPose {
+        let mut nodes: Vec<NodeTransform> =
+            self.nodes.iter().map(|node| node.rest.clone()).collect();
+        for channel in self.animations[animation].channels.iter() {
+            if let Some(node) = nodes.get_mut(channel.node) {
+                channel.apply(time, node);
+            }
+        }
+
+        ModelPose { nodes, yaw }
+    }
+
+    // From each node's space to the model's, in the pose
+    pub fn node_transforms(&self, pose: &ModelPose) -> Vec<Transform3D> {
+        let mut world = vec![Transform3D::IDENTITY; self.nodes.len()];
+        for &index in self.order.iter() {
+            let local = pose.nodes[index].to_transform();
+            world[index] = match self.nodes[index].parent {
+                Some(parent) => world[parent] * local,
+                None => local,
+            };
+        }
+        world
+    }
+
+    pub fn primitives(&self) -> &[ModelPrimitive] {
+        &self.primitives
+    }
+
+    // Every vertex of the primitive moved into place, as its position and
+    // normal in the model's space, given the transforms of every node
+    pub fn posed_vertices<'a>(
+        &'a self,
+        primitive: &'a ModelPrimitive,
+        world: &'a [Transform3D],
+    ) -> impl Iterator<Item = ([f32; 3], [f32; 3])> + 'a {
+        // Skinned meshes ignore where their own node is, as glTF asks
+        let joints: Option<Vec<Transform3D>> = primitive.skin.map(|skin| {
+            let skin = &self.skins[skin];
+            skin.joints
+                .iter()
+                .zip(skin.inverse_binds.iter())
+                .map(|(&joint, inverse_bind)| world[joint] * *inverse_bind)
+                .collect()
+        });
+        let node = world[primitive.node];
+
+        primitive.vertices.iter().map(move |vertex| match &joints {
+            Some(joints) => {
+                let mut position = [0.0; 3];
+                let mut normal = [0.0; 3];
+                for (&joint, &weight) in vertex.joints.iter().zip(vertex.weights.iter()) {
+                    let joint = match joints.get(joint as usize) {
+                        Some(joint) if weight > 0.0 => joint,
+                        _ => continue,
+                    };
+                    let moved = joint.apply_point(vertex.position);
+                    let turned = joint.apply_vector(vertex.normal);
+                    for axis in 0..3 {
+                        position[axis] += moved[axis] * weight;
+                        normal[axis] += turned[axis] * weight;
+                    }
+                }
+                (position, normal)
+            }
+            None => (
+                node.apply_point(vertex.position),
+                node.apply_vector(vertex.normal),
+            ),
+        })
+    }
+
+    // The size of the frame around the rest pose, in pixels
+    pub fn dimensions(&self) -> (f32, f32) {
+        let [left, bottom, right, top] = self.bounds;
+        (
+            (right - left) * self.pixels_per_unit,
+            (top - bottom) * self.pixels_per_unit,
+        )
+    }
+
+    // Where the model's origin is within that frame, from its top-left corner
+    // with y pointing down
+    pub fn anchor(&self) -> (f32, f32) {
+        let [left, _, _, top] = self.bounds;
+        (-left * self.pixels_per_unit, top * self.pixels_per_unit)
+    }
+
+    // From the model's space, turned to `yaw`, to pixels from the top-left of
+    // the frame with y pointing down. z becomes the depth, from 0.0 nearest
+    // the viewer to 1.0 furthest away.
+    pub fn to_frame(&self, yaw: f32) -> Transform3D {
+        let (anchor_x, anchor_y) = self.anchor();
+        Transform3D::translation(anchor_x, anchor_y, 0.5)
+            * Transform3D::scale(
+                self.pixels_per_unit,
+                -self.pixels_per_unit,
+                -0.5 / self.depth,
+            )
+            * Transform3D::rotation_y(yaw)
+    }
+}
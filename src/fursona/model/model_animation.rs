@@ -0,0 +1,75 @@
+use super::node_transform::{lerp, nlerp, NodeTransform};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChannelProperty {
+    Translation,
+    Rotation,
+    Scale,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChannelInterpolation {
+    Linear,
+    Step,
+}
+
+// Keyframes for one property of one node
+pub struct ModelChannel {
+    pub node: usize,
+    pub property: ChannelProperty,
+    pub interpolation: ChannelInterpolation,
+    // In seconds, in order
+    pub times: Vec<f32>,
+    // One for each time. Translations and scales leave the last one unused.
+    pub values: Vec<[f32; 4]>,
+}
+
+impl ModelChannel {
+    // The value at `time` seconds in, holding the first and last values
+    // before and after the keyframes
+    pub fn sample(&self, time: f32) -> Option<[f32; 4]> {
+        let next = match self.times.iter().position(|at| *at > time) {
+            Some(0) => return self.values.first().copied(),
+            Some(next) => next,
+            None => return self.values.last().copied(),
+        };
+        let previous = next - 1;
+        let (from, to) = (*self.values.get(previous)?, *self.values.get(next)?);
+
+        if self.interpolation == ChannelInterpolation::Step {
+            return Some(from);
+        }
+
+        let span = self.times[next] - self.times[previous];
+        let t = if span > 0.0 {
+            (time - self.times[previous]) / span
+        } else {
+            1.0
+        };
+        Some(match self.property {
+            ChannelProperty::Rotation => nlerp(from, to, t),
+            _ => lerp(from, to, t),
+        })
+    }
+
+    pub fn apply(&self, time: f32, transform: &mut NodeTransform) {
+        let value = match self.sample(time) {
+            Some(value) => value,
+            None => return,
+        };
+
+        let [x, y, z, _] = value;
+        match self.property {
+            ChannelProperty::Translation => transform.translation = [x, y, z],
+            ChannelProperty::Rotation => transform.rotation = value,
+            ChannelProperty::Scale => transform.scale = [x, y, z],
+        }
+    }
+}
+
+pub struct ModelAnimation {
+    pub name: String,
+    // In seconds
+    pub duration: f32,
+    pub channels: Vec<ModelChannel>,
+}
@@ -0,0 +1,11 @@
+use crate::rendering::TextureError;
+use std::path::PathBuf;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ModelError {
+    #[error("couldn't load model {}: {source}", path.display())]
+    Import { path: PathBuf, source: gltf::Error },
+    #[error(transparent)]
+    Texture(#[from] TextureError),
+}
@@ -0,0 +1,7 @@
+use super::node_transform::NodeTransform;
+
+pub struct ModelNode {
+    pub parent: Option<usize>,
+    // Where the node is when no animation moves it
+    pub rest: NodeTransform,
+}
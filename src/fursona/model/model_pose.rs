@@ -0,0 +1,26 @@
+use super::node_transform::NodeTransform;
+
+// Where every node of a model is at some point in an animation, and which
+// way the whole model is turned
+#[derive(Clone, Debug)]
+pub struct ModelPose {
+    pub nodes: Vec<NodeTransform>,
+    // In degrees around the vertical axis, from facing the viewer
+    pub yaw: f32,
+}
+
+impl ModelPose {
+    // Part of the way from this pose to the other, so that switching
+    // animations (and turning around) can be smoothed over
+    pub fn blend(&self, other: &ModelPose, t: f32) -> ModelPose {
+        ModelPose {
+            nodes: self
+                .nodes
+                .iter()
+                .zip(other.nodes.iter())
+                .map(|(from, to)| from.blend(to, t))
+                .collect(),
+            yaw: self.yaw + (other.yaw - self.yaw) * t,
+        }
+    }
+}
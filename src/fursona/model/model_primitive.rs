@@ -0,0 +1,18 @@
+use super::model_vertex::ModelVertex;
+use crate::rendering::{AlphaMode, Color};
+use glium::texture::Texture2d;
+use std::rc::Rc;
+
+// A piece of a mesh with a single material
+pub struct ModelPrimitive {
+    // The node the mesh hangs off, which places it when it isn't skinned
+    pub node: usize,
+    pub skin: Option<usize>,
+    pub vertices: Vec<ModelVertex>,
+    // Every three make up a triangle
+    pub indices: Vec<u32>,
+    // The material's base color
+    pub texture: Option<Rc<Texture2d>>,
+    pub color: Color,
+    pub alpha: AlphaMode,
+}
@@ -0,0 +1,9 @@
+use crate::rendering::Transform3D;
+
+pub struct ModelSkin {
+    // The nodes that act as this skin's joints
+    pub joints: Vec<usize>,
+    // For each joint, from the model's space to the joint's, as the mesh was
+    // bound to it
+    pub inverse_binds: Vec<Transform3D>,
+}
@@ -0,0 +1,10 @@
+#[derive(Clone, Debug)]
+pub struct ModelVertex {
+    pub position: [f32; 3],
+    pub normal: [f32; 3],
+    pub tex_coords: [f32; 2],
+    // Up to four of the skin's joints that move this vertex, and how much
+    // each one counts. Unused without a skin.
+    pub joints: [u16; 4],
+    pub weights: [f32; 4],
+}
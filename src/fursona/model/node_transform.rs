@@ -0,0 +1,56 @@
+use crate::rendering::Transform3D;
+
+// Where a node is relative to its parent, in the pieces glTF animates
+// separately
+#[derive(Clone, Debug)]
+pub struct NodeTransform {
+    pub translation: [f32; 3],
+    // A unit quaternion, as [x, y, z, w]
+    pub rotation: [f32; 4],
+    pub scale: [f32; 3],
+}
+
+pub fn lerp<const N: usize>(from: [f32; N], to: [f32; N], t: f32) -> [f32; N] {
+    let mut result = from;
+    for (value, to) in result.iter_mut().zip(to) {
+        *value += (to - *value) * t;
+    }
+    result
+}
+
+// Blends between two rotations the short way around. Not quite at an even
+// speed like a proper slerp, but close enough between keyframes.
+pub fn nlerp(from: [f32; 4], to: [f32; 4], t: f32) -> [f32; 4] {
+    let dot: f32 = from.iter().zip(to).map(|(a, b)| a * b).sum();
+    let to = if dot < 0.0 {
+        to.map(|value| -value)
+    } else {
+        to
+    };
+
+    let result = lerp(from, to, t);
+    let length = result.iter().map(|value| value * value).sum::<f32>().sqrt();
+    if length > 0.0 {
+        result.map(|value| value / length)
+    } else {
+        from
+    }
+}
+
+impl NodeTransform {
+    pub fn to_transform(&self) -> Transform3D {
+        let [x, y, z] = self.translation;
+        let [scale_x, scale_y, scale_z] = self.scale;
+        Transform3D::translation(x, y, z)
+            * Transform3D::rotation(self.rotation)
+            * Transform3D::scale(scale_x, scale_y, scale_z)
+    }
+
+    pub fn blend(&self, other: &NodeTransform, t: f32) -> NodeTransform {
+        NodeTransform {
+            translation: lerp(self.translation, other.translation, t),
+            rotation: nlerp(self.rotation, other.rotation, t),
+            scale: lerp(self.scale, other.scale, t),
+        }
+    }
+}
@@ -1,13 +1,58 @@
+mod crossfade;
 pub mod renderer_2d;
+pub mod renderer_3d;
+pub mod renderer_skeletal;
 
-use crate::rendering::RendererRect;
-use glium::{DrawParameters, Frame};
+use crate::rendering::{Renderer, RendererRect, SpriteEffects, Transform2D};
+
+use crossfade::Crossfade;
+
+// How big the drop shadow is compared to the fursona's width
+const SHADOW_WIDTH: f32 = 0.8;
+const SHADOW_ASPECT_RATIO: f32 = 0.25;
 
 pub trait FursonaRenderer {
-    fn draw(
-        &self,
-        frame: &mut Frame,
-        rect: RendererRect,
-        base_draw_parameters: &DrawParameters,
-    ) -> ();
+    // Queues this fursona up to be drawn into the given rect. Nothing is
+    // actually drawn until the stage flushes the renderer at the end of the
+    // frame, so that every fursona can be drawn together.
+    fn draw(&self, renderer: &mut Renderer, rect: RendererRect);
+
+    fn effects_mut(&mut self) -> &mut SpriteEffects;
+
+    // Moves, turns and stretches the fursona from where its animations put
+    // it. In the pixels of the current frame, from its top-left corner with y
+    // pointing down.
+    fn set_transform(&mut self, transform: Transform2D);
+
+    // Starts fading out whatever is currently shown, underneath whatever the
+    // animations draw from here on.
+    fn start_crossfade(&mut self);
+    // From 0.0 to 1.0. The crossfade is over once it reaches 1.0.
+    fn set_crossfade_progress(&mut self, progress: f32);
+}
+
+// Where the drop shadow goes for a fursona drawn into the given rect: a flat
+// ellipse centered on the bottom edge, where their feet meet the ground.
+pub fn shadow_rect(rect: &RendererRect) -> RendererRect {
+    let pixel_width = rect.pixel_width * SHADOW_WIDTH;
+    let pixel_height = pixel_width * SHADOW_ASPECT_RATIO;
+
+    // Renderer rects measure from 0.0 to 1.0 across the whole viewport, but
+    // positions go from -1.0 to 1.0, hence the doubling
+    let width = rect.width * SHADOW_WIDTH;
+    let height = if rect.pixel_height > 0.0 {
+        pixel_height * rect.height / rect.pixel_height
+    } else {
+        0.0
+    };
+    let bottom = rect.y - rect.height * 2.0;
+
+    RendererRect {
+        x: rect.x + (rect.width - width),
+        y: bottom + height,
+        width,
+        height,
+        pixel_width,
+        pixel_height,
+    }
 }
@@ -0,0 +1,32 @@
+// What a renderer was showing when a crossfade started, kept around to fade
+// out underneath whatever its animations show from then on.
+pub struct Crossfade<T> {
+    // What's being faded out, and how far through fading it we are
+    previous: Option<(T, f32)>,
+}
+
+impl<T: Clone> Crossfade<T> {
+    pub fn new() -> Self {
+        Self { previous: None }
+    }
+
+    // Nothing to fade out until something has been shown
+    pub fn start(&mut self, shown: Option<&T>) {
+        self.previous = shown.map(|shown| (shown.clone(), 0.0));
+    }
+
+    // From 0.0 to 1.0. The crossfade is over once it reaches 1.0.
+    pub fn set_progress(&mut self, progress: f32) {
+        if progress >= 1.0 {
+            self.previous = None;
+        } else if let Some((_, current)) = &mut self.previous {
+            *current = progress;
+        }
+    }
+
+    pub fn previous(&self) -> Option<(&T, f32)> {
+        self.previous
+            .as_ref()
+            .map(|(previous, progress)| (previous, *progress))
+    }
+}
@@ -1,148 +1,107 @@
-use std::ops::Deref;
-use std::rc::Rc;
-
-use crate::stage::Stage;
-
-use super::{FursonaRenderer, RendererRect};
-use glium::index::{NoIndices, PrimitiveType};
-use glium::texture::CompressedTexture2d;
-use glium::{implement_vertex, uniform, DrawParameters, Frame, Program, Surface, VertexBuffer};
-
-#[derive(Copy, Clone)]
-struct Vertex {
-    position: [f32; 2],
-    tex_coords: [f32; 2],
-}
-implement_vertex!(Vertex, position, tex_coords);
+use super::{shadow_rect, Crossfade, FursonaRenderer, RendererRect};
+use crate::rendering::{AtlasRegion, Renderer, SpriteEffects, TextureFilter, Transform2D};
 
 pub struct FursonaRenderer2D {
-    index_buffer: NoIndices,
-    program: Program,
-    texture: Option<Rc<CompressedTexture2d>>,
-    vertex_buffer: VertexBuffer<Vertex>,
-}
-
-const VERTEX_SHADER_SOURCE: &str = r#"
-#version 100
-uniform lowp mat4 matrix;
-attribute lowp vec2 position;
-attribute lowp vec2 tex_coords;
-varying lowp vec2 v_tex_coords;
-
-void main() {
-    gl_Position = matrix * vec4(position, 0.0, 1.0);
-    v_tex_coords = tex_coords;
-}
-"#;
-
-const FRAGMENT_SHADER_SOURCE: &str = r#"
-#version 100
-uniform lowp sampler2D tex;
-varying lowp vec2 v_tex_coords;
-
-void main() {
-    gl_FragColor = texture2D(tex, v_tex_coords);
+    texture: Option<AtlasRegion>,
+    // In the texture's pixels, from its top-left corner
+    anchor: (f32, f32),
+    filter: TextureFilter,
+    effects: SpriteEffects,
+    transform: Transform2D,
+    // Fading out the frame shown before, along with its anchor
+    crossfade: Crossfade<(AtlasRegion, (f32, f32))>,
 }
-"#;
 
 impl FursonaRenderer2D {
-    pub fn new(stage: &Stage) -> Self {
-        let left = 0.0f32;
-        let top = 0.0f32;
-        let right = 1.0f32;
-        let bottom = -1.0f32;
-
-        let vertex_buffer = VertexBuffer::new(
-            &stage.display,
-            &[
-                Vertex {
-                    // BL
-                    position: [left, bottom],
-                    tex_coords: [0.0, 0.0],
-                },
-                Vertex {
-                    // BR
-                    position: [right, bottom],
-                    tex_coords: [1.0, 0.0],
-                },
-                Vertex {
-                    // TR
-                    position: [right, top],
-                    tex_coords: [1.0, 1.0],
-                },
-                Vertex {
-                    // TR
-                    position: [right, top],
-                    tex_coords: [1.0, 1.0],
-                },
-                Vertex {
-                    // TL
-                    position: [left, top],
-                    tex_coords: [0.0, 1.0],
-                },
-                Vertex {
-                    // BL
-                    position: [left, bottom],
-                    tex_coords: [0.0, 0.0],
-                },
-            ],
-        )
-        .unwrap();
-
-        let index_buffer = NoIndices(PrimitiveType::TrianglesList);
-
-        let program = Program::from_source(
-            &stage.display,
-            VERTEX_SHADER_SOURCE,
-            FRAGMENT_SHADER_SOURCE,
-            None,
-        )
-        .unwrap();
-
+    pub fn new(filter: TextureFilter, effects: SpriteEffects) -> Self {
         Self {
-            vertex_buffer,
-            index_buffer,
-            program,
             texture: None,
+            anchor: (0.0, 0.0),
+            filter,
+            effects,
+            transform: Transform2D::IDENTITY,
+            crossfade: Crossfade::new(),
         }
     }
 
-    pub fn set_texture(&mut self, texture: Rc<CompressedTexture2d>) {
+    pub fn set_texture(&mut self, texture: AtlasRegion, anchor: (f32, f32)) {
         self.texture = Some(texture);
+        self.anchor = anchor;
     }
 }
 
 impl FursonaRenderer for FursonaRenderer2D {
-    fn draw(
-        &self,
-        frame: &mut Frame,
-        rect: RendererRect,
-        base_draw_parameters: &DrawParameters,
-    ) -> () {
-        let texture = self.texture.as_ref().unwrap();
+    fn draw(&self, renderer: &mut Renderer, rect: RendererRect) {
+        // Until an animation has given us a frame there's nothing to show
+        let texture = match &self.texture {
+            Some(texture) => texture,
+            None => return,
+        };
+
+        if let Some(shadow) = &self.effects.shadow {
+            renderer.draw_shadow(shadow_rect(&rect), shadow);
+        }
+
+        // The rect is the current frame scaled up to device pixels. The
+        // frame being faded out is drawn at the same scale, moved so that its
+        // anchor lines up with the current frame's.
+        let (width, height) = texture.dimensions();
+        if width == 0 || height == 0 {
+            return;
+        }
+        let transform = Transform2D::scale(
+            rect.pixel_width / width as f32,
+            rect.pixel_height / height as f32,
+        ) * self.transform;
+
+        match self.crossfade.previous() {
+            Some(((previous, (previous_x, previous_y)), progress)) => {
+                let (anchor_x, anchor_y) = self.anchor;
+                let previous_transform = transform
+                    * Transform2D::translation(anchor_x - previous_x, anchor_y - previous_y);
+                let faded = |alpha: f32| SpriteEffects {
+                    tint: self.effects.tint.alpha(self.effects.tint.3 * alpha),
+                    ..self.effects.clone()
+                };
+                renderer.draw_sprite_transformed(
+                    previous,
+                    rect.clone(),
+                    &previous_transform,
+                    &faded(1.0 - progress),
+                    self.filter,
+                );
+                renderer.draw_sprite_transformed(
+                    texture,
+                    rect,
+                    &transform,
+                    &faded(progress),
+                    self.filter,
+                );
+            }
+            None => renderer.draw_sprite_transformed(
+                texture,
+                rect,
+                &transform,
+                &self.effects,
+                self.filter,
+            ),
+        }
+    }
+
+    fn effects_mut(&mut self) -> &mut SpriteEffects {
+        &mut self.effects
+    }
 
-        // Multiply size by 2.0 because the vertices are sized to take up
-        // one quadrant of the renderer system, but the incoming rect size
-        // goes from 0.0 -> 1.0 for the whole coordinate system.
-        let scale_x = rect.width * 2.0;
-        let scale_y = rect.height * 2.0;
+    fn set_transform(&mut self, transform: Transform2D) {
+        self.transform = transform;
+    }
+
+    fn start_crossfade(&mut self) {
+        let shown = self.texture.clone().map(|texture| (texture, self.anchor));
+        self.crossfade.start(shown.as_ref());
+    }
 
-        frame
-            .draw(
-                &self.vertex_buffer,
-                &self.index_buffer,
-                &self.program,
-                &uniform! {
-                    matrix: [
-                        [ scale_x,     0.0, 0.0, 0.0],
-                        [     0.0, scale_y, 0.0, 0.0],
-                        [     0.0,     0.0, 1.0, 0.0],
-                        [  rect.x,  rect.y, 0.0, 1.0f32],
-                    ],
-                    tex: texture.deref(),
-                },
-                base_draw_parameters,
-            )
-            .unwrap();
+    fn set_crossfade_progress(&mut self, progress: f32) {
+        self.crossfade.set_progress(progress);
     }
 }
@@ -0,0 +1,135 @@
+use super::{super::model::Model, shadow_rect, Crossfade, FursonaRenderer, RendererRect};
+use crate::fursona::model::ModelPose;
+use crate::rendering::{
+    Mesh, MeshVertex, Renderer, SpriteEffects, TextureFilter, Transform2D, Transform3D,
+};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+// Draws a 3D model in whatever pose its animation last left it in, seen
+// straight on with no perspective, so that it sits among 2D fursona.
+pub struct FursonaRenderer3D {
+    model: Rc<Model>,
+    pose: Option<ModelPose>,
+    filter: TextureFilter,
+    effects: SpriteEffects,
+    transform: Transform2D,
+    // The pose being blended away from
+    crossfade: Crossfade<ModelPose>,
+    // Each primitive's vertices as they were last posed, kept from one frame
+    // to the next so that posing doesn't need to allocate
+    vertices: RefCell<Vec<Vec<MeshVertex>>>,
+}
+
+impl FursonaRenderer3D {
+    pub fn new(model: Rc<Model>, filter: TextureFilter, effects: SpriteEffects) -> Self {
+        let vertices = model
+            .primitives()
+            .iter()
+            .map(|primitive| Vec::with_capacity(primitive.vertices.len()))
+            .collect();
+
+        Self {
+            model,
+            pose: None,
+            filter,
+            effects,
+            transform: Transform2D::IDENTITY,
+            crossfade: Crossfade::new(),
+            vertices: RefCell::new(vertices),
+        }
+    }
+
+    pub fn set_pose(&mut self, pose: ModelPose) {
+        self.pose = Some(pose);
+    }
+
+    fn draw_pose(&self, renderer: &mut Renderer, rect: RendererRect, pose: &ModelPose) {
+        // The rect is the frame around the rest pose, scaled up to device
+        // pixels
+        let (width, _) = self.model.dimensions();
+        if width <= 0.0 {
+            return;
+        }
+        let scale = rect.pixel_width / width;
+        let to_frame = self.model.to_frame(pose.yaw);
+        let to_rect = Transform2D::scale(scale, scale) * self.transform;
+        // Normals only need turning, with y still up for the lighting
+        let turn = Transform3D::rotation_y(pose.yaw);
+
+        let world = self.model.node_transforms(pose);
+        let mut vertices = self.vertices.borrow_mut();
+        for (primitive, vertices) in self.model.primitives().iter().zip(vertices.iter_mut()) {
+            vertices.clear();
+            vertices.extend(
+                self.model
+                    .posed_vertices(primitive, &world)
+                    .zip(primitive.vertices.iter())
+                    .map(|((position, normal), vertex)| {
+                        let [x, y, depth] = to_frame.apply_point(position);
+                        let (x, y) = to_rect.apply(x, y);
+                        MeshVertex {
+                            position: [x, y, depth],
+                            normal: turn.apply_vector(normal),
+                            tex_coords: vertex.tex_coords,
+                        }
+                    }),
+            );
+        }
+
+        let meshes: Vec<Mesh> = self
+            .model
+            .primitives()
+            .iter()
+            .zip(vertices.iter())
+            .map(|(primitive, vertices)| Mesh {
+                vertices,
+                indices: &primitive.indices,
+                texture: primitive.texture.as_ref(),
+                color: primitive.color.clone(),
+                alpha: primitive.alpha,
+            })
+            .collect();
+
+        renderer.draw_meshes(&meshes, rect, &self.effects, self.filter);
+    }
+}
+
+impl FursonaRenderer for FursonaRenderer3D {
+    fn draw(&self, renderer: &mut Renderer, rect: RendererRect) {
+        // Until an animation has posed us there's nothing to show
+        let pose = match &self.pose {
+            Some(pose) => pose,
+            None => return,
+        };
+
+        if let Some(shadow) = &self.effects.shadow {
+            renderer.draw_shadow(shadow_rect(&rect), shadow);
+        }
+
+        // Rather than fading, models move from one pose to the other, which
+        // also turns them around smoothly
+        match self.crossfade.previous() {
+            Some((previous, progress)) => {
+                self.draw_pose(renderer, rect, &previous.blend(pose, progress))
+            }
+            None => self.draw_pose(renderer, rect, pose),
+        }
+    }
+
+    fn effects_mut(&mut self) -> &mut SpriteEffects {
+        &mut self.effects
+    }
+
+    fn set_transform(&mut self, transform: Transform2D) {
+        self.transform = transform;
+    }
+
+    fn start_crossfade(&mut self) {
+        self.crossfade.start(self.pose.as_ref());
+    }
+
+    fn set_crossfade_progress(&mut self, progress: f32) {
+        self.crossfade.set_progress(progress);
+    }
+}
@@ -0,0 +1,103 @@
+use super::{super::skeleton::Skeleton, shadow_rect, Crossfade, FursonaRenderer, RendererRect};
+use crate::fursona::skeleton::SkeletonPose;
+use crate::rendering::{Renderer, SpriteEffects, TextureFilter, Transform2D};
+use std::rc::Rc;
+
+// Draws a skeleton in whatever pose its animation last left it in.
+pub struct FursonaRendererSkeletal {
+    skeleton: Rc<Skeleton>,
+    pose: Option<SkeletonPose>,
+    filter: TextureFilter,
+    effects: SpriteEffects,
+    transform: Transform2D,
+    // Fading out the pose shown before
+    crossfade: Crossfade<SkeletonPose>,
+}
+
+impl FursonaRendererSkeletal {
+    pub fn new(skeleton: Rc<Skeleton>, filter: TextureFilter, effects: SpriteEffects) -> Self {
+        Self {
+            skeleton,
+            pose: None,
+            filter,
+            effects,
+            transform: Transform2D::IDENTITY,
+            crossfade: Crossfade::new(),
+        }
+    }
+
+    pub fn set_pose(&mut self, pose: SkeletonPose) {
+        self.pose = Some(pose);
+    }
+
+    fn draw_pose(
+        &self,
+        renderer: &mut Renderer,
+        rect: &RendererRect,
+        pose: &SkeletonPose,
+        effects: &SpriteEffects,
+    ) {
+        // The rect is the box around the setup pose, scaled up to device
+        // pixels
+        let (width, _) = self.skeleton.dimensions();
+        if width <= 0.0 {
+            return;
+        }
+        let scale = rect.pixel_width / width;
+        let to_rect = Transform2D::scale(scale, scale)
+            * self.transform
+            * self.skeleton.to_frame(pose.mirrored);
+
+        for (transform, attachment) in self.skeleton.attachment_transforms(pose) {
+            renderer.draw_sprite_transformed(
+                &attachment.image,
+                rect.clone(),
+                &(to_rect * transform),
+                effects,
+                self.filter,
+            );
+        }
+    }
+}
+
+impl FursonaRenderer for FursonaRendererSkeletal {
+    fn draw(&self, renderer: &mut Renderer, rect: RendererRect) {
+        // Until an animation has posed us there's nothing to show
+        let pose = match &self.pose {
+            Some(pose) => pose,
+            None => return,
+        };
+
+        if let Some(shadow) = &self.effects.shadow {
+            renderer.draw_shadow(shadow_rect(&rect), shadow);
+        }
+
+        match self.crossfade.previous() {
+            Some((previous, progress)) => {
+                let faded = |alpha: f32| SpriteEffects {
+                    tint: self.effects.tint.alpha(self.effects.tint.3 * alpha),
+                    ..self.effects.clone()
+                };
+                self.draw_pose(renderer, &rect, previous, &faded(1.0 - progress));
+                self.draw_pose(renderer, &rect, pose, &faded(progress));
+            }
+            None => self.draw_pose(renderer, &rect, pose, &self.effects),
+        }
+    }
+
+    fn effects_mut(&mut self) -> &mut SpriteEffects {
+        &mut self.effects
+    }
+
+    fn set_transform(&mut self, transform: Transform2D) {
+        self.transform = transform;
+    }
+
+    fn start_crossfade(&mut self) {
+        self.crossfade.start(self.pose.as_ref());
+    }
+
+    fn set_crossfade_progress(&mut self, progress: f32) {
+        self.crossfade.set_progress(progress);
+    }
+}
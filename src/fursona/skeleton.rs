@@ -0,0 +1,404 @@
+mod attachment;
+mod bone;
+mod curve;
+mod skeleton_animation;
+mod skeleton_error;
+mod skeleton_file;
+mod skeleton_pose;
+mod slot;
+mod timeline;
+
+use crate::rendering::{Palette, TextureCache, Transform2D};
+use glium::Display;
+use glutin::surface::WindowSurface;
+use std::path::Path;
+use tracing::warn;
+
+use attachment::Attachment;
+use bone::{Bone, BoneTransform};
+use curve::Curve;
+use skeleton_animation::{AttachmentTimeline, BoneTimelines, SkeletonAnimation};
+pub use skeleton_error::SkeletonError;
+use skeleton_file::{SkeletonFile, SkeletonFileCurve, SkeletonFileKey};
+pub use skeleton_pose::SkeletonPose;
+use slot::Slot;
+use timeline::{Timeline, TimelineKey};
+
+// A cutout character made of images attached to a hierarchy of bones, and
+// the animations that move those bones around. Loaded from Spine's JSON
+// format; see `skeleton_file` for which parts of it are supported.
+pub struct Skeleton {
+    bones: Vec<Bone>,
+    // In the order they're drawn, back to front
+    slots: Vec<Slot>,
+    attachments: Vec<Attachment>,
+    animations: Vec<SkeletonAnimation>,
+    // Around every attachment in the setup pose, as [left, bottom, right,
+    // top] in skeleton space
+    bounds: [f32; 4],
+}
+
+fn to_curve(key: &SkeletonFileKey) -> Curve {
+    match &key.curve {
+        Some(SkeletonFileCurve::Named(name)) if name == "stepped" => Curve::Stepped,
+        Some(SkeletonFileCurve::Bezier(values)) if values.len() == 4 => {
+            Curve::Bezier([values[0], values[1], values[2], values[3]])
+        }
+        Some(SkeletonFileCurve::Cx1(cx1)) => Curve::Bezier([
+            *cx1,
+            key.c2.unwrap_or(0.0),
+            key.c3.unwrap_or(1.0),
+            key.c4.unwrap_or(1.0),
+        ]),
+        _ => Curve::Linear,
+    }
+}
+
+fn to_timeline(keys: &[SkeletonFileKey], default: f32) -> Timeline {
+    Timeline {
+        keys: keys
+            .iter()
+            .map(|key| TimelineKey {
+                time: key.time,
+                values: [
+                    key.angle.or(key.x).unwrap_or(default),
+                    key.y.unwrap_or(default),
+                ],
+                curve: to_curve(key),
+            })
+            .collect(),
+    }
+}
+
+impl Skeleton {
+    // Images are loaded through the texture cache, from the directory the
+    // skeleton file is in.
+    pub fn load(
+        path: &Path,
+        display: &Display<WindowSurface>,
+        texture_cache: &mut TextureCache,
+        palette: &Palette,
+    ) -> Result<Self, SkeletonError> {
+        let contents = std::fs::read_to_string(path).map_err(|source| SkeletonError::Read {
+            path: path.to_owned(),
+            source,
+        })?;
+        let file: SkeletonFile =
+            serde_json::from_str(&contents).map_err(|source| SkeletonError::Parse {
+                path: path.to_owned(),
+                source,
+            })?;
+
+        let mut image_directory = path.parent().unwrap_or(Path::new("")).to_owned();
+        if let Some(images) = file.skeleton.as_ref().and_then(|info| info.images.as_ref()) {
+            image_directory.push(images);
+        }
+
+        let mut bones: Vec<Bone> = Vec::new();
+        for bone in file.bones.iter() {
+            let parent = match &bone.parent {
+                Some(parent) => Some(
+                    bones
+                        .iter()
+                        .position(|existing| &existing.name == parent)
+                        .ok_or_else(|| SkeletonError::UnknownBone(parent.to_owned()))?,
+                ),
+                None => None,
+            };
+
+            bones.push(Bone {
+                name: bone.name.to_owned(),
+                parent,
+                setup: BoneTransform {
+                    x: bone.x,
+                    y: bone.y,
+                    rotation: bone.rotation,
+                    scale_x: bone.scale_x,
+                    scale_y: bone.scale_y,
+                },
+            });
+        }
+        let bone_index = |name: &str| {
+            bones
+                .iter()
+                .position(|bone| bone.name == name)
+                .ok_or_else(|| SkeletonError::UnknownBone(name.to_owned()))
+        };
+
+        let mut slots = Vec::new();
+        for slot in file.slots.iter() {
+            slots.push(Slot {
+                name: slot.name.to_owned(),
+                bone: bone_index(&slot.bone)?,
+                attachment: None,
+            });
+        }
+        let slot_index = |name: &str| {
+            slots
+                .iter()
+                .position(|slot| slot.name == name)
+                .ok_or_else(|| SkeletonError::UnknownSlot(name.to_owned()))
+        };
+
+        // Only the default skin is used
+        let mut attachments = Vec::new();
+        let skin = file.skins.iter().find(|skin| skin.name == "default");
+        for (slot_name, slot_attachments) in skin.iter().flat_map(|skin| skin.attachments.iter()) {
+            let slot = slot_index(slot_name)?;
+            for (name, attachment) in slot_attachments.iter() {
+                if attachment.kind != "region" {
+                    warn!(
+                        "Skipping {name}, since {} attachments aren't supported",
+                        attachment.kind
+                    );
+                    continue;
+                }
+
+                let image_name = attachment.path.as_ref().unwrap_or(name);
+                let image = texture_cache.get(
+                    display,
+                    image_directory.join(format!("{image_name}.png")),
+                    palette,
+                )?;
+
+                // The image is centered on the attachment's position and
+                // stretched to its size. Images have y pointing down, so flip
+                // them over on the way in.
+                let (image_width, image_height) = image.dimensions();
+                let transform = Transform2D::translation(attachment.x, attachment.y)
+                    * Transform2D::rotation(attachment.rotation)
+                    * Transform2D::scale(attachment.scale_x, attachment.scale_y)
+                    * Transform2D::scale(
+                        attachment.width / image_width as f32,
+                        -attachment.height / image_height as f32,
+                    )
+                    * Transform2D::translation(
+                        -(image_width as f32) / 2.0,
+                        -(image_height as f32) / 2.0,
+                    );
+
+                attachments.push(Attachment {
+                    slot,
+                    name: name.to_owned(),
+                    image,
+                    transform,
+                });
+            }
+        }
+        let attachment_index = |slot: usize, name: &Option<String>| {
+            let name = name.as_ref()?;
+            let index = attachments
+                .iter()
+                .position(|attachment| attachment.slot == slot && &attachment.name == name);
+            if index.is_none() {
+                warn!("Skipping attachment {name}, which isn't in the default skin");
+            }
+            index
+        };
+
+        let mut animations = Vec::new();
+        for (name, animation) in file.animations.iter() {
+            let mut duration: f32 = 0.0;
+            let mut extend = |time: f32| duration = duration.max(time);
+
+            let mut bone_timelines = Vec::new();
+            for (bone, timelines) in animation.bones.iter() {
+                for key in timelines
+                    .rotate
+                    .iter()
+                    .chain(timelines.translate.iter())
+                    .chain(timelines.scale.iter())
+                {
+                    extend(key.time);
+                }
+
+                bone_timelines.push(BoneTimelines {
+                    bone: bone_index(bone)?,
+                    rotate: to_timeline(&timelines.rotate, 0.0),
+                    translate: to_timeline(&timelines.translate, 0.0),
+                    scale: to_timeline(&timelines.scale, 1.0),
+                });
+            }
+
+            let mut attachment_timelines = Vec::new();
+            for (slot, timelines) in animation.slots.iter() {
+                let slot = slot_index(slot)?;
+                let keys = timelines
+                    .attachment
+                    .iter()
+                    .map(|key| {
+                        extend(key.time);
+                        (key.time, attachment_index(slot, &key.name))
+                    })
+                    .collect();
+
+                attachment_timelines.push(AttachmentTimeline { slot, keys });
+            }
+
+            let events = animation
+                .events
+                .iter()
+                .map(|key| {
+                    extend(key.time);
+                    (key.time, key.name.to_owned())
+                })
+                .collect();
+
+            animations.push(SkeletonAnimation {
+                name: name.to_owned(),
+                duration,
+                bones: bone_timelines,
+                attachments: attachment_timelines,
+                events,
+            });
+        }
+
+        let setup_attachments: Vec<Option<usize>> = file
+            .slots
+            .iter()
+            .enumerate()
+            .map(|(index, slot)| attachment_index(index, &slot.attachment))
+            .collect();
+        for (slot, attachment) in slots.iter_mut().zip(setup_attachments) {
+            slot.attachment = attachment;
+        }
+
+        let mut skeleton = Self {
+            bones,
+            slots,
+            attachments,
+            animations,
+            bounds: [0.0; 4],
+        };
+        skeleton.bounds = skeleton.measure(&skeleton.setup_pose());
+        Ok(skeleton)
+    }
+
+    fn setup_pose(&self) -> SkeletonPose {
+        self.pose_with(|_, setup| setup.clone(), |slot| self.slots[slot].attachment)
+    }
+
+    // Works out where every bone is, given how to find each one's local
+    // transform and what each slot shows
+    fn pose_with(
+        &self,
+        local: impl Fn(usize, &BoneTransform) -> BoneTransform,
+        attachment: impl Fn(usize) -> Option<usize>,
+    ) -> SkeletonPose {
+        let mut bones: Vec<Transform2D> = Vec::with_capacity(self.bones.len());
+        for (index, bone) in self.bones.iter().enumerate() {
+            let transform = local(index, &bone.setup).to_transform();
+            bones.push(match bone.parent {
+                Some(parent) => bones[parent] * transform,
+                None => transform,
+            });
+        }
+
+        SkeletonPose {
+            bones,
+            attachments: (0..self.slots.len()).map(attachment).collect(),
+            mirrored: false,
+        }
+    }
+
+    // The smallest box around everything shown in the pose
+    fn measure(&self, pose: &SkeletonPose) -> [f32; 4] {
+        let mut bounds: Option<[f32; 4]> = None;
+        for (transform, attachment) in self.attachment_transforms(pose) {
+            let (width, height) = attachment.image.dimensions();
+            for (x, y) in [(0, 0), (width, 0), (0, height), (width, height)] {
+                let (x, y) = transform.apply(x as f32, y as f32);
+                let [left, bottom, right, top] = bounds.get_or_insert([x, y, x, y]);
+                *left = left.min(x);
+                *bottom = bottom.min(y);
+                *right = right.max(x);
+                *top = top.max(y);
+            }
+        }
+
+        bounds.unwrap_or([0.0; 4])
+    }
+
+    pub fn animation_index(&self, name: &str) -> Option<usize> {
+        self.animations
+            .iter()
+            .position(|animation| animation.name == name)
+    }
+
+    pub fn animation(&self, index: usize) -> &SkeletonAnimation {
+        &self.animations[index]
+    }
+
+    // Where everything is `time` seconds into the animation
+    pub fn pose(&self, animation: usize, time: f32, mirrored: bool) -> SkeletonPose {
+        let animation = &self.animations[animation];
+        let mut pose = self.pose_with(
+            |bone, setup| {
+                let mut transform = setup.clone();
+                for timelines in animation.bones.iter().filter(|t| t.bone == bone) {
+                    if let Some(angle) = timelines.rotate.sample_angle(time) {
+                        transform.rotation += angle;
+                    }
+                    if let Some([x, y]) = timelines.translate.sample(time) {
+                        transform.x += x;
+                        transform.y += y;
+                    }
+                    if let Some([x, y]) = timelines.scale.sample(time) {
+                        transform.scale_x *= x;
+                        transform.scale_y *= y;
+                    }
+                }
+                transform
+            },
+            |slot| {
+                let timeline = animation.attachments.iter().find(|t| t.slot == slot);
+                match timeline.and_then(|t| t.keys.iter().rev().find(|(at, _)| *at <= time)) {
+                    Some((_, attachment)) => *attachment,
+                    None => self.slots[slot].attachment,
+                }
+            },
+        );
+
+        pose.mirrored = mirrored;
+        pose
+    }
+
+    // Everything shown in the pose, back to front, with the transform from
+    // each image's pixels to skeleton space
+    pub fn attachment_transforms<'a>(
+        &'a self,
+        pose: &'a SkeletonPose,
+    ) -> impl Iterator<Item = (Transform2D, &'a Attachment)> + 'a {
+        self.slots
+            .iter()
+            .zip(pose.attachments.iter())
+            .filter_map(move |(slot, attachment)| {
+                let attachment = &self.attachments[(*attachment)?];
+                Some((pose.bones[slot.bone] * attachment.transform, attachment))
+            })
+    }
+
+    // The size of the box around the setup pose, in skeleton units
+    pub fn dimensions(&self) -> (f32, f32) {
+        let [left, bottom, right, top] = self.bounds;
+        (right - left, top - bottom)
+    }
+
+    // Where the skeleton's origin is within that box, from its top-left
+    // corner with y pointing down
+    pub fn anchor(&self, mirrored: bool) -> (f32, f32) {
+        let [left, _, right, top] = self.bounds;
+        match mirrored {
+            false => (-left, top),
+            true => (right, top),
+        }
+    }
+
+    // From skeleton space to pixels from the top-left of the box around the
+    // setup pose, with y pointing down
+    pub fn to_frame(&self, mirrored: bool) -> Transform2D {
+        let (anchor_x, anchor_y) = self.anchor(mirrored);
+        let flip_x = if mirrored { -1.0 } else { 1.0 };
+        Transform2D::translation(anchor_x, anchor_y) * Transform2D::scale(flip_x, -1.0)
+    }
+}
@@ -0,0 +1,10 @@
+use crate::rendering::{AtlasRegion, Transform2D};
+
+// An image shown in one of the skeleton's slots
+pub struct Attachment {
+    pub slot: usize,
+    pub name: String,
+    pub image: AtlasRegion,
+    // From the image's pixels to the space of the bone the slot follows
+    pub transform: Transform2D,
+}
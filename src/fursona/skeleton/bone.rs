@@ -0,0 +1,28 @@
+use crate::rendering::Transform2D;
+
+// Where a bone sits relative to its parent
+#[derive(Clone)]
+pub struct BoneTransform {
+    pub x: f32,
+    pub y: f32,
+    // In degrees, counterclockwise
+    pub rotation: f32,
+    pub scale_x: f32,
+    pub scale_y: f32,
+}
+
+impl BoneTransform {
+    pub fn to_transform(&self) -> Transform2D {
+        Transform2D::translation(self.x, self.y)
+            * Transform2D::rotation(self.rotation)
+            * Transform2D::scale(self.scale_x, self.scale_y)
+    }
+}
+
+pub struct Bone {
+    pub name: String,
+    // Always comes before this bone
+    pub parent: Option<usize>,
+    // Where the bone is when no animation is moving it
+    pub setup: BoneTransform,
+}
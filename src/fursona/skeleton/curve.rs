@@ -0,0 +1,84 @@
+// How a value eases from one key to the next
+#[derive(Clone, Copy)]
+pub enum Curve {
+    Linear,
+    // Holds the value until the next key
+    Stepped,
+    // A cubic bezier from (0, 0) to (1, 1), given its two control points as
+    // [x1, y1, x2, y2]
+    Bezier([f32; 4]),
+}
+
+// How many times to halve the search when solving a bezier for x, which is
+// plenty for anything that ends up on screen
+const BEZIER_ITERATIONS: u32 = 16;
+
+fn cubic(p1: f32, p2: f32, s: f32) -> f32 {
+    let inverse = 1.0 - s;
+    3.0 * inverse * inverse * s * p1 + 3.0 * inverse * s * s * p2 + s * s * s
+}
+
+impl Curve {
+    // Maps how far we are between two keys, from 0.0 to 1.0, to how far the
+    // value should be between them
+    pub fn apply(&self, progress: f32) -> f32 {
+        match self {
+            Curve::Linear => progress,
+            Curve::Stepped => 0.0,
+            Curve::Bezier([x1, y1, x2, y2]) => {
+                // Find where along the curve we reach the given x, then use
+                // the y there
+                let (mut low, mut high) = (0.0, 1.0);
+                for _ in 0..BEZIER_ITERATIONS {
+                    let middle = (low + high) / 2.0;
+                    if cubic(*x1, *x2, middle) < progress {
+                        low = middle;
+                    } else {
+                        high = middle;
+                    }
+                }
+
+                cubic(*y1, *y2, (low + high) / 2.0)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn linear_is_unchanged() {
+        assert_eq!(Curve::Linear.apply(0.25), 0.25);
+    }
+
+    #[test]
+    fn stepped_holds_until_the_next_key() {
+        assert_eq!(Curve::Stepped.apply(0.0), 0.0);
+        assert_eq!(Curve::Stepped.apply(0.99), 0.0);
+    }
+
+    #[test]
+    fn bezier_passes_through_its_ends() {
+        let curve = Curve::Bezier([0.25, 0.1, 0.25, 1.0]);
+        assert!(curve.apply(0.0).abs() < 0.001);
+        assert!((curve.apply(1.0) - 1.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn bezier_along_the_diagonal_is_linear() {
+        let curve = Curve::Bezier([1.0 / 3.0, 1.0 / 3.0, 2.0 / 3.0, 2.0 / 3.0]);
+        for progress in [0.1, 0.5, 0.8] {
+            assert!((curve.apply(progress) - progress).abs() < 0.001);
+        }
+    }
+
+    #[test]
+    fn bezier_eases_in() {
+        // Slow to start, so it's behind a linear curve early on
+        let curve = Curve::Bezier([0.42, 0.0, 1.0, 1.0]);
+        assert!(curve.apply(0.25) < 0.25);
+        assert!(curve.apply(0.5) < curve.apply(0.75));
+    }
+}
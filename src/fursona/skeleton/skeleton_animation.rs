@@ -0,0 +1,39 @@
+use super::Timeline;
+
+pub struct BoneTimelines {
+    pub bone: usize,
+    // Added to the setup pose's rotation
+    pub rotate: Timeline,
+    // Added to the setup pose's position
+    pub translate: Timeline,
+    // Multiplied with the setup pose's scale
+    pub scale: Timeline,
+}
+
+pub struct AttachmentTimeline {
+    pub slot: usize,
+    // Times in seconds, and what to show from then on
+    pub keys: Vec<(f32, Option<usize>)>,
+}
+
+pub struct SkeletonAnimation {
+    pub name: String,
+    // In seconds
+    pub duration: f32,
+    pub bones: Vec<BoneTimelines>,
+    pub attachments: Vec<AttachmentTimeline>,
+    // Times in seconds, and the name of the event fired then
+    pub events: Vec<(f32, String)>,
+}
+
+impl SkeletonAnimation {
+    // The names of the events after `from` (or from the very start when not
+    // given) up to and including `to`, in order
+    pub fn events_between(&self, from: Option<f32>, to: f32) -> Vec<String> {
+        self.events
+            .iter()
+            .filter(|(time, _)| from.is_none_or(|from| *time > from) && *time <= to)
+            .map(|(_, name)| name.clone())
+            .collect()
+    }
+}
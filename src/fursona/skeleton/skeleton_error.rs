@@ -0,0 +1,23 @@
+use crate::rendering::TextureError;
+use std::path::PathBuf;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum SkeletonError {
+    #[error("couldn't read skeleton {}: {source}", path.display())]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("couldn't parse skeleton {}: {source}", path.display())]
+    Parse {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    #[error("the skeleton refers to a bone named {0}, which it doesn't define first")]
+    UnknownBone(String),
+    #[error("the skeleton refers to a slot named {0}, which it doesn't define")]
+    UnknownSlot(String),
+    #[error(transparent)]
+    Texture(#[from] TextureError),
+}
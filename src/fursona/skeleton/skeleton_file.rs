@@ -0,0 +1,154 @@
+// The subset of Spine's JSON skeleton format (as exported by Spine 3.8) that
+// we understand. Anything else in the file is ignored.
+//
+// Spine measures with y pointing up, rotations in degrees counterclockwise,
+// and times in seconds.
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+fn one() -> f32 {
+    1.0
+}
+
+fn region() -> String {
+    "region".to_owned()
+}
+
+#[derive(Deserialize)]
+pub struct SkeletonFileInfo {
+    // Where the images are, relative to the skeleton file
+    pub images: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct SkeletonFileBone {
+    pub name: String,
+    pub parent: Option<String>,
+    #[serde(default)]
+    pub x: f32,
+    #[serde(default)]
+    pub y: f32,
+    #[serde(default)]
+    pub rotation: f32,
+    #[serde(default = "one", rename = "scaleX")]
+    pub scale_x: f32,
+    #[serde(default = "one", rename = "scaleY")]
+    pub scale_y: f32,
+}
+
+#[derive(Deserialize)]
+pub struct SkeletonFileSlot {
+    pub name: String,
+    pub bone: String,
+    // Shown in the setup pose
+    pub attachment: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct SkeletonFileAttachment {
+    // Only "region" attachments, which are plain images, are supported
+    #[serde(default = "region", rename = "type")]
+    pub kind: String,
+    // The image to show, without its extension. Defaults to the attachment's
+    // name.
+    pub path: Option<String>,
+    #[serde(default)]
+    pub x: f32,
+    #[serde(default)]
+    pub y: f32,
+    #[serde(default)]
+    pub rotation: f32,
+    #[serde(default = "one", rename = "scaleX")]
+    pub scale_x: f32,
+    #[serde(default = "one", rename = "scaleY")]
+    pub scale_y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+#[derive(Deserialize)]
+pub struct SkeletonFileSkin {
+    pub name: String,
+    // Slot name -> attachment name -> attachment
+    #[serde(default)]
+    pub attachments: BTreeMap<String, BTreeMap<String, SkeletonFileAttachment>>,
+}
+
+// Either "stepped", a list of the four bezier control values, or the first
+// of them with the rest in `c2`, `c3` and `c4` on the key.
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum SkeletonFileCurve {
+    Named(String),
+    Bezier(Vec<f32>),
+    Cx1(f32),
+}
+
+#[derive(Deserialize)]
+pub struct SkeletonFileKey {
+    #[serde(default)]
+    pub time: f32,
+    // Rotate keys have an angle, while translate and scale keys have x and y
+    pub angle: Option<f32>,
+    pub x: Option<f32>,
+    pub y: Option<f32>,
+    pub curve: Option<SkeletonFileCurve>,
+    pub c2: Option<f32>,
+    pub c3: Option<f32>,
+    pub c4: Option<f32>,
+}
+
+#[derive(Deserialize)]
+pub struct SkeletonFileBoneTimelines {
+    #[serde(default)]
+    pub rotate: Vec<SkeletonFileKey>,
+    #[serde(default)]
+    pub translate: Vec<SkeletonFileKey>,
+    #[serde(default)]
+    pub scale: Vec<SkeletonFileKey>,
+}
+
+#[derive(Deserialize)]
+pub struct SkeletonFileAttachmentKey {
+    #[serde(default)]
+    pub time: f32,
+    // Hides the slot when not set
+    pub name: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct SkeletonFileSlotTimelines {
+    #[serde(default)]
+    pub attachment: Vec<SkeletonFileAttachmentKey>,
+}
+
+#[derive(Deserialize)]
+pub struct SkeletonFileEventKey {
+    #[serde(default)]
+    pub time: f32,
+    pub name: String,
+}
+
+#[derive(Deserialize)]
+pub struct SkeletonFileAnimation {
+    #[serde(default)]
+    pub bones: BTreeMap<String, SkeletonFileBoneTimelines>,
+    #[serde(default)]
+    pub slots: BTreeMap<String, SkeletonFileSlotTimelines>,
+    #[serde(default)]
+    pub events: Vec<SkeletonFileEventKey>,
+}
+
+#[derive(Deserialize)]
+pub struct SkeletonFile {
+    pub skeleton: Option<SkeletonFileInfo>,
+    #[serde(default)]
+    pub bones: Vec<SkeletonFileBone>,
+    // In the order they're drawn, back to front
+    #[serde(default)]
+    pub slots: Vec<SkeletonFileSlot>,
+    #[serde(default)]
+    pub skins: Vec<SkeletonFileSkin>,
+    #[serde(default)]
+    pub animations: BTreeMap<String, SkeletonFileAnimation>,
+}
@@ -0,0 +1,12 @@
+use crate::rendering::Transform2D;
+
+// Where everything in a skeleton is at one moment of an animation
+#[derive(Clone)]
+pub struct SkeletonPose {
+    // Each bone's transform to skeleton space, with y pointing up
+    pub bones: Vec<Transform2D>,
+    // What each slot is showing, if anything
+    pub attachments: Vec<Option<usize>>,
+    // Facing the other way to how the skeleton was made
+    pub mirrored: bool,
+}
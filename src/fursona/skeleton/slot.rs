@@ -0,0 +1,7 @@
+// Somewhere an attachment can be shown, following a bone around
+pub struct Slot {
+    pub name: String,
+    pub bone: usize,
+    // What's shown in the setup pose, if anything
+    pub attachment: Option<usize>,
+}
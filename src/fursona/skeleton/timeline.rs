@@ -0,0 +1,146 @@
+use super::Curve;
+
+pub struct TimelineKey {
+    // In seconds
+    pub time: f32,
+    pub values: [f32; 2],
+    // How to get from this key to the next
+    pub curve: Curve,
+}
+
+// A value that changes over the course of an animation, eased from one key
+// to the next. Keys are in order of time.
+pub struct Timeline {
+    pub keys: Vec<TimelineKey>,
+}
+
+// The shortest way round from one angle to another, in degrees
+fn angle_between(from: f32, to: f32) -> f32 {
+    (to - from + 180.0).rem_euclid(360.0) - 180.0
+}
+
+impl Timeline {
+    // The key we're on and, when we're between two, the next one and how far
+    // we are towards it once eased
+    fn segment(&self, time: f32) -> Option<(&TimelineKey, Option<(&TimelineKey, f32)>)> {
+        let first = self.keys.first()?;
+        if time <= first.time {
+            return Some((first, None));
+        }
+
+        let index = self.keys.iter().rposition(|key| key.time <= time)?;
+        let key = &self.keys[index];
+        let next = match self.keys.get(index + 1) {
+            Some(next) => next,
+            None => return Some((key, None)),
+        };
+
+        let span = next.time - key.time;
+        let progress = if span > 0.0 {
+            (time - key.time) / span
+        } else {
+            1.0
+        };
+        Some((key, Some((next, key.curve.apply(progress)))))
+    }
+
+    pub fn sample(&self, time: f32) -> Option<[f32; 2]> {
+        let (key, next) = self.segment(time)?;
+        let [x, y] = key.values;
+        Some(match next {
+            Some((next, progress)) => {
+                let [next_x, next_y] = next.values;
+                [x + (next_x - x) * progress, y + (next_y - y) * progress]
+            }
+            None => key.values,
+        })
+    }
+
+    // Like `sample`, for timelines of angles in degrees, turning whichever way
+    // round is shortest
+    pub fn sample_angle(&self, time: f32) -> Option<f32> {
+        let (key, next) = self.segment(time)?;
+        let angle = key.values[0];
+        Some(match next {
+            Some((next, progress)) => angle + angle_between(angle, next.values[0]) * progress,
+            None => angle,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(time: f32, values: [f32; 2], curve: Curve) -> TimelineKey {
+        TimelineKey {
+            time,
+            values,
+            curve,
+        }
+    }
+
+    #[test]
+    fn empty_timelines_have_nothing_to_say() {
+        let timeline = Timeline { keys: Vec::new() };
+        assert_eq!(timeline.sample(0.5), None);
+        assert_eq!(timeline.sample_angle(0.5), None);
+    }
+
+    #[test]
+    fn samples_between_keys() {
+        let timeline = Timeline {
+            keys: vec![
+                key(0.0, [0.0, 10.0], Curve::Linear),
+                key(2.0, [4.0, 20.0], Curve::Linear),
+            ],
+        };
+        assert_eq!(timeline.sample(1.0), Some([2.0, 15.0]));
+        assert_eq!(timeline.sample(0.5), Some([1.0, 12.5]));
+    }
+
+    #[test]
+    fn holds_the_first_and_last_keys_outside_of_them() {
+        let timeline = Timeline {
+            keys: vec![
+                key(1.0, [1.0, 1.0], Curve::Linear),
+                key(2.0, [3.0, 3.0], Curve::Linear),
+            ],
+        };
+        assert_eq!(timeline.sample(0.0), Some([1.0, 1.0]));
+        assert_eq!(timeline.sample(5.0), Some([3.0, 3.0]));
+    }
+
+    #[test]
+    fn uses_the_curve_of_the_key_being_left() {
+        let timeline = Timeline {
+            keys: vec![
+                key(0.0, [0.0, 0.0], Curve::Stepped),
+                key(1.0, [1.0, 1.0], Curve::Linear),
+                key(2.0, [3.0, 3.0], Curve::Linear),
+            ],
+        };
+        assert_eq!(timeline.sample(0.9), Some([0.0, 0.0]));
+        assert_eq!(timeline.sample(1.5), Some([2.0, 2.0]));
+    }
+
+    #[test]
+    fn angles_turn_the_shortest_way_round() {
+        let timeline = Timeline {
+            keys: vec![
+                key(0.0, [350.0, 0.0], Curve::Linear),
+                key(1.0, [10.0, 0.0], Curve::Linear),
+            ],
+        };
+        let angle = timeline.sample_angle(0.5).unwrap();
+        assert!((angle.rem_euclid(360.0)).abs() < 0.001);
+        assert!((timeline.sample_angle(0.25).unwrap() - 355.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn angle_between_wraps_around() {
+        assert_eq!(angle_between(170.0, -170.0), 20.0);
+        assert_eq!(angle_between(-170.0, 170.0), -20.0);
+        assert_eq!(angle_between(0.0, 90.0), 90.0);
+    }
+}
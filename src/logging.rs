@@ -0,0 +1,87 @@
+use std::path::PathBuf;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::{fmt, EnvFilter};
+
+const LOG_DIRECTORY_NAME: &str = "desktop-fursona";
+const LOG_FILE_PREFIX: &str = "desktop-fursona";
+const MAX_LOG_FILES: usize = 7;
+
+// Where log files are written when file logging is turned on: a `logs`
+// directory inside the platform's state directory (`$XDG_STATE_HOME` on
+// Linux), or its local data directory on platforms that don't have one.
+pub fn log_directory() -> PathBuf {
+    match dirs::state_dir().or_else(dirs::data_local_dir) {
+        Some(dir) => dir.join(LOG_DIRECTORY_NAME).join("logs"),
+        None => PathBuf::from(".").join("logs"),
+    }
+}
+
+// Sets up logging for the whole application. Each module logs under its own
+// target (`desktop_fursona::stage`, `desktop_fursona::fursona::behaviors`, and
+// so on), so `RUST_LOG` can be used to turn individual modules up or down.
+// If `RUST_LOG` isn't set, the verbosity picks a level for everything of ours:
+// info by default, then debug, then trace (which also times each frame).
+pub fn init(verbosity: u8, log_to_file: bool) {
+    let level = match verbosity {
+        0 => "info",
+        1 => "debug",
+        _ => "trace",
+    };
+    let filter = || {
+        EnvFilter::try_from_default_env()
+            .unwrap_or_else(|_| EnvFilter::new(format!("warn,desktop_fursona={level}")))
+    };
+
+    // Closing a span reports how long it was open for, which is how
+    // `Stage::draw` and `FursonaInstance::update` get timed
+    let stderr_layer = fmt::layer()
+        .with_writer(std::io::stderr)
+        .with_span_events(FmtSpan::CLOSE)
+        .with_filter(filter());
+
+    let mut file_error = None;
+    let file_layer = if log_to_file {
+        // The appender prunes old files as soon as it's built, which it
+        // complains about if the directory isn't there yet
+        let directory = log_directory();
+        let appender = std::fs::create_dir_all(&directory)
+            .map_err(|e| e.to_string())
+            .and_then(|_| {
+                RollingFileAppender::builder()
+                    .rotation(Rotation::DAILY)
+                    .filename_prefix(LOG_FILE_PREFIX)
+                    .filename_suffix("log")
+                    .max_log_files(MAX_LOG_FILES)
+                    .build(&directory)
+                    .map_err(|e| e.to_string())
+            });
+
+        match appender {
+            Ok(appender) => Some(
+                fmt::layer()
+                    .with_writer(appender)
+                    .with_ansi(false)
+                    .with_span_events(FmtSpan::CLOSE)
+                    .with_filter(filter()),
+            ),
+            Err(e) => {
+                file_error = Some(e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    tracing_subscriber::registry()
+        .with(stderr_layer)
+        .with(file_layer)
+        .init();
+
+    // We can only report this now that there's somewhere to report it to
+    if let Some(e) = file_error {
+        tracing::error!("Couldn't log to {}: {e}", log_directory().display());
+    }
+}
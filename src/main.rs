@@ -1,67 +1,175 @@
+mod atomic_write;
+mod benchmark;
+mod cli;
 mod event_loop;
 mod fursona;
+mod logging;
 mod rendering;
 mod settings;
 mod stage;
+mod state;
+mod surfaces;
 
+use clap::Parser;
 use colored::Colorize;
 use std::cell::RefCell;
+use std::time::Instant;
+use tracing::{debug, error, info};
 
+use benchmark::Benchmark;
+use cli::Cli;
 use event_loop::{Event, EventLoop};
 use fursona::FursonaInstance;
+use rendering::TextureCache;
 use settings::Settings;
 use stage::Stage;
+use state::State;
+use surfaces::Surfaces;
 
-fn main() -> Result<(), impl std::error::Error> {
-    let event_loop = EventLoop::new().unwrap();
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let cli = Cli::parse();
+    logging::init(cli.verbose, cli.log_to_file);
+
+    let settings_path = cli.config.unwrap_or_else(Settings::default_path);
+    let state_path = cli.state.unwrap_or_else(State::default_path);
+
+    if cli.print_schema {
+        match Settings::schema_json() {
+            Ok(schema) => println!("{schema}"),
+            Err(msg) => {
+                println!("{}", msg.red());
+                std::process::exit(-1);
+            }
+        }
+
+        return Ok(());
+    }
+
+    if let Some(destination) = cli.convert {
+        if let Err(msg) = Settings::convert(&settings_path, &destination) {
+            println!("{}", msg.red());
+            std::process::exit(-1);
+        }
 
-    let settings = Settings::load_or_create("./settings.json");
-    if settings.fursona.is_empty() {
         println!(
-            "{}",
-            "You have no fursona configured in your settings.".red()
+            "Converted {} to {}",
+            settings_path.display(),
+            destination.display()
         );
+        return Ok(());
+    }
+
+    if let Some(directory) = cli.import_shimeji {
+        match Settings::import_shimeji(&settings_path, &directory) {
+            Ok(untranslated) => {
+                println!(
+                    "Imported {} into {}",
+                    directory.display(),
+                    settings_path.display()
+                );
+                if !untranslated.is_empty() {
+                    println!("{}", "These couldn't be carried over:".yellow());
+                    for item in untranslated {
+                        println!("  {}", item.yellow());
+                    }
+                }
+            }
+            Err(msg) => {
+                println!("{}", msg.red());
+                std::process::exit(-1);
+            }
+        }
+
+        return Ok(());
+    }
+
+    if let Some(index) = cli.rollback {
+        if let Err(msg) = Settings::rollback(&settings_path, index) {
+            println!("{}", msg.red());
+            std::process::exit(-1);
+        }
+
+        println!("Restored {} from backup {index}", settings_path.display());
+        return Ok(());
+    }
+
+    let event_loop = EventLoop::new()?;
+
+    let settings = Settings::load_or_create(&settings_path);
+    if settings.fursona.is_empty() {
+        error!("You have no fursona configured in your settings.");
         std::process::exit(-1);
     }
 
-    println!("Defined fursona:");
-    for fursona in settings.fursona {
-        println!("  - {}", fursona.name)
+    for fursona in settings.fursona.iter() {
+        info!("Defined fursona: {}", fursona.name)
     }
 
-    let settings = RefCell::new(Settings::load_or_create("./settings.json"));
+    let settings = RefCell::new(settings);
 
-    let mut stage = Stage::new(&event_loop).unwrap();
+    let mut stage = Stage::new(&event_loop)?;
     stage.set_debug_mode(true);
 
+    let mut surfaces = Surfaces::new(cli.mock_windows);
+    let mut texture_cache = TextureCache::new();
     let mut instances: Vec<FursonaInstance> = Vec::new();
+    let mut benchmark = cli.benchmark.map(|_| Benchmark::new());
 
     event_loop.run(move |event| match event {
         Event::Initialization => {
-            instances.extend(
-                settings
-                    .borrow()
-                    .fursona
-                    .iter()
-                    .map(|fursona| fursona.make_instance(&stage)),
-            );
+            let settings = settings.borrow();
+            let instance_count = cli.benchmark.unwrap_or(settings.fursona.len());
+            for fursona in settings.fursona.iter().cycle().take(instance_count) {
+                match fursona.make_instance(&stage, &mut texture_cache) {
+                    Ok(instance) => instances.push(instance),
+                    Err(e) => error!("Couldn't create {}: {e}", fursona.name),
+                }
+            }
+
+            if benchmark.is_some() {
+                info!("Benchmarking with {} instances", instances.len());
+            } else {
+                State::load(&state_path).restore(instances.iter_mut(), &stage);
+            }
         }
         Event::MouseDown(coords) => {
-            println!("LMB click: ({}, {})", coords.x, coords.y);
+            debug!("LMB click: ({}, {})", coords.x, coords.y);
         }
         Event::MouseMove(coords) => {
-            stage.on_mouse_over(coords);
+            let point = stage.viewport.convert_desktop_point(coords.clone());
+            for instance in instances.iter_mut() {
+                instance.set_hovered(instance.hitbox().contains(&point));
+            }
+
+            stage.on_mouse_over(coords, instances.iter());
         }
         Event::Exit => {
-            println!("EXITING");
+            info!("Exiting");
+            if benchmark.is_none() {
+                State::capture(instances.iter()).save(&state_path);
+            }
         }
         Event::Resized { width, height } => stage.resize(width, height),
+        Event::ScaleFactorChanged { scale_factor } => stage.set_scale_factor(scale_factor),
         Event::Update { delta_t_ms } => {
+            let update_start = Instant::now();
+
+            surfaces.update(delta_t_ms, &stage.viewport);
             for instance in instances.iter_mut() {
-                instance.update(delta_t_ms, &stage);
+                instance.update(delta_t_ms, &stage, surfaces.list());
             }
+            let update_time = update_start.elapsed();
 
-            stage.draw(instances.iter());
+            match stage.draw(instances.iter(), surfaces.list()) {
+                Ok(draw_time) => {
+                    if let Some(benchmark) = benchmark.as_mut() {
+                        benchmark.record_frame(update_time + draw_time);
+                    }
+                }
+                Err(e) => error!("Couldn't draw frame: {e}"),
+            }
         }
-    })
+    })?;
+
+    Ok(())
 }
@@ -1,13 +1,32 @@
+mod alpha_mode;
+mod atlas_region;
+mod mesh;
+mod mesh_vertex;
+mod palette;
+mod render_error;
 mod renderer;
+mod sprite_effects;
+mod texture_atlas;
 mod texture_cache;
+mod texture_error;
+mod texture_filter;
+mod transform_2d;
+mod transform_3d;
 
+pub use alpha_mode::AlphaMode;
+pub use atlas_region::AtlasRegion;
+pub use mesh::Mesh;
+pub use mesh_vertex::MeshVertex;
+pub use palette::Palette;
+pub use render_error::RenderError;
 pub use renderer::Renderer;
+pub use sprite_effects::SpriteEffects;
+use texture_atlas::TextureAtlas;
 pub use texture_cache::TextureCache;
-
-pub struct RendererCoord {
-    pub x: f32,
-    pub y: f32,
-}
+pub use texture_error::TextureError;
+pub use texture_filter::TextureFilter;
+pub use transform_2d::Transform2D;
+pub use transform_3d::Transform3D;
 
 #[derive(Clone)]
 pub struct RendererRect {
@@ -19,11 +38,41 @@ pub struct RendererRect {
     pub pixel_height: f32,
 }
 
+// Parses `#rrggbb` or `#rrggbbaa` into its channels, with alpha last
+pub fn parse_hex_color(hex: &str) -> Option<[u8; 4]> {
+    let digits = hex.strip_prefix('#')?;
+    if !matches!(digits.len(), 6 | 8) || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let value = u32::from_str_radix(digits, 16).ok()?;
+    let value = if digits.len() == 6 {
+        value << 8 | 0xff
+    } else {
+        value
+    };
+
+    Some(value.to_be_bytes())
+}
+
 #[derive(Clone)]
 pub struct Color(pub f32, pub f32, pub f32, pub f32);
 
 impl Color {
+    pub const WHITE: Color = Color(1.0, 1.0, 1.0, 1.0);
+    pub const TRANSPARENT: Color = Color(0.0, 0.0, 0.0, 0.0);
+
+    // Parses `#rrggbb` or `#rrggbbaa`
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let [r, g, b, a] = parse_hex_color(hex)?.map(|channel| channel as f32 / 255.0);
+        Some(Self(r, g, b, a))
+    }
+
     pub fn alpha(&self, alpha: f32) -> Self {
         Self(self.0, self.1, self.2, alpha)
     }
+
+    pub fn to_array(&self) -> [f32; 4] {
+        [self.0, self.1, self.2, self.3]
+    }
 }
@@ -0,0 +1,11 @@
+// How a mesh's alpha is treated, following glTF's materials
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AlphaMode {
+    // Alpha is ignored, and everything is drawn solid
+    Opaque,
+    // Pixels with alpha below the cutoff aren't drawn at all, and the rest
+    // are drawn solid
+    Mask(f32),
+    // Blended with whatever's behind
+    Blend,
+}
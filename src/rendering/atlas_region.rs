@@ -0,0 +1,37 @@
+use glium::texture::Texture2d;
+use std::rc::Rc;
+
+// A handle to one image that's been packed into a texture atlas. These are
+// cheap to clone, and keep the atlas alive for as long as they're around.
+#[derive(Clone)]
+pub struct AtlasRegion {
+    texture: Rc<Texture2d>,
+    uv_rect: [f32; 4],
+    dimensions: (u32, u32),
+}
+
+impl AtlasRegion {
+    pub fn new(texture: Rc<Texture2d>, uv_rect: [f32; 4], dimensions: (u32, u32)) -> Self {
+        Self {
+            texture,
+            uv_rect,
+            dimensions,
+        }
+    }
+
+    // The atlas texture the image lives in.
+    pub fn texture(&self) -> &Rc<Texture2d> {
+        &self.texture
+    }
+
+    // Where the image lives within the atlas, as [x, y, width, height] in
+    // texture coordinates (0.0 -> 1.0).
+    pub fn uv_rect(&self) -> [f32; 4] {
+        self.uv_rect
+    }
+
+    // The size of the image itself, in pixels.
+    pub fn dimensions(&self) -> (u32, u32) {
+        self.dimensions
+    }
+}
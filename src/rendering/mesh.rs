@@ -0,0 +1,16 @@
+use super::{AlphaMode, Color, MeshVertex};
+use glium::texture::Texture2d;
+use std::rc::Rc;
+
+// Triangles to draw with the depth buffer, so that they hide each other
+// properly no matter what order they're in
+pub struct Mesh<'a> {
+    pub vertices: &'a [MeshVertex],
+    // Every three make up a triangle
+    pub indices: &'a [u32],
+    // Sampled with the vertices' texture coordinates, which wrap around
+    pub texture: Option<&'a Rc<Texture2d>>,
+    // Multiplied with the texture, or used as it is without one
+    pub color: Color,
+    pub alpha: AlphaMode,
+}
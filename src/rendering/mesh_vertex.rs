@@ -0,0 +1,11 @@
+#[derive(Clone, Copy, Debug)]
+pub struct MeshVertex {
+    // In device pixels from the top-left corner of the rect the mesh is drawn
+    // into, with y pointing down. z is the depth, from 0.0 nearest the viewer
+    // to 1.0 furthest away.
+    pub position: [f32; 3],
+    // Which way the surface faces, with y pointing up and z towards the
+    // viewer, for lighting
+    pub normal: [f32; 3],
+    pub tex_coords: [f32; 2],
+}
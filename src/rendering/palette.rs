@@ -0,0 +1,36 @@
+use image::RgbaImage;
+use std::collections::BTreeMap;
+
+// Swaps exact colors in an image for others, so that one set of art can be
+// used for differently colored characters. Alpha is left alone.
+#[derive(Clone, Default, Hash, PartialEq, Eq)]
+pub struct Palette {
+    mappings: BTreeMap<[u8; 3], [u8; 3]>,
+}
+
+impl Palette {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, from: [u8; 3], to: [u8; 3]) {
+        self.mappings.insert(from, to);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.mappings.is_empty()
+    }
+
+    pub fn apply(&self, image: &mut RgbaImage) {
+        if self.is_empty() {
+            return;
+        }
+
+        for pixel in image.pixels_mut() {
+            let [r, g, b, _] = pixel.0;
+            if let Some(&[r, g, b]) = self.mappings.get(&[r, g, b]) {
+                pixel.0[..3].copy_from_slice(&[r, g, b]);
+            }
+        }
+    }
+}
@@ -0,0 +1,17 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum RenderError {
+    #[error("couldn't create vertex buffer: {0}")]
+    VertexBuffer(#[from] glium::vertex::BufferCreationError),
+    #[error("couldn't create index buffer: {0}")]
+    IndexBuffer(#[from] glium::index::BufferCreationError),
+    #[error("couldn't create texture: {0}")]
+    Texture(#[from] glium::texture::TextureCreationError),
+    #[error("couldn't compile shader program: {0}")]
+    Program(#[from] glium::ProgramCreationError),
+    #[error("draw call failed: {0}")]
+    Draw(#[from] glium::DrawError),
+    #[error("couldn't present frame: {0}")]
+    SwapBuffers(#[from] glium::SwapBuffersError),
+}
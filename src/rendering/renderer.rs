@@ -1,46 +1,174 @@
-mod square_renderer;
+mod mesh_batcher;
+mod sprite_batcher;
 
 use glium::{Display, DrawParameters, Frame};
 use glutin::surface::WindowSurface;
-use square_renderer::SquareRenderer;
+use mesh_batcher::MeshBatcher;
+use sprite_batcher::{Quad, QuadShape, SpriteBatcher};
 
-use super::{Color, RendererRect};
+use super::{
+    AtlasRegion, Color, Mesh, RenderError, RendererRect, SpriteEffects, TextureFilter, Transform2D,
+};
 
+// Everything drawn through the renderer is queued up and only actually drawn
+// when the frame is flushed, so that it can be batched together.
 pub struct Renderer {
-    square_renderer: SquareRenderer,
+    sprite_batcher: SpriteBatcher,
+    mesh_batcher: MeshBatcher,
 }
 
 impl Renderer {
-    pub fn new(display: &Display<WindowSurface>) -> Self {
-        Self {
-            square_renderer: SquareRenderer::new(display),
-        }
+    pub fn new(display: &Display<WindowSurface>) -> Result<Self, RenderError> {
+        Ok(Self {
+            sprite_batcher: SpriteBatcher::new(display)?,
+            mesh_batcher: MeshBatcher::new(display)?,
+        })
     }
 
-    pub fn fill_rect(
-        &self,
-        frame: &mut Frame,
+    // Draws the image placed within the rect by the transform. The transform
+    // maps from the image's own pixels (from its top-left corner, y pointing
+    // down) to device pixels from the rect's top-left corner, so it can
+    // rotate, scale and skew the image.
+    pub fn draw_sprite_transformed(
+        &mut self,
+        image: &AtlasRegion,
         rect: RendererRect,
-        color: Color,
-        base_draw_parameters: &DrawParameters,
-    ) -> () {
-        self.square_renderer
-            .draw(frame, rect, &color, (1.0, 1.0), base_draw_parameters);
+        transform: &Transform2D,
+        effects: &SpriteEffects,
+        filter: TextureFilter,
+    ) {
+        let (width, height) = image.dimensions();
+        self.sprite_batcher.push(Quad {
+            rect,
+            transform: Some(*transform * Transform2D::scale(width as f32, height as f32)),
+            shape: QuadShape::Rect,
+            texture: Some((image.texture(), image.uv_rect())),
+            filter,
+            tint: &effects.tint,
+            hue_shift: effects.hue_shift,
+            outline: effects.outline.as_ref().unwrap_or(&Color::TRANSPARENT),
+            thickness: (1.0, 1.0),
+        });
     }
 
-    pub fn outline_rect(
-        &self,
-        frame: &mut Frame,
-        rect: RendererRect,
-        color: Color,
-        thickness_pixels: f32,
-        base_draw_parameters: &DrawParameters,
-    ) -> () {
+    // A soft-edged ellipse filling the rect, darkest in the middle
+    pub fn draw_shadow(&mut self, rect: RendererRect, color: &Color) {
+        self.sprite_batcher.push(Quad {
+            rect,
+            transform: None,
+            shape: QuadShape::SoftEllipse,
+            texture: None,
+            filter: TextureFilter::Linear,
+            tint: color,
+            hue_shift: 0.0,
+            outline: &Color::TRANSPARENT,
+            thickness: (1.0, 1.0),
+        });
+    }
+
+    pub fn fill_rect(&mut self, rect: RendererRect, color: Color) {
+        self.sprite_batcher.push(Quad {
+            rect,
+            transform: None,
+            shape: QuadShape::Rect,
+            texture: None,
+            filter: TextureFilter::Linear,
+            tint: &color,
+            hue_shift: 0.0,
+            outline: &Color::TRANSPARENT,
+            thickness: (1.0, 1.0),
+        });
+    }
+
+    pub fn outline_rect(&mut self, rect: RendererRect, color: Color, thickness_pixels: f32) {
         let thickness = (
             thickness_pixels / rect.pixel_width,
             thickness_pixels / rect.pixel_height,
         );
-        self.square_renderer
-            .draw(frame, rect, &color, thickness, base_draw_parameters);
+        self.sprite_batcher.push(Quad {
+            rect,
+            transform: None,
+            shape: QuadShape::Rect,
+            texture: None,
+            filter: TextureFilter::Linear,
+            tint: &color,
+            hue_shift: 0.0,
+            outline: &Color::TRANSPARENT,
+            thickness,
+        });
+    }
+
+    // Draws the meshes within the rect, over everything drawn before them and
+    // under everything drawn after. They only hide parts of each other, as
+    // the pieces of one model. The tint is multiplied with each mesh's own
+    // color, but outlines aren't drawn around meshes.
+    pub fn draw_meshes(
+        &mut self,
+        meshes: &[Mesh],
+        rect: RendererRect,
+        effects: &SpriteEffects,
+        filter: TextureFilter,
+    ) {
+        let Color(tint_r, tint_g, tint_b, tint_a) = &effects.tint;
+        let meshes: Vec<Mesh> = meshes
+            .iter()
+            .map(|mesh| {
+                let Color(r, g, b, a) = &mesh.color;
+                Mesh {
+                    color: Color(r * tint_r, g * tint_g, b * tint_b, a * tint_a),
+                    ..*mesh
+                }
+            })
+            .collect();
+
+        let sprite_batches_before = self.sprite_batcher.split();
+        self.mesh_batcher.push(
+            &meshes,
+            &rect,
+            effects.hue_shift,
+            filter,
+            sprite_batches_before,
+        );
+    }
+
+    pub fn flush(
+        &mut self,
+        display: &Display<WindowSurface>,
+        frame: &mut Frame,
+        base_draw_parameters: &DrawParameters,
+    ) -> Result<(), RenderError> {
+        let result = self.draw_all(display, frame, base_draw_parameters);
+
+        // Start the next frame from scratch even if this one failed
+        self.sprite_batcher.clear();
+        self.mesh_batcher.clear();
+        result
+    }
+
+    fn draw_all(
+        &mut self,
+        display: &Display<WindowSurface>,
+        frame: &mut Frame,
+        base_draw_parameters: &DrawParameters,
+    ) -> Result<(), RenderError> {
+        self.sprite_batcher.upload(display)?;
+        self.mesh_batcher.upload(display)?;
+
+        // Meshes go in between the sprite batches, in the order everything
+        // was drawn
+        let mut drawn = 0;
+        for mesh in 0..self.mesh_batcher.mesh_count() {
+            let before = self.mesh_batcher.sprite_batches_before(mesh);
+            self.sprite_batcher
+                .draw(frame, drawn..before, base_draw_parameters)?;
+            drawn = before;
+            self.mesh_batcher.draw(frame, mesh, base_draw_parameters)?;
+        }
+
+        self.sprite_batcher.draw(
+            frame,
+            drawn..self.sprite_batcher.batch_count(),
+            base_draw_parameters,
+        )
     }
 }
@@ -0,0 +1,281 @@
+use crate::rendering::{AlphaMode, Mesh, RenderError, RendererRect, TextureFilter};
+use glium::draw_parameters::{Depth, DepthTest};
+use glium::index::PrimitiveType;
+use glium::texture::Texture2d;
+use glium::uniforms::{Sampler, SamplerWrapFunction};
+use glium::{
+    implement_vertex, uniform, Display, DrawParameters, Frame, IndexBuffer, Program, Surface,
+    VertexBuffer,
+};
+use glutin::surface::WindowSurface;
+use std::rc::Rc;
+
+// How many vertices and indices the buffers have room for before they first
+// need to grow
+const INITIAL_VERTEX_CAPACITY: usize = 1024;
+const INITIAL_INDEX_CAPACITY: usize = 4096;
+
+#[derive(Copy, Clone)]
+struct Vertex {
+    position: [f32; 3],
+    normal: [f32; 3],
+    tex_coords: [f32; 2],
+}
+implement_vertex!(Vertex, position, normal, tex_coords);
+
+const VERTEX_SHADER_SOURCE: &str = r#"
+#version 100
+attribute highp vec3 position;
+attribute mediump vec3 normal;
+attribute highp vec2 tex_coords;
+varying mediump vec3 v_normal;
+varying highp vec2 v_tex_coords;
+
+void main() {
+    gl_Position = vec4(position, 1.0);
+    v_normal = normal;
+    v_tex_coords = tex_coords;
+}
+"#;
+
+const FRAGMENT_SHADER_SOURCE: &str = concat!(
+    r#"
+#version 100
+uniform lowp sampler2D tex;
+uniform lowp vec4 color;
+uniform highp float hue_shift;
+// Below zero for blending, and one or more to ignore alpha altogether
+uniform lowp float alpha_cutoff;
+varying mediump vec3 v_normal;
+varying highp vec2 v_tex_coords;
+
+// Up and to the left of the viewer, so fursona are lit from the front
+const mediump vec3 LIGHT_DIRECTION = vec3(-0.4, 0.6, 0.69);
+const lowp float AMBIENT = 0.55;
+
+"#,
+    include_str!("shift_hue.glsl"),
+    r#"
+void main() {
+    lowp vec4 base = texture2D(tex, v_tex_coords) * color;
+    if (alpha_cutoff >= 1.0) {
+        base.a = color.a;
+    } else if (base.a < alpha_cutoff) {
+        discard;
+    }
+
+    mediump float diffuse = max(dot(normalize(v_normal), LIGHT_DIRECTION), 0.0);
+    lowp float light = AMBIENT + (1.0 - AMBIENT) * diffuse;
+    gl_FragColor = vec4(shift_hue(base.rgb, hue_shift) * light, base.a);
+}
+"#
+);
+
+// One mesh, waiting to be drawn over the sprite batches that came before it
+struct MeshDraw {
+    texture: Option<Rc<Texture2d>>,
+    filter: TextureFilter,
+    color: [f32; 4],
+    hue_shift: f32,
+    alpha_cutoff: f32,
+    // Which of the indices are this mesh's
+    start: usize,
+    end: usize,
+    // Set on the first of each group of meshes sharing the depth buffer
+    clears_depth: bool,
+    sprite_batches_before: usize,
+}
+
+// Collects every mesh drawn during a frame into one vertex buffer and one
+// index buffer. Unlike quads, every mesh takes its own draw call, and each
+// group of them has the depth buffer to itself.
+pub struct MeshBatcher {
+    program: Program,
+    vertex_buffer: VertexBuffer<Vertex>,
+    index_buffer: IndexBuffer<u32>,
+    // Bound for meshes without a texture, since every draw call needs some
+    // texture for the sampler
+    blank_texture: Texture2d,
+    vertices: Vec<Vertex>,
+    indices: Vec<u32>,
+    draws: Vec<MeshDraw>,
+}
+
+impl MeshBatcher {
+    pub fn new(display: &Display<WindowSurface>) -> Result<Self, RenderError> {
+        let program =
+            Program::from_source(display, VERTEX_SHADER_SOURCE, FRAGMENT_SHADER_SOURCE, None)?;
+        let vertex_buffer = VertexBuffer::empty_dynamic(display, INITIAL_VERTEX_CAPACITY)?;
+        let index_buffer = IndexBuffer::empty_dynamic(
+            display,
+            PrimitiveType::TrianglesList,
+            INITIAL_INDEX_CAPACITY,
+        )?;
+        let blank_texture = Texture2d::new(display, vec![vec![(255u8, 255u8, 255u8, 255u8)]])?;
+
+        Ok(Self {
+            program,
+            vertex_buffer,
+            index_buffer,
+            blank_texture,
+            vertices: Vec::new(),
+            indices: Vec::new(),
+            draws: Vec::new(),
+        })
+    }
+
+    // Queues up meshes that share the depth buffer, so that they hide each
+    // other properly but nothing drawn before them. `sprite_batches_before`
+    // is how many of the sprite batches to draw first.
+    pub fn push(
+        &mut self,
+        meshes: &[Mesh],
+        rect: &RendererRect,
+        hue_shift: f32,
+        filter: TextureFilter,
+        sprite_batches_before: usize,
+    ) {
+        // Multiply size by 2.0 because the incoming rect size goes from
+        // 0.0 -> 1.0 for the whole coordinate system, which spans -1.0 -> 1.0
+        let scale_x = if rect.pixel_width > 0.0 {
+            rect.width * 2.0 / rect.pixel_width
+        } else {
+            0.0
+        };
+        let scale_y = if rect.pixel_height > 0.0 {
+            rect.height * 2.0 / rect.pixel_height
+        } else {
+            0.0
+        };
+
+        for (index, mesh) in meshes.iter().enumerate() {
+            let base = self.vertices.len() as u32;
+            self.vertices.extend(mesh.vertices.iter().map(|vertex| {
+                let [x, y, depth] = vertex.position;
+                Vertex {
+                    position: [
+                        rect.x + x * scale_x,
+                        rect.y - y * scale_y,
+                        depth * 2.0 - 1.0,
+                    ],
+                    normal: vertex.normal,
+                    tex_coords: vertex.tex_coords,
+                }
+            }));
+
+            let start = self.indices.len();
+            self.indices
+                .extend(mesh.indices.iter().map(|index| base + index));
+
+            self.draws.push(MeshDraw {
+                texture: mesh.texture.cloned(),
+                filter,
+                color: mesh.color.to_array(),
+                hue_shift,
+                alpha_cutoff: match mesh.alpha {
+                    AlphaMode::Opaque => 1.0,
+                    AlphaMode::Mask(cutoff) => cutoff,
+                    AlphaMode::Blend => -1.0,
+                },
+                start,
+                end: self.indices.len(),
+                clears_depth: index == 0,
+                sprite_batches_before,
+            });
+        }
+    }
+
+    pub fn mesh_count(&self) -> usize {
+        self.draws.len()
+    }
+
+    pub fn sprite_batches_before(&self, mesh: usize) -> usize {
+        self.draws[mesh].sprite_batches_before
+    }
+
+    // Starts the next frame from scratch, but hangs on to the allocations
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+        self.indices.clear();
+        self.draws.clear();
+    }
+
+    // Sends every mesh pushed since the last clear to the GPU, ready to draw
+    pub fn upload(&mut self, display: &Display<WindowSurface>) -> Result<(), RenderError> {
+        if self.draws.is_empty() {
+            return Ok(());
+        }
+
+        // Grow the buffers if this frame has more to draw than any before it
+        if self.vertices.len() > self.vertex_buffer.len() {
+            self.vertex_buffer =
+                VertexBuffer::empty_dynamic(display, self.vertices.len().next_power_of_two())?;
+        }
+        if self.indices.len() > self.index_buffer.len() {
+            self.index_buffer = IndexBuffer::empty_dynamic(
+                display,
+                PrimitiveType::TrianglesList,
+                self.indices.len().next_power_of_two(),
+            )?;
+        }
+
+        self.vertex_buffer
+            .slice(0..self.vertices.len())
+            .expect("vertex buffer was grown to fit")
+            .write(&self.vertices);
+        self.index_buffer
+            .slice(0..self.indices.len())
+            .expect("index buffer was grown to fit")
+            .write(&self.indices);
+
+        Ok(())
+    }
+
+    // Draws one of the uploaded meshes, over whatever's already in the frame
+    pub fn draw(
+        &self,
+        frame: &mut Frame,
+        mesh: usize,
+        base_draw_parameters: &DrawParameters,
+    ) -> Result<(), RenderError> {
+        let draw = &self.draws[mesh];
+        let texture = draw.texture.as_deref().unwrap_or(&self.blank_texture);
+        let sampler = Sampler::new(texture)
+            .magnify_filter(draw.filter.magnify())
+            .minify_filter(draw.filter.minify())
+            .wrap_function(SamplerWrapFunction::Repeat);
+        let indices = self
+            .index_buffer
+            .slice(draw.start..draw.end)
+            .expect("index buffer was grown to fit");
+
+        let draw_parameters = DrawParameters {
+            depth: Depth {
+                test: DepthTest::IfLess,
+                write: true,
+                ..Default::default()
+            },
+            ..base_draw_parameters.clone()
+        };
+
+        // Each group of meshes only hides parts of itself, and is otherwise
+        // drawn over everything before it like a sprite would be
+        if draw.clears_depth {
+            frame.clear_depth(1.0);
+        }
+        frame.draw(
+            &self.vertex_buffer,
+            indices,
+            &self.program,
+            &uniform! {
+                tex: sampler,
+                color: draw.color,
+                hue_shift: draw.hue_shift,
+                alpha_cutoff: draw.alpha_cutoff,
+            },
+            &draw_parameters,
+        )?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,7 @@
+// Rotates the color around the grey axis of the RGB cube
+highp vec3 shift_hue(highp vec3 color, highp float degrees) {
+    highp float angle = radians(degrees);
+    highp vec3 axis = vec3(0.57735);
+    highp float c = cos(angle);
+    return color * c + cross(axis, color) * sin(angle) + axis * dot(axis, color) * (1.0 - c);
+}
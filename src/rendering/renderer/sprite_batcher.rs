@@ -0,0 +1,404 @@
+use crate::rendering::{Color, RenderError, RendererRect, TextureFilter, Transform2D};
+use glium::index::{NoIndices, PrimitiveType};
+use glium::texture::Texture2d;
+use glium::uniforms::Sampler;
+use glium::{
+    implement_vertex, uniform, Display, DrawParameters, Frame, Program, Surface, VertexBuffer,
+};
+use glutin::surface::WindowSurface;
+use std::ops::Range;
+use std::rc::Rc;
+
+// How many quads the vertex buffer has room for before it first needs to grow.
+const INITIAL_QUAD_CAPACITY: usize = 64;
+const VERTICES_PER_QUAD: usize = 6;
+
+// What the fragment shader does with a quad
+const MODE_SOLID: f32 = 0.0;
+const MODE_SPRITE: f32 = 1.0;
+const MODE_SOFT_ELLIPSE: f32 = 2.0;
+
+#[derive(Copy, Clone)]
+struct Vertex {
+    position: [f32; 2],
+    tex_coords: [f32; 2],
+    // The part of the texture the quad's image occupies, as [left, bottom,
+    // right, top], so that outlines don't pick up neighbouring images.
+    tex_bounds: [f32; 4],
+    // Where this vertex sits within its quad, from 0.0 to 1.0 in each
+    // dimension. Used to cut out the middle of outlines.
+    internal_pos: [f32; 2],
+    thickness: [f32; 2],
+    tint: [f32; 4],
+    // In degrees
+    hue_shift: f32,
+    // Fully transparent for no outline
+    outline: [f32; 4],
+    mode: f32,
+}
+implement_vertex!(
+    Vertex,
+    position,
+    tex_coords,
+    tex_bounds,
+    internal_pos,
+    thickness,
+    tint,
+    hue_shift,
+    outline,
+    mode
+);
+
+const VERTEX_SHADER_SOURCE: &str = r#"
+#version 100
+attribute highp vec2 position;
+attribute highp vec2 tex_coords;
+attribute highp vec4 tex_bounds;
+attribute lowp vec2 internal_pos;
+attribute lowp vec2 thickness;
+attribute lowp vec4 tint;
+attribute highp float hue_shift;
+attribute lowp vec4 outline;
+attribute lowp float mode;
+varying highp vec2 v_tex_coords;
+varying highp vec4 v_tex_bounds;
+varying lowp vec2 v_internal_pos;
+varying lowp vec2 v_thickness;
+varying lowp vec4 v_tint;
+varying highp float v_hue_shift;
+varying lowp vec4 v_outline;
+varying lowp float v_mode;
+
+void main() {
+    gl_Position = vec4(position, 0.0, 1.0);
+    v_tex_coords = tex_coords;
+    v_tex_bounds = tex_bounds;
+    v_internal_pos = internal_pos;
+    v_thickness = thickness;
+    v_tint = tint;
+    v_hue_shift = hue_shift;
+    v_outline = outline;
+    v_mode = mode;
+}
+"#;
+
+const FRAGMENT_SHADER_SOURCE: &str = concat!(
+    r#"
+#version 100
+uniform lowp sampler2D tex;
+// The size of one texel in texture coordinates
+uniform highp vec2 texel_size;
+varying highp vec2 v_tex_coords;
+varying highp vec4 v_tex_bounds;
+varying lowp vec2 v_internal_pos;
+varying lowp vec2 v_thickness;
+varying lowp vec4 v_tint;
+varying highp float v_hue_shift;
+varying lowp vec4 v_outline;
+varying lowp float v_mode;
+
+"#,
+    include_str!("shift_hue.glsl"),
+    r#"
+lowp float alpha_at(highp vec2 tex_coords) {
+    highp vec2 clamped = clamp(tex_coords, v_tex_bounds.xy, v_tex_bounds.zw);
+    return texture2D(tex, clamped).a;
+}
+
+void main() {
+    if (v_mode > 1.5) {
+        // Soft ellipse, fading out towards the edges
+        lowp float from_center = length((v_internal_pos - 0.5) * 2.0);
+        gl_FragColor = vec4(v_tint.rgb, v_tint.a * (1.0 - smoothstep(0.3, 1.0, from_center)));
+        return;
+    }
+
+    if (
+        v_internal_pos.x > v_thickness.x &&
+        v_internal_pos.x < 1.0 - v_thickness.x &&
+        v_internal_pos.y > v_thickness.y &&
+        v_internal_pos.y < 1.0 - v_thickness.y
+    ) {
+        discard;
+    }
+
+    if (v_mode < 0.5) {
+        gl_FragColor = v_tint;
+        return;
+    }
+
+    lowp vec4 color = texture2D(tex, v_tex_coords);
+
+    // Transparent pixels right next to opaque ones form the outline
+    if (v_outline.a > 0.0 && color.a < 0.5) {
+        lowp float neighbors = max(
+            max(
+                alpha_at(v_tex_coords + vec2(texel_size.x, 0.0)),
+                alpha_at(v_tex_coords - vec2(texel_size.x, 0.0))
+            ),
+            max(
+                alpha_at(v_tex_coords + vec2(0.0, texel_size.y)),
+                alpha_at(v_tex_coords - vec2(0.0, texel_size.y))
+            )
+        );
+        if (neighbors >= 0.5) {
+            gl_FragColor = v_outline;
+            return;
+        }
+    }
+
+    color.rgb = shift_hue(color.rgb, v_hue_shift);
+    gl_FragColor = color * v_tint;
+}
+"#
+);
+
+// A run of consecutive quads that can all be drawn with a single draw call,
+// because they all sample from the same texture in the same way (or don't
+// sample at all).
+struct Batch {
+    texture: Option<Rc<Texture2d>>,
+    filter: TextureFilter,
+    start: usize,
+    end: usize,
+}
+
+pub enum QuadShape {
+    Rect,
+    // An ellipse filling the quad that fades out towards its edges. Always a
+    // solid color.
+    SoftEllipse,
+}
+
+pub struct Quad<'a> {
+    pub rect: RendererRect,
+    // When set, places the quad's corners within the rect rather than having
+    // it fill the rect. It maps from the unit square (y pointing down) to
+    // device pixels from the rect's top-left corner.
+    pub transform: Option<Transform2D>,
+    pub shape: QuadShape,
+    // The texture to sample from, and where within it as [x, y, width,
+    // height] in texture coordinates. Quads without one are a solid color.
+    pub texture: Option<(&'a Rc<Texture2d>, [f32; 4])>,
+    // Ignored for quads without a texture
+    pub filter: TextureFilter,
+    pub tint: &'a Color,
+    // These two only apply to quads with a texture. Hue shift is in degrees.
+    pub hue_shift: f32,
+    pub outline: &'a Color,
+    // Thickness measure from 0.0 to 1.0 and is a percentage of the
+    // provided rect in each dimension. A thickness of (0.2, 0.2)
+    // means the outline will be 20% of the provided rect on each
+    // side, and (1.0, 1.0) fills the whole thing.
+    pub thickness: (f32, f32),
+}
+
+// Collects every quad drawn during a frame into one vertex buffer, so that
+// the whole frame can be drawn with as few draw calls as possible. Quads are
+// drawn in the order they were added; a new draw call is only needed when
+// consecutive quads want different textures.
+pub struct SpriteBatcher {
+    program: Program,
+    vertex_buffer: VertexBuffer<Vertex>,
+    // Bound for batches that don't sample from anything, since every draw
+    // call needs some texture for the sampler
+    blank_texture: Texture2d,
+    vertices: Vec<Vertex>,
+    batches: Vec<Batch>,
+    // Stops the next quad from joining the last batch, so that something
+    // else can be drawn in between them
+    split: bool,
+}
+
+impl SpriteBatcher {
+    pub fn new(display: &Display<WindowSurface>) -> Result<Self, RenderError> {
+        let program =
+            Program::from_source(display, VERTEX_SHADER_SOURCE, FRAGMENT_SHADER_SOURCE, None)?;
+        let vertex_buffer =
+            VertexBuffer::empty_dynamic(display, INITIAL_QUAD_CAPACITY * VERTICES_PER_QUAD)?;
+        let blank_texture = Texture2d::new(display, vec![vec![(255u8, 255u8, 255u8, 255u8)]])?;
+
+        Ok(Self {
+            program,
+            vertex_buffer,
+            blank_texture,
+            vertices: Vec::new(),
+            batches: Vec::new(),
+            split: false,
+        })
+    }
+
+    pub fn push(&mut self, quad: Quad) {
+        let start = self.vertices.len();
+
+        // Multiply size by 2.0 because the incoming rect size goes from
+        // 0.0 -> 1.0 for the whole coordinate system, which spans -1.0 -> 1.0
+        let rect = &quad.rect;
+        let corner = |x: f32, y: f32| match &quad.transform {
+            None => [
+                rect.x + x * rect.width * 2.0,
+                rect.y - y * rect.height * 2.0,
+            ],
+            Some(transform) => {
+                let (pixel_x, pixel_y) = transform.apply(x, y);
+                let scale_x = if rect.pixel_width > 0.0 {
+                    rect.width * 2.0 / rect.pixel_width
+                } else {
+                    0.0
+                };
+                let scale_y = if rect.pixel_height > 0.0 {
+                    rect.height * 2.0 / rect.pixel_height
+                } else {
+                    0.0
+                };
+                [rect.x + pixel_x * scale_x, rect.y - pixel_y * scale_y]
+            }
+        };
+        let top_left = corner(0.0, 0.0);
+        let top_right = corner(1.0, 0.0);
+        let bottom_left = corner(0.0, 1.0);
+        let bottom_right = corner(1.0, 1.0);
+
+        let (texture, uv_rect) = match quad.texture {
+            Some((texture, uv_rect)) => (Some(texture), uv_rect),
+            None => (None, [0.0, 0.0, 1.0, 1.0]),
+        };
+        let (u_left, u_right) = (uv_rect[0], uv_rect[0] + uv_rect[2]);
+        let (v_bottom, v_top) = (uv_rect[1], uv_rect[1] + uv_rect[3]);
+
+        let tex_bounds = [
+            uv_rect[0],
+            uv_rect[1],
+            uv_rect[0] + uv_rect[2],
+            uv_rect[1] + uv_rect[3],
+        ];
+        let mode = match (&quad.shape, texture) {
+            (QuadShape::SoftEllipse, _) => MODE_SOFT_ELLIPSE,
+            (QuadShape::Rect, Some(_)) => MODE_SPRITE,
+            (QuadShape::Rect, None) => MODE_SOLID,
+        };
+
+        let vertex = |position: [f32; 2], tex_coords: [f32; 2], internal_pos: [f32; 2]| Vertex {
+            position,
+            tex_coords,
+            tex_bounds,
+            internal_pos,
+            thickness: [quad.thickness.0, quad.thickness.1],
+            tint: quad.tint.to_array(),
+            hue_shift: quad.hue_shift,
+            outline: quad.outline.to_array(),
+            mode,
+        };
+        self.vertices.extend_from_slice(&[
+            // BL
+            vertex(bottom_left, [u_left, v_bottom], [0.0, 0.0]),
+            // BR
+            vertex(bottom_right, [u_right, v_bottom], [1.0, 0.0]),
+            // TR
+            vertex(top_right, [u_right, v_top], [1.0, 1.0]),
+            // TR
+            vertex(top_right, [u_right, v_top], [1.0, 1.0]),
+            // TL
+            vertex(top_left, [u_left, v_top], [0.0, 1.0]),
+            // BL
+            vertex(bottom_left, [u_left, v_bottom], [0.0, 0.0]),
+        ]);
+        let end = self.vertices.len();
+
+        // Extend the current batch if this quad can share its draw call
+        if let Some(batch) = self.batches.last_mut().filter(|_| !self.split) {
+            let can_share = match (&batch.texture, texture) {
+                (_, None) | (None, Some(_)) => true,
+                (Some(current), Some(texture)) => {
+                    Rc::ptr_eq(current, texture) && batch.filter == quad.filter
+                }
+            };
+
+            if can_share {
+                if batch.texture.is_none() {
+                    batch.texture = texture.cloned();
+                    batch.filter = quad.filter;
+                }
+                batch.end = end;
+                return;
+            }
+        }
+
+        self.batches.push(Batch {
+            texture: texture.cloned(),
+            filter: quad.filter,
+            start,
+            end,
+        });
+        self.split = false;
+    }
+
+    // Makes sure everything pushed so far can be drawn separately from
+    // everything pushed after, returning how many batches there are so far.
+    pub fn split(&mut self) -> usize {
+        self.split = true;
+        self.batches.len()
+    }
+
+    pub fn batch_count(&self) -> usize {
+        self.batches.len()
+    }
+
+    // Starts the next frame from scratch, but hangs on to the allocations
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+        self.batches.clear();
+        self.split = false;
+    }
+
+    // Sends every quad pushed since the last clear to the GPU, ready to draw
+    pub fn upload(&mut self, display: &Display<WindowSurface>) -> Result<(), RenderError> {
+        if self.vertices.is_empty() {
+            return Ok(());
+        }
+
+        // Grow the buffer if this frame has more quads than any before it
+        if self.vertices.len() > self.vertex_buffer.len() {
+            self.vertex_buffer =
+                VertexBuffer::empty_dynamic(display, self.vertices.len().next_power_of_two())?;
+        }
+        self.vertex_buffer
+            .slice(0..self.vertices.len())
+            .expect("vertex buffer was grown to fit")
+            .write(&self.vertices);
+
+        Ok(())
+    }
+
+    // Draws some of the uploaded batches, in order
+    pub fn draw(
+        &self,
+        frame: &mut Frame,
+        batches: Range<usize>,
+        base_draw_parameters: &DrawParameters,
+    ) -> Result<(), RenderError> {
+        let index_buffer = NoIndices(PrimitiveType::TrianglesList);
+        for batch in self.batches[batches].iter() {
+            let texture = batch.texture.as_deref().unwrap_or(&self.blank_texture);
+            let (width, height) = texture.dimensions();
+            let texel_size = [1.0 / width as f32, 1.0 / height as f32];
+            let sampler = Sampler::new(texture)
+                .magnify_filter(batch.filter.magnify())
+                .minify_filter(batch.filter.minify());
+            let vertices = self
+                .vertex_buffer
+                .slice(batch.start..batch.end)
+                .expect("vertex buffer was grown to fit");
+
+            frame.draw(
+                vertices,
+                index_buffer,
+                &self.program,
+                &uniform! { tex: sampler, texel_size: texel_size },
+                base_draw_parameters,
+            )?;
+        }
+
+        Ok(())
+    }
+}
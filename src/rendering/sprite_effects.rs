@@ -0,0 +1,27 @@
+use super::Color;
+
+// Adjustments made to a sprite as it's drawn. Settings pick the starting
+// point, and behaviors are free to change them from there.
+#[derive(Clone)]
+pub struct SpriteEffects {
+    // Multiplied with every pixel, so lowering the alpha fades the sprite out
+    pub tint: Color,
+    // Rotates the hue of every pixel, in degrees, for making differently
+    // colored variants of the same art
+    pub hue_shift: f32,
+    // Drawn around the edge of the opaque parts of the sprite, when set
+    pub outline: Option<Color>,
+    // Drawn as a soft ellipse on the ground beneath the sprite, when set
+    pub shadow: Option<Color>,
+}
+
+impl SpriteEffects {
+    pub fn new() -> Self {
+        Self {
+            tint: Color::WHITE,
+            hue_shift: 0.0,
+            outline: None,
+            shadow: None,
+        }
+    }
+}
@@ -0,0 +1,88 @@
+mod shelf_packer;
+
+use super::{AtlasRegion, TextureError};
+use glium::texture::{ClientFormat, MipmapsOption, RawImage2d, Texture2d, UncompressedFloatFormat};
+use glium::{Display, Rect, Surface};
+use glutin::surface::WindowSurface;
+use shelf_packer::ShelfPacker;
+use std::borrow::Cow;
+use std::rc::Rc;
+
+// The size of each atlas texture. Images larger than this get an atlas all
+// to themselves.
+const ATLAS_SIZE: u32 = 2048;
+
+// Empty space left around each image, so that sampling near the edge of one
+// image doesn't pick up the colors of its neighbors.
+const PADDING: u32 = 1;
+
+// A single texture that many images get packed into, so that they can all be
+// drawn without switching textures.
+pub struct TextureAtlas {
+    texture: Rc<Texture2d>,
+    packer: ShelfPacker,
+}
+
+impl TextureAtlas {
+    // Creates an empty atlas, which is guaranteed to have room for at least
+    // one image of the given size.
+    pub fn new(
+        display: &Display<WindowSurface>,
+        min_dimensions: (u32, u32),
+    ) -> Result<Self, TextureError> {
+        let width = ATLAS_SIZE.max(min_dimensions.0 + PADDING);
+        let height = ATLAS_SIZE.max(min_dimensions.1 + PADDING);
+        let texture = Texture2d::empty_with_format(
+            display,
+            UncompressedFloatFormat::U8U8U8U8,
+            // Images are written in one at a time, which would leave any
+            // mipmaps out of date
+            MipmapsOption::NoMipmap,
+            width,
+            height,
+        )?;
+
+        // The contents of a new texture are undefined, and anything showing
+        // through the padding should be transparent
+        texture.as_surface().clear_color(0.0, 0.0, 0.0, 0.0);
+
+        Ok(Self {
+            texture: Rc::new(texture),
+            packer: ShelfPacker::new(width, height),
+        })
+    }
+
+    // Copies the image into the atlas, if there's room for it. Pixels are
+    // RGBA, with the bottom row first as OpenGL expects.
+    pub fn insert(&mut self, pixels: &[u8], dimensions: (u32, u32)) -> Option<AtlasRegion> {
+        let (width, height) = dimensions;
+        let (x, y) = self.packer.allocate(width + PADDING, height + PADDING)?;
+
+        self.texture.write(
+            Rect {
+                left: x,
+                bottom: y,
+                width,
+                height,
+            },
+            RawImage2d {
+                data: Cow::Borrowed(pixels),
+                width,
+                height,
+                format: ClientFormat::U8U8U8U8,
+            },
+        );
+
+        let (atlas_width, atlas_height) = self.texture.dimensions();
+        Some(AtlasRegion::new(
+            self.texture.clone(),
+            [
+                x as f32 / atlas_width as f32,
+                y as f32 / atlas_height as f32,
+                width as f32 / atlas_width as f32,
+                height as f32 / atlas_height as f32,
+            ],
+            dimensions,
+        ))
+    }
+}
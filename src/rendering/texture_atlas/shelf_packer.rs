@@ -0,0 +1,133 @@
+// A row of rectangles sharing the same vertical space. Rectangles are placed
+// left to right, and the shelf is as tall as the first one placed on it.
+struct Shelf {
+    y: u32,
+    height: u32,
+    next_x: u32,
+}
+
+// Finds space for rectangles within a fixed area using the shelf algorithm:
+// each rectangle goes on the existing shelf that wastes the least height, or
+// on a new shelf stacked on top of the others if none of them have room. It's
+// not the tightest packing, but sprites tend to come in similar sizes, which
+// is exactly what shelves are good at.
+pub struct ShelfPacker {
+    width: u32,
+    height: u32,
+    shelves: Vec<Shelf>,
+}
+
+impl ShelfPacker {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            shelves: Vec::new(),
+        }
+    }
+
+    // Reserves space for a rectangle of the given size, returning the corner
+    // closest to the origin, or None if there's no room left.
+    pub fn allocate(&mut self, width: u32, height: u32) -> Option<(u32, u32)> {
+        if width > self.width || height > self.height {
+            return None;
+        }
+
+        let atlas_width = self.width;
+        let best_shelf = self
+            .shelves
+            .iter_mut()
+            .filter(|shelf| shelf.height >= height && atlas_width - shelf.next_x >= width)
+            .min_by_key(|shelf| shelf.height - height);
+
+        if let Some(shelf) = best_shelf {
+            let position = (shelf.next_x, shelf.y);
+            shelf.next_x += width;
+            return Some(position);
+        }
+
+        // Nothing fits on the existing shelves, so start a new one
+        let y = self
+            .shelves
+            .last()
+            .map(|shelf| shelf.y + shelf.height)
+            .unwrap_or(0);
+        if y + height > self.height {
+            return None;
+        }
+
+        self.shelves.push(Shelf {
+            y,
+            height,
+            next_x: width,
+        });
+        Some((0, y))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn places_rectangles_along_a_shelf() {
+        let mut packer = ShelfPacker::new(100, 100);
+        assert_eq!(packer.allocate(30, 20), Some((0, 0)));
+        assert_eq!(packer.allocate(30, 20), Some((30, 0)));
+        // Shorter rectangles still fit on the same shelf
+        assert_eq!(packer.allocate(30, 10), Some((60, 0)));
+    }
+
+    #[test]
+    fn starts_a_new_shelf_when_one_is_full() {
+        let mut packer = ShelfPacker::new(100, 100);
+        assert_eq!(packer.allocate(60, 20), Some((0, 0)));
+        assert_eq!(packer.allocate(60, 20), Some((0, 20)));
+        // Taller rectangles don't fit on the existing shelves either
+        assert_eq!(packer.allocate(10, 30), Some((0, 40)));
+        // But there's room left on the ends of them
+        assert_eq!(packer.allocate(40, 20), Some((60, 0)));
+    }
+
+    #[test]
+    fn picks_the_shelf_that_wastes_the_least_height() {
+        let mut packer = ShelfPacker::new(100, 100);
+        assert_eq!(packer.allocate(50, 40), Some((0, 0)));
+        assert_eq!(packer.allocate(60, 20), Some((0, 40)));
+        // Both shelves have room, but the second is closer in height
+        assert_eq!(packer.allocate(10, 15), Some((60, 40)));
+    }
+
+    #[test]
+    fn refuses_anything_too_big() {
+        let mut packer = ShelfPacker::new(100, 100);
+        assert_eq!(packer.allocate(101, 10), None);
+        assert_eq!(packer.allocate(10, 101), None);
+
+        // Or anything that doesn't fit in the space left over
+        assert_eq!(packer.allocate(100, 80), Some((0, 0)));
+        assert_eq!(packer.allocate(10, 30), None);
+        assert_eq!(packer.allocate(10, 20), Some((0, 80)));
+    }
+
+    #[test]
+    fn never_overlaps() {
+        let mut packer = ShelfPacker::new(256, 256);
+        let mut placed: Vec<(u32, u32, u32, u32)> = Vec::new();
+        for i in 0..200 {
+            let (width, height) = (8 + i * 7 % 40, 8 + i * 13 % 40);
+            if let Some((x, y)) = packer.allocate(width, height) {
+                assert!(x + width <= 256 && y + height <= 256);
+                for &(other_x, other_y, other_width, other_height) in &placed {
+                    let overlaps = x < other_x + other_width
+                        && other_x < x + width
+                        && y < other_y + other_height
+                        && other_y < y + height;
+                    assert!(!overlaps);
+                }
+                placed.push((x, y, width, height));
+            }
+        }
+        assert!(placed.len() > 10);
+    }
+}
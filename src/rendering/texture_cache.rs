@@ -1,41 +1,137 @@
-use std::rc::Rc;
-
-use glium::texture::CompressedTexture2d;
+use glium::Display;
+use glutin::surface::WindowSurface;
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use tracing::{debug, error};
+
+use super::{AtlasRegion, Palette, TextureAtlas, TextureError};
 
-use crate::stage::Stage;
+// The placeholder shown in place of images that couldn't be loaded: a
+// magenta and black checkerboard, which is hard to mistake for real art.
+const MISSING_TEXTURE_SIZE: u32 = 32;
+const MISSING_TEXTURE_CHECK_SIZE: u32 = 8;
+const MISSING_TEXTURE_COLORS: [[u8; 4]; 2] = [[255, 0, 255, 255], [0, 0, 0, 255]];
 
-pub struct TextureCache<'a> {
-    cache: HashMap<&'static str, Rc<CompressedTexture2d>>,
-    stage: &'a Stage,
+// Loads images and packs them into as few atlas textures as possible. One of
+// these is shared by every fursona instance, so each image is only ever
+// loaded once no matter how many instances use it. Recolored copies of an
+// image are cached separately, one per palette.
+pub struct TextureCache {
+    cache: HashMap<(PathBuf, Palette), AtlasRegion>,
+    atlases: Vec<TextureAtlas>,
+    missing_texture: Option<AtlasRegion>,
 }
 
-impl<'a> TextureCache<'a> {
-    pub fn new(stage: &'a Stage) -> Self {
+impl TextureCache {
+    pub fn new() -> Self {
         Self {
             cache: HashMap::new(),
-            stage,
+            atlases: Vec::new(),
+            missing_texture: None,
         }
     }
 
-    pub fn get(&mut self, filename: &'static str) -> Rc<CompressedTexture2d> {
+    // Returns the texture for the given image, recolored with the palette. If
+    // the image can't be loaded the placeholder is returned instead, so that
+    // the only way this fails is if we can't create textures at all.
+    pub fn get(
+        &mut self,
+        display: &Display<WindowSurface>,
+        filename: PathBuf,
+        palette: &Palette,
+    ) -> Result<AtlasRegion, TextureError> {
         // If it's cached, return the reference
-        if let Some(cached) = self.cache.get(filename) {
-            return cached.clone();
+        let key = (filename, palette.clone());
+        if let Some(cached) = self.cache.get(&key) {
+            return Ok(cached.clone());
         }
 
         // It isn't cached, so we need to create it
-        let created = Rc::new(self.load(filename));
-        self.cache.insert(filename, created.clone());
-        created
+        let created = match self.load(display, &key.0, palette) {
+            Ok(region) => region,
+            Err(e) => {
+                error!("{e}, using a placeholder instead");
+                self.missing_texture(display)?
+            }
+        };
+
+        // Cache it even if it's the placeholder, so that we only complain
+        // about each missing image once
+        self.cache.insert(key, created.clone());
+        Ok(created)
+    }
+
+    fn load(
+        &mut self,
+        display: &Display<WindowSurface>,
+        filename: &Path,
+        palette: &Palette,
+    ) -> Result<AtlasRegion, TextureError> {
+        let mut image = match image::open(filename) {
+            Ok(image) => image.to_rgba8(),
+            Err(source) => {
+                return Err(TextureError::Image {
+                    path: filename.to_owned(),
+                    source,
+                })
+            }
+        };
+
+        palette.apply(&mut image);
+
+        // OpenGL expects the bottom row first
+        image::imageops::flip_vertical_in_place(&mut image);
+
+        let dimensions = image.dimensions();
+        self.insert(display, &image.into_raw(), dimensions)
     }
 
-    fn load(&self, filename: &str) -> CompressedTexture2d {
-        let image = image::open(Path::new(filename)).unwrap().to_rgba8();
-        let image_dimensions = image.dimensions();
-        let image =
-            glium::texture::RawImage2d::from_raw_rgba_reversed(&image.into_raw(), image_dimensions);
-        CompressedTexture2d::new(&self.stage.display, image).unwrap()
+    fn insert(
+        &mut self,
+        display: &Display<WindowSurface>,
+        pixels: &[u8],
+        dimensions: (u32, u32),
+    ) -> Result<AtlasRegion, TextureError> {
+        for atlas in self.atlases.iter_mut() {
+            if let Some(region) = atlas.insert(pixels, dimensions) {
+                return Ok(region);
+            }
+        }
+
+        // None of the existing atlases have room, so start a new one that's
+        // big enough for this image
+        debug!("Creating texture atlas #{}", self.atlases.len() + 1);
+        let mut atlas = TextureAtlas::new(display, dimensions)?;
+        let region = atlas.insert(pixels, dimensions);
+        self.atlases.push(atlas);
+        region.ok_or(TextureError::DoesNotFit {
+            width: dimensions.0,
+            height: dimensions.1,
+        })
+    }
+
+    fn missing_texture(
+        &mut self,
+        display: &Display<WindowSurface>,
+    ) -> Result<AtlasRegion, TextureError> {
+        if let Some(region) = &self.missing_texture {
+            return Ok(region.clone());
+        }
+
+        let mut pixels = Vec::new();
+        for y in 0..MISSING_TEXTURE_SIZE {
+            for x in 0..MISSING_TEXTURE_SIZE {
+                let check = (x / MISSING_TEXTURE_CHECK_SIZE + y / MISSING_TEXTURE_CHECK_SIZE) % 2;
+                pixels.extend_from_slice(&MISSING_TEXTURE_COLORS[check as usize]);
+            }
+        }
+
+        let region = self.insert(
+            display,
+            &pixels,
+            (MISSING_TEXTURE_SIZE, MISSING_TEXTURE_SIZE),
+        )?;
+        self.missing_texture = Some(region.clone());
+        Ok(region)
     }
 }
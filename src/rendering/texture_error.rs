@@ -0,0 +1,15 @@
+use std::path::PathBuf;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum TextureError {
+    #[error("couldn't load image {}: {source}", path.display())]
+    Image {
+        path: PathBuf,
+        source: image::ImageError,
+    },
+    #[error("couldn't upload texture: {0}")]
+    Upload(#[from] glium::texture::TextureCreationError),
+    #[error("a {width}x{height} image doesn't fit in a texture atlas")]
+    DoesNotFit { width: u32, height: u32 },
+}
@@ -0,0 +1,25 @@
+use glium::uniforms::{MagnifySamplerFilter, MinifySamplerFilter};
+
+// How textures are sampled when they're drawn at a different size to the
+// image. Pixel art wants `Nearest`, which keeps every pixel a crisp square.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TextureFilter {
+    Nearest,
+    Linear,
+}
+
+impl TextureFilter {
+    pub fn magnify(&self) -> MagnifySamplerFilter {
+        match self {
+            TextureFilter::Nearest => MagnifySamplerFilter::Nearest,
+            TextureFilter::Linear => MagnifySamplerFilter::Linear,
+        }
+    }
+
+    pub fn minify(&self) -> MinifySamplerFilter {
+        match self {
+            TextureFilter::Nearest => MinifySamplerFilter::Nearest,
+            TextureFilter::Linear => MinifySamplerFilter::Linear,
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use std::ops::Mul;
+
+// An affine transform in 2D:
+//   x' = a * x + c * y + tx
+//   y' = b * x + d * y + ty
+// Multiplying two together gives a transform that applies the right-hand one
+// first, as with matrices.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform2D {
+    pub a: f32,
+    pub b: f32,
+    pub c: f32,
+    pub d: f32,
+    pub tx: f32,
+    pub ty: f32,
+}
+
+impl Transform2D {
+    pub const IDENTITY: Transform2D = Transform2D {
+        a: 1.0,
+        b: 0.0,
+        c: 0.0,
+        d: 1.0,
+        tx: 0.0,
+        ty: 0.0,
+    };
+
+    pub fn translation(x: f32, y: f32) -> Self {
+        Self {
+            tx: x,
+            ty: y,
+            ..Self::IDENTITY
+        }
+    }
+
+    // Positive angles turn the x axis towards the y axis
+    pub fn rotation(degrees: f32) -> Self {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        Self {
+            a: cos,
+            b: sin,
+            c: -sin,
+            d: cos,
+            ..Self::IDENTITY
+        }
+    }
+
+    pub fn scale(x: f32, y: f32) -> Self {
+        Self {
+            a: x,
+            d: y,
+            ..Self::IDENTITY
+        }
+    }
+
+    pub fn apply(&self, x: f32, y: f32) -> (f32, f32) {
+        (
+            self.a * x + self.c * y + self.tx,
+            self.b * x + self.d * y + self.ty,
+        )
+    }
+}
+
+impl Mul for Transform2D {
+    type Output = Transform2D;
+
+    fn mul(self, rhs: Transform2D) -> Transform2D {
+        Transform2D {
+            a: self.a * rhs.a + self.c * rhs.b,
+            b: self.b * rhs.a + self.d * rhs.b,
+            c: self.a * rhs.c + self.c * rhs.d,
+            d: self.b * rhs.c + self.d * rhs.d,
+            tx: self.a * rhs.tx + self.c * rhs.ty + self.tx,
+            ty: self.b * rhs.tx + self.d * rhs.ty + self.ty,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(actual: (f32, f32), expected: (f32, f32)) {
+        assert!(
+            (actual.0 - expected.0).abs() < 0.0001 && (actual.1 - expected.1).abs() < 0.0001,
+            "{actual:?} isn't {expected:?}"
+        );
+    }
+
+    #[test]
+    fn identity_leaves_points_alone() {
+        assert_eq!(Transform2D::IDENTITY.apply(3.0, -4.0), (3.0, -4.0));
+        let transform = Transform2D::translation(1.0, 2.0);
+        assert_eq!(transform * Transform2D::IDENTITY, transform);
+        assert_eq!(Transform2D::IDENTITY * transform, transform);
+    }
+
+    #[test]
+    fn rotation_turns_x_towards_y() {
+        assert_near(Transform2D::rotation(90.0).apply(1.0, 0.0), (0.0, 1.0));
+        assert_near(Transform2D::rotation(90.0).apply(0.0, 1.0), (-1.0, 0.0));
+    }
+
+    #[test]
+    fn multiplying_applies_the_right_hand_side_first() {
+        let translate = Transform2D::translation(10.0, 0.0);
+        let scale = Transform2D::scale(2.0, 3.0);
+
+        // Scaled, then moved
+        assert_near((translate * scale).apply(1.0, 1.0), (12.0, 3.0));
+        // Moved, then scaled
+        assert_near((scale * translate).apply(1.0, 1.0), (22.0, 3.0));
+    }
+
+    #[test]
+    fn composition_matches_applying_one_after_the_other() {
+        let outer = Transform2D::translation(5.0, -2.0) * Transform2D::rotation(30.0);
+        let inner = Transform2D::scale(1.5, 0.5) * Transform2D::rotation(-75.0);
+        let (x, y) = inner.apply(2.0, 7.0);
+        assert_near((outer * inner).apply(2.0, 7.0), outer.apply(x, y));
+    }
+}
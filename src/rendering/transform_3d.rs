@@ -0,0 +1,106 @@
+use std::ops::Mul;
+
+// An affine transform in 3D, stored as a 4x4 matrix in columns, the way glTF
+// stores them. Multiplying two together gives a transform that applies the
+// right-hand one first, as with `Transform2D`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform3D {
+    pub columns: [[f32; 4]; 4],
+}
+
+impl Transform3D {
+    pub const IDENTITY: Transform3D = Transform3D {
+        columns: [
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ],
+    };
+
+    pub fn translation(x: f32, y: f32, z: f32) -> Self {
+        let mut transform = Self::IDENTITY;
+        transform.columns[3] = [x, y, z, 1.0];
+        transform
+    }
+
+    pub fn scale(x: f32, y: f32, z: f32) -> Self {
+        let mut transform = Self::IDENTITY;
+        transform.columns[0][0] = x;
+        transform.columns[1][1] = y;
+        transform.columns[2][2] = z;
+        transform
+    }
+
+    // From a unit quaternion, as [x, y, z, w]
+    pub fn rotation(quaternion: [f32; 4]) -> Self {
+        let [x, y, z, w] = quaternion;
+        Self {
+            columns: [
+                [
+                    1.0 - 2.0 * (y * y + z * z),
+                    2.0 * (x * y + z * w),
+                    2.0 * (x * z - y * w),
+                    0.0,
+                ],
+                [
+                    2.0 * (x * y - z * w),
+                    1.0 - 2.0 * (x * x + z * z),
+                    2.0 * (y * z + x * w),
+                    0.0,
+                ],
+                [
+                    2.0 * (x * z + y * w),
+                    2.0 * (y * z - x * w),
+                    1.0 - 2.0 * (x * x + y * y),
+                    0.0,
+                ],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+        }
+    }
+
+    // Positive angles turn the z axis towards the x axis
+    pub fn rotation_y(degrees: f32) -> Self {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let mut transform = Self::IDENTITY;
+        transform.columns[0] = [cos, 0.0, -sin, 0.0];
+        transform.columns[2] = [sin, 0.0, cos, 0.0];
+        transform
+    }
+
+    pub fn apply_point(&self, point: [f32; 3]) -> [f32; 3] {
+        let [x, y, z] = point;
+        let c = &self.columns;
+        [
+            c[0][0] * x + c[1][0] * y + c[2][0] * z + c[3][0],
+            c[0][1] * x + c[1][1] * y + c[2][1] * z + c[3][1],
+            c[0][2] * x + c[1][2] * y + c[2][2] * z + c[3][2],
+        ]
+    }
+
+    // Like `apply_point`, but ignoring translation, for directions
+    pub fn apply_vector(&self, vector: [f32; 3]) -> [f32; 3] {
+        let [x, y, z] = vector;
+        let c = &self.columns;
+        [
+            c[0][0] * x + c[1][0] * y + c[2][0] * z,
+            c[0][1] * x + c[1][1] * y + c[2][1] * z,
+            c[0][2] * x + c[1][2] * y + c[2][2] * z,
+        ]
+    }
+}
+
+impl Mul for Transform3D {
+    type Output = Transform3D;
+
+    fn mul(self, rhs: Transform3D) -> Transform3D {
+        let mut columns = [[0.0; 4]; 4];
+        for (column, rhs_column) in columns.iter_mut().zip(rhs.columns.iter()) {
+            for (row, value) in column.iter_mut().enumerate() {
+                *value = (0..4).map(|k| self.columns[k][row] * rhs_column[k]).sum();
+            }
+        }
+        Transform3D { columns }
+    }
+}
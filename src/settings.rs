@@ -1,64 +1,355 @@
-use crate::fursona::Fursona;
-use std::path::Path;
+use crate::fursona::animation::{FrameRect, ProceduralMotionStyle, ANIMATION_NAMES};
+use crate::fursona::behaviors::BehaviorKind;
+use crate::fursona::{Fursona, FursonaKeyframe, FursonaModel};
+use crate::rendering::{parse_hex_color, Color, Palette, SpriteEffects, TextureFilter};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
 
+mod backups;
+mod file_format;
 mod settings_file;
+mod shimeji;
 
-use settings_file::{CurrentSettingsFile, LoadSettingsResult};
+use file_format::FileFormat;
+use settings_file::{
+    CurrentSettingsFile, CurrentSettingsFileBehavior, CurrentSettingsFileEffects,
+    CurrentSettingsFileFilter, CurrentSettingsFileKeyframe, LoadSettingsResult,
+};
+use shimeji::ShimejiImport;
+use tracing::{error, info, warn};
+
+const SETTINGS_DIRECTORY_NAME: &str = "desktop-fursona";
+const SETTINGS_FILE_STEM: &str = "settings";
+const SHADOW_COLOR: Color = Color(0.0, 0.0, 0.0, 0.35);
+// The images the default fursona is drawn with, written out alongside a new
+// settings file so that it has something to show
+const DEFAULT_IMAGES: [(&str, &[u8]); 2] = [
+    (
+        "jack_by_nal_cinnamonspots.png",
+        include_bytes!("../jack_by_nal_cinnamonspots.png"),
+    ),
+    (
+        "jack_by_nal_cinnamonspots_flipped.png",
+        include_bytes!("../jack_by_nal_cinnamonspots_flipped.png"),
+    ),
+];
 
 pub struct Settings {
     pub fursona: Vec<Fursona>,
 }
 
 impl Settings {
-    fn from_settings_file(file: &CurrentSettingsFile) -> Self {
+    fn from_settings_file(file: &CurrentSettingsFile, asset_directory: &Path) -> Self {
+        // Relative paths in the file are relative to the file itself, rather
+        // than to wherever we happened to be launched from
+        let to_keyframes = |keyframes: &Vec<CurrentSettingsFileKeyframe>| {
+            keyframes
+                .iter()
+                .map(|keyframe| FursonaKeyframe {
+                    duration_ms: keyframe.duration_ms,
+                    image: asset_directory.join(&keyframe.image),
+                    anchor: keyframe.anchor.map(|[x, y]| (x, y)),
+                    hitbox: keyframe.hitbox.as_ref().map(|hitbox| FrameRect {
+                        x: hitbox.x,
+                        y: hitbox.y,
+                        width: hitbox.width,
+                        height: hitbox.height,
+                    }),
+                    events: keyframe.events.clone(),
+                })
+                .collect()
+        };
+
         Self {
             fursona: file
                 .fursona
                 .iter()
                 .map(|fursona| Fursona {
                     name: fursona.name.to_owned(),
+                    animations: ANIMATION_NAMES
+                        .into_iter()
+                        .filter_map(|name| {
+                            let keyframes = fursona.animations.get(name)?;
+                            Some((name, to_keyframes(keyframes)))
+                        })
+                        .collect(),
+                    skeleton: fursona
+                        .skeleton
+                        .as_ref()
+                        .map(|skeleton| asset_directory.join(skeleton)),
+                    model: fursona.model.as_ref().map(|model| FursonaModel {
+                        path: asset_directory.join(&model.path),
+                        height: model.height.max(0.0),
+                    }),
+                    // A scale of 0 would make the fursona vanish entirely
+                    scale: fursona.scale.max(1),
+                    filter: match fursona.filter {
+                        CurrentSettingsFileFilter::Nearest => TextureFilter::Nearest,
+                        CurrentSettingsFileFilter::Linear => TextureFilter::Linear,
+                    },
+                    // Monitors are numbered from 1 in the file
+                    monitor: fursona
+                        .monitor
+                        .and_then(|monitor| (monitor as usize).checked_sub(1)),
+                    behavior: match fursona.behavior {
+                        CurrentSettingsFileBehavior::Pace => BehaviorKind::Pace,
+                        CurrentSettingsFileBehavior::Platform => BehaviorKind::Platform,
+                        CurrentSettingsFileBehavior::Climb => BehaviorKind::Climb,
+                        CurrentSettingsFileBehavior::Jump => BehaviorKind::Jump,
+                    },
+                    effects: Settings::to_effects(&fursona.name, &fursona.effects),
+                    palette: Settings::to_palette(&fursona.name, &fursona.palette),
+                    crossfade_ms: fursona.crossfade_ms,
+                    speed: fursona.speed.max(0.0),
+                    walk_cycle_speed: fursona.walk_cycle_speed,
+                    // Squashing all the way would flatten the fursona to
+                    // nothing
+                    procedural_motion: ProceduralMotionStyle {
+                        bob: fursona.procedural_motion.bob.max(0.0),
+                        squash: fursona.procedural_motion.squash.clamp(0.0, 0.9),
+                        tilt: fursona.procedural_motion.tilt.clamp(-45.0, 45.0),
+                        breathe: fursona.procedural_motion.breathe.clamp(0.0, 0.9),
+                    },
                 })
                 .collect(),
         }
     }
 
-    pub fn load_or_create(filename: &str) -> Self {
+    fn to_effects(name: &str, effects: &CurrentSettingsFileEffects) -> SpriteEffects {
+        let tint = match &effects.tint {
+            Some(hex) => Color::from_hex(hex).unwrap_or_else(|| {
+                warn!("Ignoring {name}'s tint of {hex}, which isn't #rrggbb or #rrggbbaa");
+                Color::WHITE
+            }),
+            None => Color::WHITE,
+        };
+
+        SpriteEffects {
+            tint,
+            hue_shift: effects.hue_shift,
+            shadow: effects.shadow.then_some(SHADOW_COLOR),
+            ..SpriteEffects::new()
+        }
+    }
+
+    fn to_palette(name: &str, mappings: &BTreeMap<String, String>) -> Palette {
+        // Palettes swap colors and leave each pixel's alpha alone, so there's
+        // no alpha to give
+        let parse_rgb = |hex: &str| match parse_hex_color(hex) {
+            Some([r, g, b, _]) if hex.len() == "#rrggbb".len() => Some([r, g, b]),
+            _ => None,
+        };
+
+        let mut palette = Palette::new();
+        for (from, to) in mappings.iter() {
+            match (parse_rgb(from), parse_rgb(to)) {
+                (Some(from), Some(to)) => palette.insert(from, to),
+                _ => warn!(
+                    "Ignoring {name}'s palette entry {from} -> {to}, as both colors need to be \
+                     #rrggbb, without alpha"
+                ),
+            }
+        }
+
+        palette
+    }
+
+    // The location of the settings file when one isn't explicitly provided:
+    // the platform's config directory (`$XDG_CONFIG_HOME` on Linux), falling
+    // back to the working directory if the platform doesn't have one. Any
+    // supported format already there is used, preferring JSON if none is.
+    pub fn default_path() -> PathBuf {
+        let directory = match dirs::config_dir() {
+            Some(dir) => dir.join(SETTINGS_DIRECTORY_NAME),
+            None => PathBuf::from("."),
+        };
+
+        let base = directory.join(SETTINGS_FILE_STEM);
+        FileFormat::EXTENSIONS
+            .iter()
+            .map(|extension| base.with_extension(extension))
+            .find(|path| path.exists())
+            .unwrap_or_else(|| base.with_extension("json"))
+    }
+
+    pub fn load_or_create(filename: &Path) -> Self {
+        let asset_directory = filename.parent().unwrap_or(Path::new("."));
+        let display_name = filename.display();
+
         // Attempt to load the file if it already exists
-        if Path::new(filename).exists() {
-            println!("Attempting to load {filename}");
+        if filename.exists() {
+            info!("Attempting to load {display_name}");
             match CurrentSettingsFile::load(filename) {
                 LoadSettingsResult::Success { file, did_migrate } => {
-                    println!("Successfully loaded settings file");
-                    let parsed = Settings::from_settings_file(&file);
+                    info!("Successfully loaded settings file");
+                    let parsed = Settings::from_settings_file(&file, asset_directory);
 
                     if did_migrate {
-                        println!("Saving settings file to migrate to latest version");
+                        // Files from before animations were configurable
+                        // are migrated to use Jack's images, which used to
+                        // be built in
+                        Settings::write_default_images(&file, asset_directory);
+
+                        Settings::backup(filename);
+                        info!("Saving settings file to migrate to latest version");
                         if let Err(msg) = file.save(filename) {
-                            println!("Error saving migrated file: {msg}");
+                            error!("Error saving migrated file: {msg}");
                         }
                     }
 
                     return parsed;
                 }
                 LoadSettingsResult::Error(e) => {
-                    println!("Load of file {filename} failed: {e}");
+                    warn!("Load of file {display_name} failed: {e}");
+
+                    // We're about to replace this file with a fresh one, so
+                    // hang on to what was there in case it can be fixed by hand
+                    Settings::backup(filename);
                 }
             }
         } else {
-            println!("File {filename} doesn't exist");
+            info!("File {display_name} doesn't exist");
         }
 
         // The file didn't load, so we'll create a new file from scratch
         let created_file = CurrentSettingsFile::new();
-        let created = Settings::from_settings_file(&created_file);
+        let created = Settings::from_settings_file(&created_file, asset_directory);
 
-        // Let's serialize this file to the filesystem
-        println!("Saving new settings file to {filename}");
+        // Let's serialize this file to the filesystem, along with the images
+        // it uses
+        Settings::write_default_images(&created_file, asset_directory);
+        info!("Saving new settings file to {display_name}");
         if let Err(msg) = created_file.save(filename) {
-            println!("Error saving new file: {msg}");
+            error!("Error saving new file: {msg}");
         }
 
         // Return this newly created file
         created
     }
+
+    // Writes out whichever of the default images the file uses. Leaves any
+    // images already there alone, in case they've been edited.
+    fn write_default_images(file: &CurrentSettingsFile, asset_directory: &Path) {
+        for (name, contents) in DEFAULT_IMAGES {
+            let is_used = file.fursona.iter().any(|fursona| {
+                ANIMATION_NAMES
+                    .iter()
+                    .filter_map(|animation| fursona.animations.get(animation))
+                    .flatten()
+                    .any(|keyframe| keyframe.image == name)
+            });
+            let path = asset_directory.join(name);
+            if !is_used || path.exists() {
+                continue;
+            }
+
+            info!("Writing {}", path.display());
+            if let Err(e) = fs::write(&path, contents) {
+                error!("Error writing {}: {e}", path.display());
+            }
+        }
+    }
+
+    // The JSON Schema describing the settings file, for editors to use for
+    // autocompletion and validation.
+    pub fn schema_json() -> Result<String, String> {
+        settings_file::schema_json()
+    }
+
+    // Rewrites a settings file in another format, picked by the extension of
+    // the destination. The file is migrated to the latest version on the way.
+    pub fn convert(from: &Path, to: &Path) -> Result<(), String> {
+        match CurrentSettingsFile::load(from) {
+            LoadSettingsResult::Success { file, .. } => file.save(to),
+            LoadSettingsResult::Error(e) => Err(e),
+        }
+    }
+
+    // Adds a Shimeji-ee character to the settings file as a new fursona named
+    // after its image folder, copying its images in alongside. Returns
+    // everything that couldn't be carried over.
+    pub fn import_shimeji(filename: &Path, directory: &Path) -> Result<Vec<String>, String> {
+        let mut file = match filename.exists() {
+            true => match CurrentSettingsFile::load(filename) {
+                LoadSettingsResult::Success { file, .. } => file,
+                LoadSettingsResult::Error(e) => return Err(e),
+            },
+            false => CurrentSettingsFile {
+                fursona: Vec::new(),
+            },
+        };
+
+        // Going by the full path, so that `.` is named too
+        let name = directory
+            .canonicalize()
+            .ok()
+            .and_then(|path| Some(path.file_name()?.to_string_lossy().into_owned()))
+            .ok_or_else(|| format!("{} isn't a folder", directory.display()))?;
+        if file.fursona.iter().any(|fursona| fursona.name == name) {
+            return Err(format!("There's already a fursona called {name}"));
+        }
+
+        let asset_directory = filename.parent().unwrap_or(Path::new("."));
+        let import = ShimejiImport::load(directory, &name, asset_directory)?;
+
+        if filename.exists() {
+            Settings::backup(filename);
+        }
+        file.fursona.push(import.fursona);
+        if let Err(e) = file.save(filename) {
+            // Nothing refers to the copied images without the settings
+            let _ = fs::remove_dir_all(asset_directory.join(&name));
+            return Err(e);
+        }
+
+        Ok(import.untranslated)
+    }
+
+    fn backup(filename: &Path) {
+        match backups::create_backup(filename) {
+            Ok(backup) => info!("Backed up settings file to {}", backup.display()),
+            Err(msg) => error!("Error backing up settings file: {msg}"),
+        }
+    }
+
+    // Restores the settings file from one of the backups taken before it was
+    // migrated or replaced. Index 1 is the most recent backup.
+    pub fn rollback(filename: &Path, index: u32) -> Result<(), String> {
+        if index == 0 || index > backups::MAX_BACKUPS {
+            return Err(format!(
+                "Backups are numbered 1 to {}, but {index} was requested",
+                backups::MAX_BACKUPS
+            ));
+        }
+
+        backups::restore_backup(filename, index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_the_default_images_when_migrating_to_them() {
+        let directory = std::env::temp_dir().join(format!(
+            "desktop-fursona-settings-{}-migration",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        let filename = directory.join("settings.json");
+        fs::write(
+            &filename,
+            r#"{"version": "2", "fursona": [{"name": "Jack"}]}"#,
+        )
+        .unwrap();
+
+        let settings = Settings::load_or_create(&filename);
+        assert_eq!(settings.fursona.len(), 1);
+        for (name, contents) in DEFAULT_IMAGES {
+            assert_eq!(fs::read(directory.join(name)).unwrap(), contents);
+        }
+    }
 }
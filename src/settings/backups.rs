@@ -0,0 +1,112 @@
+use crate::atomic_write::write_atomically;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// How many backups we keep around. Index 1 is always the most recent, and
+// anything that would be shifted past this is discarded.
+pub const MAX_BACKUPS: u32 = 5;
+
+pub fn backup_path(filename: &Path, index: u32) -> PathBuf {
+    let mut path = OsString::from(filename.as_os_str());
+    path.push(format!(".bak.{index}"));
+    PathBuf::from(path)
+}
+
+// Copies the file to `<filename>.bak.1`, first shifting any existing backups
+// up by one (`.bak.1` -> `.bak.2`, and so on).
+pub fn create_backup(filename: &Path) -> Result<PathBuf, String> {
+    for index in (1..MAX_BACKUPS).rev() {
+        let from = backup_path(filename, index);
+        if from.exists() {
+            fs::rename(&from, backup_path(filename, index + 1)).map_err(|e| e.to_string())?;
+        }
+    }
+
+    let newest = backup_path(filename, 1);
+    fs::copy(filename, &newest).map_err(|e| e.to_string())?;
+    Ok(newest)
+}
+
+// Replaces the file with the contents of the backup at the given index. The
+// file being replaced is itself backed up first, so a rollback can be undone
+// by rolling back again.
+pub fn restore_backup(filename: &Path, index: u32) -> Result<(), String> {
+    let backup = backup_path(filename, index);
+    let contents = match fs::read(&backup) {
+        Ok(contents) => contents,
+        Err(e) => return Err(format!("Couldn't read {}: {e}", backup.display())),
+    };
+
+    if filename.exists() {
+        create_backup(filename)?;
+    }
+
+    write_atomically(filename, &contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A settings file in a fresh directory of its own
+    fn settings_file(test: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!(
+            "desktop-fursona-backups-{}-{test}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        root.join("settings.json")
+    }
+
+    fn read(path: &Path) -> String {
+        fs::read_to_string(path).unwrap()
+    }
+
+    #[test]
+    fn keeps_only_the_newest_backups() {
+        let filename = settings_file("rotation");
+        for version in 1..=MAX_BACKUPS + 2 {
+            fs::write(&filename, format!("{version}")).unwrap();
+            assert_eq!(create_backup(&filename), Ok(backup_path(&filename, 1)));
+        }
+
+        // Newest first, with the oldest two gone
+        for index in 1..=MAX_BACKUPS {
+            let version = MAX_BACKUPS + 3 - index;
+            assert_eq!(read(&backup_path(&filename, index)), format!("{version}"));
+        }
+        assert!(!backup_path(&filename, MAX_BACKUPS + 1).exists());
+        assert_eq!(read(&filename), format!("{}", MAX_BACKUPS + 2));
+    }
+
+    #[test]
+    fn restores_a_backup_and_backs_up_what_it_replaces() {
+        let filename = settings_file("restore");
+        for version in ["old", "older"].iter().rev() {
+            fs::write(&filename, version).unwrap();
+            create_backup(&filename).unwrap();
+        }
+        fs::write(&filename, "current").unwrap();
+
+        restore_backup(&filename, 2).unwrap();
+        assert_eq!(read(&filename), "older");
+        assert_eq!(read(&backup_path(&filename, 1)), "current");
+        assert_eq!(read(&backup_path(&filename, 2)), "old");
+        assert_eq!(read(&backup_path(&filename, 3)), "older");
+
+        // Which can be undone by restoring again
+        restore_backup(&filename, 1).unwrap();
+        assert_eq!(read(&filename), "current");
+    }
+
+    #[test]
+    fn refuses_to_restore_a_missing_backup() {
+        let filename = settings_file("missing");
+        fs::write(&filename, "current").unwrap();
+
+        assert!(restore_backup(&filename, 1).is_err());
+        assert_eq!(read(&filename), "current");
+    }
+}
@@ -0,0 +1,153 @@
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{Map, Value};
+use std::path::Path;
+
+// The serialization formats a settings file can be written in. Which one is
+// used is decided entirely by the file's extension.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FileFormat {
+    Json,
+    Toml,
+    Yaml,
+}
+
+impl FileFormat {
+    pub const EXTENSIONS: [&'static str; 4] = ["json", "toml", "yaml", "yml"];
+
+    pub fn from_path(filename: &Path) -> Result<Self, String> {
+        let extension = filename
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase());
+
+        match extension.as_deref() {
+            Some("json") => Ok(FileFormat::Json),
+            Some("toml") => Ok(FileFormat::Toml),
+            Some("yaml") | Some("yml") => Ok(FileFormat::Yaml),
+            _ => Err(format!(
+                "Don't know how to read {}; settings files must end in one of: .{}",
+                filename.display(),
+                FileFormat::EXTENSIONS.join(", .")
+            )),
+        }
+    }
+
+    pub fn parse<T: DeserializeOwned>(&self, contents: &str) -> Result<T, String> {
+        match self {
+            FileFormat::Json => serde_json::from_str(contents).map_err(|e| e.to_string()),
+            FileFormat::Toml => toml::from_str(contents).map_err(|e| e.to_string()),
+            FileFormat::Yaml => serde_yaml::from_str(contents).map_err(|e| e.to_string()),
+        }
+    }
+
+    // Serializes the value, pointing editors at the given JSON Schema in
+    // whichever way is conventional for the format.
+    pub fn serialize<T: Serialize>(&self, value: &T, schema: &str) -> Result<String, String> {
+        match self {
+            FileFormat::Json => {
+                let value = serde_json::to_value(value).map_err(|e| e.to_string())?;
+
+                // The schema reference goes first so it's the first thing a
+                // reader sees
+                let mut with_schema = Map::new();
+                with_schema.insert("$schema".to_owned(), Value::String(schema.to_owned()));
+                if let Value::Object(fields) = value {
+                    with_schema.extend(fields);
+                }
+
+                serde_json::to_string_pretty(&with_schema).map_err(|e| e.to_string())
+            }
+            FileFormat::Toml => {
+                // Directive understood by Taplo (Even Better TOML)
+                let contents = toml::to_string_pretty(value).map_err(|e| e.to_string())?;
+                Ok(format!("#:schema {schema}\n\n{contents}"))
+            }
+            FileFormat::Yaml => {
+                // Directive understood by yaml-language-server
+                let contents = serde_yaml::to_string(value).map_err(|e| e.to_string())?;
+                Ok(format!(
+                    "# yaml-language-server: $schema={schema}\n{contents}"
+                ))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    const SCHEMA: &str = "https://example.com/settings.schema.json";
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Frame {
+        image: String,
+        duration_ms: u32,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Settings {
+        name: String,
+        scale: f32,
+        frames: Vec<Frame>,
+    }
+
+    fn settings() -> Settings {
+        Settings {
+            name: "Jack".to_owned(),
+            scale: 1.5,
+            frames: vec![
+                Frame {
+                    image: "walk_1.png".to_owned(),
+                    duration_ms: 100,
+                },
+                Frame {
+                    image: "walk_2.png".to_owned(),
+                    duration_ms: 150,
+                },
+            ],
+        }
+    }
+
+    fn round_trip(format: FileFormat) -> String {
+        let contents = format.serialize(&settings(), SCHEMA).unwrap();
+        assert_eq!(format.parse::<Settings>(&contents), Ok(settings()));
+        contents
+    }
+
+    #[test]
+    fn picks_the_format_by_extension() {
+        let format = |filename: &str| FileFormat::from_path(Path::new(filename));
+        assert_eq!(format("settings.json"), Ok(FileFormat::Json));
+        assert_eq!(format("settings.toml"), Ok(FileFormat::Toml));
+        assert_eq!(format("settings.yaml"), Ok(FileFormat::Yaml));
+        assert_eq!(format("settings.yml"), Ok(FileFormat::Yaml));
+        assert_eq!(format("Settings.TOML"), Ok(FileFormat::Toml));
+        assert!(format("settings.txt").is_err());
+        assert!(format("settings").is_err());
+    }
+
+    #[test]
+    fn round_trips_json_with_the_schema_first() {
+        let contents = round_trip(FileFormat::Json);
+        assert!(contents.starts_with(&format!("{{\n  \"$schema\": \"{SCHEMA}\",")));
+    }
+
+    #[test]
+    fn round_trips_toml_with_a_schema_directive() {
+        let contents = round_trip(FileFormat::Toml);
+        assert!(contents.starts_with(&format!("#:schema {SCHEMA}\n")));
+    }
+
+    #[test]
+    fn round_trips_yaml_with_a_schema_comment() {
+        let contents = round_trip(FileFormat::Yaml);
+        assert!(contents.starts_with(&format!("# yaml-language-server: $schema={SCHEMA}\n")));
+    }
+
+    #[test]
+    fn reports_what_it_could_not_parse() {
+        assert!(FileFormat::Toml.parse::<Settings>("name = ").is_err());
+        assert!(FileFormat::Yaml.parse::<Settings>("name: [").is_err());
+    }
+}
@@ -1,10 +1,19 @@
 mod v1;
 mod v2;
+mod v3;
+mod v4;
 
+use super::file_format::FileFormat;
+use crate::atomic_write::write_atomically;
+use schemars::gen::SchemaGenerator;
+use schemars::schema::{RootSchema, Schema, SchemaObject};
+use schemars::schema_for;
 use serde::{Deserialize, Serialize};
 use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::info;
 
-// Structure for the JSON representation of settings.
+// Structure for the serialized representation of settings.
 // This will mirror exactly with what's on the filesystem,
 // whereas the more public Settings struct wraps this file
 // but converts types to be easier to work with at runtime.
@@ -15,12 +24,22 @@ enum SettingsFile {
     V1(v1::V1SettingsFile),
     #[serde(rename = "2")]
     V2(v2::V2SettingsFile),
+    #[serde(rename = "3")]
+    V3(v3::V3SettingsFile),
+    #[serde(rename = "4")]
+    V4(v4::V4SettingsFile),
 }
 
-pub type CurrentSettingsFile = v2::V2SettingsFile;
+pub type CurrentSettingsFile = v4::V4SettingsFile;
+pub type CurrentSettingsFileFursona = v4::V4SettingsFileFursona;
+pub type CurrentSettingsFileAnimations = v4::V4SettingsFileAnimations;
+pub type CurrentSettingsFileKeyframe = v4::V4SettingsFileKeyframe;
+pub type CurrentSettingsFileBehavior = v4::V4SettingsFileBehavior;
+pub type CurrentSettingsFileEffects = v4::V4SettingsFileEffects;
+pub type CurrentSettingsFileFilter = v4::V4SettingsFileFilter;
 
 impl SettingsFile {
-    fn load(filename: &str) -> Result<SettingsFile, String> {
+    fn load(filename: &Path) -> Result<SettingsFile, String> {
         // Read the file from the filesystem
         let file_contents = match fs::read_to_string(filename) {
             Ok(str) => str,
@@ -29,31 +48,78 @@ impl SettingsFile {
             }
         };
 
-        // Parse the string into a JSON object
-        let parsed: Result<SettingsFile, serde_json::Error> = serde_json::from_str(&file_contents);
-        match parsed {
-            Ok(j) => Ok(j),
-            Err(e) => Err(e.to_string()),
-        }
+        // Parse the string, in whichever format the extension says it's in
+        FileFormat::from_path(filename)?.parse(&file_contents)
     }
 
-    fn save(&self, filename: &str) -> Result<(), String> {
-        // Convert the structure to a JSON string
-        let file_contents = match serde_json::to_string_pretty(self) {
-            Ok(str) => str,
-            Err(msg) => {
-                return Err(msg.to_string());
-            }
+    fn save(&self, filename: &Path) -> Result<(), String> {
+        // Point editors at the schema we're about to write alongside the file
+        let schema_filename = schema_path(filename);
+        let schema_reference = match schema_filename.file_name() {
+            Some(name) => format!("./{}", name.to_string_lossy()),
+            None => return Err(format!("{} isn't a file", filename.display())),
         };
 
-        // Write the string to the filesystem
-        match fs::write(filename, file_contents) {
-            Ok(_) => Ok(()),
-            Err(e) => Err(e.to_string()),
+        // Convert the structure to a string, in whichever format the
+        // extension asks for
+        let file_contents = FileFormat::from_path(filename)?.serialize(self, &schema_reference)?;
+
+        // Make sure the directory we're saving into exists; the default
+        // location in the user's config directory won't on first launch
+        if let Some(parent) = filename.parent() {
+            if let Err(e) = fs::create_dir_all(parent) {
+                return Err(e.to_string());
+            }
         }
+
+        // Write the schema first, so the file never refers to one that
+        // doesn't exist
+        write_atomically(&schema_filename, schema_json()?.as_bytes())?;
+
+        // Write the string to the filesystem
+        write_atomically(filename, file_contents.as_bytes())
     }
 }
 
+// Where the schema for a settings file is written: alongside it, with the
+// extension swapped (`settings.json` -> `settings.schema.json`).
+fn schema_path(filename: &Path) -> PathBuf {
+    filename.with_extension("schema.json")
+}
+
+// Generates the JSON Schema for the current version of the settings file.
+// Older versions aren't described, since they get migrated on load anyway.
+fn schema() -> RootSchema {
+    let mut schema = schema_for!(CurrentSettingsFile);
+    schema.schema.metadata().title = Some("Desktop Fursona settings".to_owned());
+
+    // The version tag is added by SettingsFile rather than being a field on
+    // the versioned structs, so it needs describing by hand. Rather than
+    // duplicate the version string here, take it from what we'd serialize.
+    let version = serde_json::to_value(SettingsFile::V4(CurrentSettingsFile::new()))
+        .ok()
+        .and_then(|value| value.get("version").cloned());
+    let object = schema.schema.object();
+    object.properties.insert(
+        "version".to_owned(),
+        Schema::Object(SchemaObject {
+            const_value: version,
+            ..Default::default()
+        }),
+    );
+    object.required.insert("version".to_owned());
+    object.properties.insert(
+        "$schema".to_owned(),
+        SchemaGenerator::default().subschema_for::<String>(),
+    );
+
+    schema
+}
+
+pub fn schema_json() -> Result<String, String> {
+    serde_json::to_string_pretty(&schema()).map_err(|e| e.to_string())
+}
+
 pub enum LoadSettingsResult {
     Success {
         file: CurrentSettingsFile,
@@ -63,7 +129,7 @@ pub enum LoadSettingsResult {
 }
 
 impl CurrentSettingsFile {
-    pub fn load(filename: &str) -> LoadSettingsResult {
+    pub fn load(filename: &Path) -> LoadSettingsResult {
         let mut current = match SettingsFile::load(filename) {
             Ok(file) => file,
             Err(e) => return LoadSettingsResult::Error(e),
@@ -73,16 +139,26 @@ impl CurrentSettingsFile {
         loop {
             current = match current {
                 SettingsFile::V1(file) => {
-                    println!("Migrating settings v1 -> v2");
+                    info!("Migrating settings v1 -> v2");
                     did_migrate = true;
                     SettingsFile::V2(file.migrate())
                 }
-                SettingsFile::V2(file) => return LoadSettingsResult::Success { did_migrate, file },
+                SettingsFile::V2(file) => {
+                    info!("Migrating settings v2 -> v3");
+                    did_migrate = true;
+                    SettingsFile::V3(file.migrate())
+                }
+                SettingsFile::V3(file) => {
+                    info!("Migrating settings v3 -> v4");
+                    did_migrate = true;
+                    SettingsFile::V4(file.migrate())
+                }
+                SettingsFile::V4(file) => return LoadSettingsResult::Success { did_migrate, file },
             }
         }
     }
 
-    pub fn save(self, filename: &str) -> Result<(), String> {
-        SettingsFile::V2(self).save(filename)
+    pub fn save(self, filename: &Path) -> Result<(), String> {
+        SettingsFile::V4(self).save(filename)
     }
 }
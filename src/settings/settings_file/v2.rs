@@ -1,3 +1,4 @@
+use super::v3::{V3SettingsFile, V3SettingsFileAnimations, V3SettingsFileFursona};
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize)]
@@ -11,11 +12,16 @@ pub struct V2SettingsFile {
 }
 
 impl V2SettingsFile {
-    pub fn new() -> Self {
-        Self {
-            fursona: vec![V2SettingsFileFursona {
-                name: "Jack".to_owned(),
-            }],
+    pub fn migrate(&self) -> V3SettingsFile {
+        V3SettingsFile {
+            fursona: self
+                .fursona
+                .iter()
+                .map(|fursona| V3SettingsFileFursona {
+                    name: fursona.name.to_owned(),
+                    animations: V3SettingsFileAnimations::jack(),
+                })
+                .collect(),
         }
     }
 }
@@ -0,0 +1,118 @@
+use super::v4::{
+    V4SettingsFile, V4SettingsFileAnimations, V4SettingsFileFursona, V4SettingsFileKeyframe,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct V3SettingsFileKeyframe {
+    /// Path to the image for this frame. Relative paths are resolved against
+    /// the directory containing the settings file.
+    pub image: String,
+    /// How long this frame is shown for, in milliseconds.
+    pub duration_ms: u32,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct V3SettingsFileAnimations {
+    /// Frames shown while walking towards the left of the screen.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub walk_left: Option<Vec<V3SettingsFileKeyframe>>,
+    /// Frames shown while walking towards the right of the screen.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub walk_right: Option<Vec<V3SettingsFileKeyframe>>,
+}
+
+impl V3SettingsFileAnimations {
+    // The animations for Jack, which shipped as the hard-coded art for every
+    // fursona prior to v3.
+    pub fn jack() -> Self {
+        Self {
+            walk_left: Some(vec![V3SettingsFileKeyframe {
+                image: "jack_by_nal_cinnamonspots.png".to_owned(),
+                duration_ms: 2000,
+            }]),
+            walk_right: Some(vec![V3SettingsFileKeyframe {
+                image: "jack_by_nal_cinnamonspots_flipped.png".to_owned(),
+                duration_ms: 2000,
+            }]),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct V3SettingsFileFursona {
+    /// The name of this fursona.
+    pub name: String,
+    /// The animations this fursona is able to play. Behaviors that need an
+    /// animation that isn't listed here won't be used.
+    pub animations: V3SettingsFileAnimations,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct V3SettingsFile {
+    /// Every fursona that will appear on the desktop.
+    pub fursona: Vec<V3SettingsFileFursona>,
+}
+
+impl V3SettingsFile {
+    pub fn migrate(&self) -> V4SettingsFile {
+        let migrate_keyframes = |keyframes: &Option<Vec<V3SettingsFileKeyframe>>| {
+            keyframes.as_ref().map(|keyframes| {
+                keyframes
+                    .iter()
+                    .map(|keyframe| V4SettingsFileKeyframe {
+                        image: keyframe.image.to_owned(),
+                        duration_ms: keyframe.duration_ms,
+                        anchor: None,
+                        hitbox: None,
+                        events: Vec::new(),
+                    })
+                    .collect()
+            })
+        };
+
+        V4SettingsFile {
+            fursona: self
+                .fursona
+                .iter()
+                .map(|fursona| {
+                    V4SettingsFileFursona::new(
+                        fursona.name.to_owned(),
+                        V4SettingsFileAnimations {
+                            walk_left: migrate_keyframes(&fursona.animations.walk_left),
+                            walk_right: migrate_keyframes(&fursona.animations.walk_right),
+                            ..Default::default()
+                        },
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrates_animations_and_defaults_the_rest() {
+        let file = V3SettingsFile {
+            fursona: vec![V3SettingsFileFursona {
+                name: "Jack".to_owned(),
+                animations: V3SettingsFileAnimations::jack(),
+            }],
+        };
+
+        let migrated = file.migrate();
+        let fursona = &migrated.fursona[0];
+        assert_eq!(fursona.name, "Jack");
+        let walk_left = fursona.animations.walk_left.as_ref().unwrap();
+        assert_eq!(walk_left[0].image, "jack_by_nal_cinnamonspots.png");
+        assert_eq!(walk_left[0].duration_ms, 2000);
+        assert!(walk_left[0].anchor.is_none());
+        assert!(fursona.animations.idle_left.is_none());
+        assert_eq!(fursona.scale, 1);
+        assert_eq!(fursona.speed, 240.0);
+    }
+}
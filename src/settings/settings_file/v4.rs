@@ -0,0 +1,381 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct V4SettingsFileRect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct V4SettingsFileKeyframe {
+    /// Path to the image for this frame. Relative paths are resolved against
+    /// the directory containing the settings file.
+    pub image: String,
+    /// How long this frame is shown for, in milliseconds.
+    pub duration_ms: u32,
+    /// The point in the image, as `[x, y]` in pixels from its top-left
+    /// corner, that stays in the same place on screen from one frame to the
+    /// next, even between animations. Defaults to the middle of the bottom
+    /// edge, where the feet usually are.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub anchor: Option<[f32; 2]>,
+    /// The part of the image, in pixels from its top-left corner, that
+    /// counts as the fursona when the mouse is over it. Defaults to the whole
+    /// image.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hitbox: Option<V4SettingsFileRect>,
+    /// Names of events to fire when this frame is reached, such as
+    /// `footstep` or `blink`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<String>,
+}
+
+#[derive(Default, Serialize, Deserialize, JsonSchema)]
+pub struct V4SettingsFileAnimations {
+    /// Frames shown while walking towards the left of the screen.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub walk_left: Option<Vec<V4SettingsFileKeyframe>>,
+    /// Frames shown while walking towards the right of the screen.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub walk_right: Option<Vec<V4SettingsFileKeyframe>>,
+    /// Frames shown while standing still facing left. Used alongside
+    /// `idle_right`; both are needed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idle_left: Option<Vec<V4SettingsFileKeyframe>>,
+    /// Frames shown while standing still facing right.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idle_right: Option<Vec<V4SettingsFileKeyframe>>,
+    /// Frames shown while falling facing left. Used alongside `fall_right`;
+    /// both are needed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fall_left: Option<Vec<V4SettingsFileKeyframe>>,
+    /// Frames shown while falling facing right.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fall_right: Option<Vec<V4SettingsFileKeyframe>>,
+    /// Frames played once when turning around to face left, before walking
+    /// left.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub turn_left: Option<Vec<V4SettingsFileKeyframe>>,
+    /// Frames played once when turning around to face right, before walking
+    /// right.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub turn_right: Option<Vec<V4SettingsFileKeyframe>>,
+    /// Frames shown while climbing up the left edge of the screen, facing
+    /// it. Used alongside `climb_right`; both are needed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub climb_left: Option<Vec<V4SettingsFileKeyframe>>,
+    /// Frames shown while climbing up the right edge of the screen, facing
+    /// it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub climb_right: Option<Vec<V4SettingsFileKeyframe>>,
+    /// Frames shown while hanging from the top of the screen, moving
+    /// left. Used alongside `hang_right`; both are needed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hang_left: Option<Vec<V4SettingsFileKeyframe>>,
+    /// Frames shown while hanging from the top of the screen, moving
+    /// right.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hang_right: Option<Vec<V4SettingsFileKeyframe>>,
+    /// Frames played once when jumping towards the left, before
+    /// `airborne_left`. Used alongside `jump_start_right`; both are needed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jump_start_left: Option<Vec<V4SettingsFileKeyframe>>,
+    /// Frames played once when jumping towards the right, before
+    /// `airborne_right`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jump_start_right: Option<Vec<V4SettingsFileKeyframe>>,
+    /// Frames shown in the air during a jump towards the left. Used
+    /// alongside `airborne_right`; both are needed. Falls that aren't part of
+    /// a jump use `fall_left`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub airborne_left: Option<Vec<V4SettingsFileKeyframe>>,
+    /// Frames shown in the air during a jump towards the right.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub airborne_right: Option<Vec<V4SettingsFileKeyframe>>,
+    /// Frames played once on landing facing left, after a jump or a fall.
+    /// Used alongside `land_right`; both are needed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub land_left: Option<Vec<V4SettingsFileKeyframe>>,
+    /// Frames played once on landing facing right.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub land_right: Option<Vec<V4SettingsFileKeyframe>>,
+}
+
+impl V4SettingsFileAnimations {
+    // Looks an animation up by its name in `ANIMATION_NAMES`, which is the
+    // same as its field
+    pub fn get(&self, name: &str) -> Option<&Vec<V4SettingsFileKeyframe>> {
+        let animation = match name {
+            "walk_left" => &self.walk_left,
+            "walk_right" => &self.walk_right,
+            "idle_left" => &self.idle_left,
+            "idle_right" => &self.idle_right,
+            "fall_left" => &self.fall_left,
+            "fall_right" => &self.fall_right,
+            "turn_left" => &self.turn_left,
+            "turn_right" => &self.turn_right,
+            "climb_left" => &self.climb_left,
+            "climb_right" => &self.climb_right,
+            "hang_left" => &self.hang_left,
+            "hang_right" => &self.hang_right,
+            "jump_start_left" => &self.jump_start_left,
+            "jump_start_right" => &self.jump_start_right,
+            "airborne_left" => &self.airborne_left,
+            "airborne_right" => &self.airborne_right,
+            "land_left" => &self.land_left,
+            "land_right" => &self.land_right,
+            _ => return None,
+        };
+        animation.as_ref()
+    }
+
+    // The animations for Jack, the fursona new settings files start out with
+    pub fn jack() -> Self {
+        Self {
+            walk_left: Some(vec![V4SettingsFileKeyframe {
+                image: "jack_by_nal_cinnamonspots.png".to_owned(),
+                duration_ms: 2000,
+                anchor: None,
+                hitbox: None,
+                events: Vec::new(),
+            }]),
+            walk_right: Some(vec![V4SettingsFileKeyframe {
+                image: "jack_by_nal_cinnamonspots_flipped.png".to_owned(),
+                duration_ms: 2000,
+                anchor: None,
+                hitbox: None,
+                events: Vec::new(),
+            }]),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum V4SettingsFileFilter {
+    /// Keep every pixel a crisp square. Best for pixel art.
+    Nearest,
+    /// Blend between neighbouring pixels. Best for smooth, painted art.
+    #[default]
+    Linear,
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum V4SettingsFileBehavior {
+    /// Walk back and forth along the top of the screen.
+    #[default]
+    Pace,
+    /// Walk along the bottom of the screen and the tops of other windows,
+    /// jumping between them. Only supported on X11 for now.
+    Platform,
+    /// Walk along the bottom of the screen, climbing up its edges, hanging
+    /// along the top for a while and dropping back down. Needs the `climb`
+    /// animations.
+    Climb,
+    /// Walk along the bottom of the screen, leaping forward every so often.
+    /// Needs the `airborne` animations.
+    Jump,
+}
+
+#[derive(Default, Serialize, Deserialize, JsonSchema)]
+pub struct V4SettingsFileEffects {
+    /// A color to multiply the images by, as `#rrggbb` or `#rrggbbaa`. The
+    /// alpha fades the fursona out.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tint: Option<String>,
+    /// How far to rotate the hue of every pixel, in degrees, for making
+    /// differently colored variants of the same art.
+    #[serde(default)]
+    pub hue_shift: f32,
+    /// Whether to draw a soft shadow on the ground beneath the fursona.
+    #[serde(default)]
+    pub shadow: bool,
+}
+
+impl V4SettingsFileEffects {
+    fn is_default(&self) -> bool {
+        self.tint.is_none() && self.hue_shift == 0.0 && !self.shadow
+    }
+}
+
+#[derive(Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct V4SettingsFileProceduralMotion {
+    /// How far to bob up with each step while walking, in pixels of the art.
+    #[serde(default)]
+    #[schemars(range(min = 0))]
+    pub bob: f32,
+    /// How much to squash on landing and stretch while falling, as a
+    /// fraction of the fursona's height, such as `0.15`.
+    #[serde(default)]
+    #[schemars(range(min = 0, max = 0.9))]
+    pub squash: f32,
+    /// How far to lean into turns, in degrees, with negative angles leaning
+    /// back instead.
+    #[serde(default)]
+    #[schemars(range(min = -45, max = 45))]
+    pub tilt: f32,
+    /// How much to grow and shrink with each breath while standing still, as
+    /// a fraction of the fursona's height, such as `0.03`.
+    #[serde(default)]
+    #[schemars(range(min = 0, max = 0.9))]
+    pub breathe: f32,
+}
+
+impl V4SettingsFileProceduralMotion {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+fn default_model_height() -> f32 {
+    128.0
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct V4SettingsFileModel {
+    /// Path to a glTF 2.0 model (`.gltf` or `.glb`).
+    pub path: String,
+    /// How tall the model stands, in pixels before `scale` is applied.
+    #[serde(default = "default_model_height")]
+    #[schemars(range(min = 0))]
+    pub height: f32,
+}
+
+fn default_scale() -> u32 {
+    1
+}
+
+fn default_speed() -> f32 {
+    240.0
+}
+
+fn is_zero(value: &u32) -> bool {
+    *value == 0
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct V4SettingsFileFursona {
+    /// The name of this fursona.
+    pub name: String,
+    /// The animations this fursona is able to play. Behaviors that need an
+    /// animation that isn't listed here won't be used.
+    #[serde(default)]
+    pub animations: V4SettingsFileAnimations,
+    /// Path to a skeleton exported from Spine 3.8 as JSON, to animate
+    /// instead of `animations`. Its images are looked for next to it, named
+    /// after each attachment with a `.png` extension.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skeleton: Option<String>,
+    /// A 3D model to animate instead of `animations` or `skeleton`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<V4SettingsFileModel>,
+    /// How many screen pixels each pixel of the images takes up, in each
+    /// direction.
+    #[serde(default = "default_scale")]
+    #[schemars(range(min = 1))]
+    pub scale: u32,
+    /// How the images are smoothed when they're drawn at a different size.
+    #[serde(default)]
+    pub filter: V4SettingsFileFilter,
+    /// Keeps this fursona on a single monitor, numbered from 1 starting with
+    /// the leftmost. When left out, the fursona is free to walk between any
+    /// monitors that sit side by side.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(range(min = 1))]
+    pub monitor: Option<u32>,
+    /// What this fursona gets up to.
+    #[serde(default)]
+    pub behavior: V4SettingsFileBehavior,
+    /// Changes to how the images are drawn.
+    #[serde(default, skip_serializing_if = "V4SettingsFileEffects::is_default")]
+    pub effects: V4SettingsFileEffects,
+    /// Colors to swap in the images, from the color in the art (`#rrggbb`)
+    /// to the color to draw instead (`#rrggbb`), for recoloring the same art
+    /// without needing new files.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub palette: BTreeMap<String, String>,
+    /// How long to fade between animations when switching from one to
+    /// another, in milliseconds. Switches are instant when left out.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub crossfade_ms: u32,
+    /// How fast this fursona walks, in pixels per second.
+    #[serde(default = "default_speed")]
+    #[schemars(range(min = 0))]
+    pub speed: f32,
+    /// The walking speed the walk animations were made for, in pixels per
+    /// second. They're played faster or slower to keep up with `speed`, so
+    /// the same art can make for a stroll or a run.
+    #[serde(default = "default_speed")]
+    #[schemars(range(min = 0))]
+    pub walk_cycle_speed: f32,
+    /// Motion added on top of the animations, such as bobbing while walking.
+    /// Each kind is off unless given a strength.
+    #[serde(
+        default,
+        skip_serializing_if = "V4SettingsFileProceduralMotion::is_default"
+    )]
+    pub procedural_motion: V4SettingsFileProceduralMotion,
+}
+
+impl V4SettingsFileFursona {
+    // A fursona with everything but its animations left as the defaults
+    pub fn new(name: String, animations: V4SettingsFileAnimations) -> Self {
+        Self {
+            name,
+            animations,
+            skeleton: None,
+            model: None,
+            scale: default_scale(),
+            filter: V4SettingsFileFilter::default(),
+            monitor: None,
+            behavior: V4SettingsFileBehavior::default(),
+            effects: V4SettingsFileEffects::default(),
+            palette: BTreeMap::new(),
+            crossfade_ms: 0,
+            speed: default_speed(),
+            walk_cycle_speed: default_speed(),
+            procedural_motion: V4SettingsFileProceduralMotion::default(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct V4SettingsFile {
+    /// Every fursona that will appear on the desktop.
+    pub fursona: Vec<V4SettingsFileFursona>,
+}
+
+impl V4SettingsFile {
+    pub fn new() -> Self {
+        Self {
+            fursona: vec![V4SettingsFileFursona::new(
+                "Jack".to_owned(),
+                V4SettingsFileAnimations::jack(),
+            )],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fursona::animation::ANIMATION_NAMES;
+
+    #[test]
+    fn looks_up_every_animation_by_name() {
+        for name in ANIMATION_NAMES {
+            let json =
+                format!(r#"{{ "{name}": [{{ "image": "{name}.png", "duration_ms": 100 }}] }}"#);
+            let animations: V4SettingsFileAnimations = serde_json::from_str(&json).unwrap();
+
+            let keyframes = animations.get(name).unwrap();
+            assert_eq!(keyframes[0].image, format!("{name}.png"));
+        }
+    }
+}
@@ -0,0 +1,503 @@
+mod shimeji_action;
+mod shimeji_images;
+mod shimeji_pose;
+
+use super::settings_file::{
+    CurrentSettingsFileAnimations, CurrentSettingsFileBehavior, CurrentSettingsFileFursona,
+    CurrentSettingsFileKeyframe,
+};
+use roxmltree::{Document, Node};
+use shimeji_action::ShimejiAction;
+use shimeji_images::ShimejiImages;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Where Shimeji-ee keeps a character's configuration, relative to its image
+// folder: a `conf` folder of its own, right alongside the images, or the one
+// shared by every character
+const CONF_DIRECTORIES: [&str; 3] = ["conf", ".", "../../conf"];
+
+type Frames = Option<Vec<CurrentSettingsFileKeyframe>>;
+
+// Where each of Shimeji-ee's standard actions goes, for facing left and
+// right, if we have anything like it
+fn animation_slots<'a>(
+    animations: &'a mut CurrentSettingsFileAnimations,
+    action: &str,
+) -> Option<(&'a mut Frames, &'a mut Frames)> {
+    Some(match action {
+        "Walk" => (&mut animations.walk_left, &mut animations.walk_right),
+        "Stand" => (&mut animations.idle_left, &mut animations.idle_right),
+        "Falling" => (&mut animations.fall_left, &mut animations.fall_right),
+        "ClimbWall" => (&mut animations.climb_left, &mut animations.climb_right),
+        "ClimbCeiling" => (&mut animations.hang_left, &mut animations.hang_right),
+        "Jumping" => (
+            &mut animations.airborne_left,
+            &mut animations.airborne_right,
+        ),
+        "Bouncing" => (&mut animations.land_left, &mut animations.land_right),
+        _ => return None,
+    })
+}
+
+// Which of our behaviors a Shimeji behavior is part of, going by its name.
+// Walking and standing around are part of all of them.
+fn behavior_for(name: &str) -> Option<CurrentSettingsFileBehavior> {
+    if name.contains("IE") {
+        // Shimeji call the active window the IE, after Internet Explorer
+        Some(CurrentSettingsFileBehavior::Platform)
+    } else if ["Climb", "Grab", "Wall", "Ceiling"]
+        .iter()
+        .any(|word| name.contains(word))
+    {
+        Some(CurrentSettingsFileBehavior::Climb)
+    } else if name.contains("Jump") {
+        Some(CurrentSettingsFileBehavior::Jump)
+    } else {
+        None
+    }
+}
+
+fn is_walking(name: &str) -> bool {
+    ["Walk", "Run", "Dash", "Stand", "Fall"]
+        .iter()
+        .any(|prefix| name.starts_with(prefix))
+}
+
+fn behavior_name(behavior: CurrentSettingsFileBehavior) -> &'static str {
+    match behavior {
+        CurrentSettingsFileBehavior::Pace => "pace",
+        CurrentSettingsFileBehavior::Platform => "platform",
+        CurrentSettingsFileBehavior::Climb => "climb",
+        CurrentSettingsFileBehavior::Jump => "jump",
+    }
+}
+
+fn find_conf(directory: &Path, name: &str) -> Option<PathBuf> {
+    CONF_DIRECTORIES
+        .iter()
+        .map(|conf| directory.join(conf).join(name))
+        .find(|path| path.exists())
+}
+
+fn read_conf(path: &Path) -> Result<String, String> {
+    fs::read_to_string(path).map_err(|e| format!("Couldn't read {}: {e}", path.display()))
+}
+
+// Frames for facing right are mirrored, unless the character has art of
+// its own for that
+fn to_keyframes(
+    action: &ShimejiAction,
+    images: &mut ShimejiImages,
+    facing_right: bool,
+) -> Result<Vec<CurrentSettingsFileKeyframe>, String> {
+    if action.poses.is_empty() {
+        return Err("it has no frames".to_owned());
+    }
+
+    action
+        .poses
+        .iter()
+        .map(|pose| {
+            let (image, anchor) = match (facing_right, &pose.image_right) {
+                (false, _) => (images.copy(&pose.image, false)?.0, pose.anchor),
+                (true, image_right) => {
+                    let (image, width) = match image_right {
+                        Some(image_right) => images.copy(image_right, false)?,
+                        None => images.copy(&pose.image, true)?,
+                    };
+                    (image, pose.anchor.map(|[x, y]| [width as f32 - x, y]))
+                }
+            };
+
+            Ok(CurrentSettingsFileKeyframe {
+                image,
+                duration_ms: pose.duration_ms(),
+                anchor,
+                hitbox: None,
+                events: Vec::new(),
+            })
+        })
+        .collect()
+}
+
+// A Shimeji-ee character, converted into a fursona
+pub struct ShimejiImport {
+    pub fursona: CurrentSettingsFileFursona,
+    // Everything that couldn't be carried over, to let the user know
+    pub untranslated: Vec<String>,
+}
+
+impl ShimejiImport {
+    // `directory` is the character's image folder. The images are copied
+    // into a folder named after the fursona in `asset_directory`, which
+    // mustn't exist yet. Nothing is copied unless the character can be
+    // imported.
+    pub fn load(directory: &Path, name: &str, asset_directory: &Path) -> Result<Self, String> {
+        let actions_path = find_conf(directory, "actions.xml")
+            .ok_or_else(|| format!("Couldn't find actions.xml for {}", directory.display()))?;
+        let actions_xml = read_conf(&actions_path)?;
+        let actions = Document::parse(&actions_xml)
+            .map_err(|e| format!("Couldn't parse {}: {e}", actions_path.display()))?;
+
+        let action_nodes = actions
+            .descendants()
+            .filter(|node| node.has_tag_name("Action"))
+            .collect::<Vec<_>>();
+        if action_nodes.is_empty() {
+            return Err(format!(
+                "{} has no actions in it. Only the English form of actions.xml is supported.",
+                actions_path.display()
+            ));
+        }
+
+        // Every fursona needs to walk
+        let has_walk = action_nodes
+            .iter()
+            .filter(|node| node.attribute("Name") == Some("Walk"))
+            .any(|node| match ShimejiAction::parse(*node) {
+                Ok(Some(action)) => !action.poses.is_empty(),
+                _ => false,
+            });
+        if !has_walk {
+            return Err(format!(
+                "{} has no Walk action that could be imported, which every fursona needs",
+                actions_path.display()
+            ));
+        }
+
+        let picked = match find_conf(directory, "behaviors.xml") {
+            Some(path) => Some(Self::picked_behaviors(&path)?),
+            None => None,
+        };
+
+        let destination = asset_directory.join(name);
+        if destination.exists() {
+            return Err(format!(
+                "{} already exists, and the images would be copied over it",
+                destination.display()
+            ));
+        }
+
+        let mut untranslated = Vec::new();
+        let mut fursona = Self::import_actions(
+            &action_nodes,
+            name,
+            directory,
+            asset_directory,
+            &mut untranslated,
+        )
+        .inspect_err(|_| {
+            // Don't leave half an import behind
+            let _ = fs::remove_dir_all(&destination);
+        })?;
+        fursona.behavior = match picked {
+            Some(picked) => Self::choose_behavior(&picked, &fursona, &mut untranslated),
+            None => {
+                untranslated.push(
+                    "behaviors.xml wasn't found, so the fursona will pace along the top of the \
+                     screen"
+                        .to_owned(),
+                );
+                CurrentSettingsFileBehavior::Pace
+            }
+        };
+
+        Ok(Self {
+            fursona,
+            untranslated,
+        })
+    }
+
+    fn import_actions(
+        action_nodes: &[Node],
+        name: &str,
+        directory: &Path,
+        asset_directory: &Path,
+        untranslated: &mut Vec<String>,
+    ) -> Result<CurrentSettingsFileFursona, String> {
+        let mut fursona = CurrentSettingsFileFursona::new(
+            name.to_owned(),
+            CurrentSettingsFileAnimations::default(),
+        );
+        let mut images = ShimejiImages::new(directory, asset_directory, name);
+
+        for node in action_nodes {
+            let action_name = node.attribute("Name").unwrap_or_default();
+            let action = match ShimejiAction::parse(*node) {
+                Ok(Some(action)) => action,
+                Ok(None) => {
+                    untranslated.push(format!(
+                        "{action_name}: made up of other actions, which fursona can't string \
+                         together"
+                    ));
+                    continue;
+                }
+                Err(e) => {
+                    untranslated.push(e);
+                    continue;
+                }
+            };
+
+            let (left, right) = match animation_slots(&mut fursona.animations, action_name) {
+                Some(slots) => slots,
+                None => {
+                    untranslated.push(format!("{action_name}: there's no animation like it"));
+                    continue;
+                }
+            };
+            match (
+                to_keyframes(&action, &mut images, false),
+                to_keyframes(&action, &mut images, true),
+            ) {
+                (Ok(left_frames), Ok(right_frames)) => {
+                    *left = Some(left_frames);
+                    *right = Some(right_frames);
+                }
+                (Err(e), _) | (_, Err(e)) if action_name == "Walk" => {
+                    return Err(format!(
+                        "Couldn't import the Walk action, which every fursona needs: {e}"
+                    ));
+                }
+                (Err(e), _) | (_, Err(e)) => {
+                    untranslated.push(format!("{action_name}: {e}"));
+                    continue;
+                }
+            }
+
+            if action.animation_count > 1 {
+                untranslated.push(format!(
+                    "{action_name}: only the first of its {} animations was used",
+                    action.animation_count
+                ));
+            }
+
+            // The walking speed is the only one a fursona has, and the rest
+            // move at speeds worked out from it
+            if action_name == "Walk" {
+                let speed = action.speed();
+                if speed > 0.0 {
+                    fursona.speed = speed;
+                    fursona.walk_cycle_speed = speed;
+                }
+            } else if action.is_moving() {
+                untranslated.push(format!(
+                    "{action_name}: its own velocities, since it moves at a speed worked out \
+                     from walking"
+                ));
+            }
+        }
+
+        Ok(fursona)
+    }
+
+    // The behaviors Shimeji pick between at random, and how often each is
+    // picked. Behaviors that are never picked at random are only there to be
+    // moved on to from others, or started from the menu.
+    fn picked_behaviors(path: &Path) -> Result<Vec<(String, u32)>, String> {
+        let behaviors_xml = read_conf(path)?;
+        let behaviors = Document::parse(&behaviors_xml)
+            .map_err(|e| format!("Couldn't parse {}: {e}", path.display()))?;
+
+        Ok(behaviors
+            .descendants()
+            .filter(|node| node.has_tag_name("Behavior"))
+            .filter_map(|node| {
+                let name = node.attribute("Name")?;
+                let frequency = node.attribute("Frequency")?.trim().parse::<u32>().ok()?;
+                (frequency > 0).then(|| (name.to_owned(), frequency))
+            })
+            .collect())
+    }
+
+    // Shimeji pick between all of their behaviors at random, where a
+    // fursona sticks to one. This picks whichever of ours the character
+    // spends the most time doing, going by how often each behavior is
+    // picked, and pacing if none of them.
+    fn choose_behavior(
+        picked: &[(String, u32)],
+        fursona: &CurrentSettingsFileFursona,
+        untranslated: &mut Vec<String>,
+    ) -> CurrentSettingsFileBehavior {
+        let animations = &fursona.animations;
+        let chosen = [
+            (
+                CurrentSettingsFileBehavior::Jump,
+                animations.airborne_left.is_some(),
+            ),
+            (
+                CurrentSettingsFileBehavior::Climb,
+                animations.climb_left.is_some(),
+            ),
+            (CurrentSettingsFileBehavior::Platform, true),
+        ]
+        .into_iter()
+        .filter(|(_, is_possible)| *is_possible)
+        .map(|(behavior, _)| {
+            let frequency = picked
+                .iter()
+                .filter(|(name, _)| behavior_for(name) == Some(behavior))
+                .map(|(_, frequency)| frequency)
+                .sum::<u32>();
+            (behavior, frequency)
+        })
+        .filter(|(_, frequency)| *frequency > 0)
+        // Ties go to the later ones
+        .max_by_key(|(_, frequency)| *frequency)
+        .map_or(CurrentSettingsFileBehavior::Pace, |(behavior, _)| behavior);
+
+        let left_out = picked
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .filter(|name| !is_walking(name) && behavior_for(name) != Some(chosen))
+            .collect::<Vec<_>>();
+        if !left_out.is_empty() {
+            untranslated.push(format!(
+                "{}: the fursona sticks to the {} behavior instead",
+                left_out.join(", "),
+                behavior_name(chosen)
+            ));
+        }
+
+        chosen
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+
+    const WALK_ACTION: &str = r#"<Action Name="Walk" Type="Move">
+        <Animation>
+            <Pose Image="/shime1.png" ImageAnchor="4,8" Velocity="-2,0" Duration="6" />
+        </Animation>
+    </Action>"#;
+    const STAND_ACTION: &str = r#"<Action Name="Stand" Type="Stay">
+        <Animation><Pose Image="/shime1.png" ImageAnchor="4,8" Duration="250" /></Animation>
+    </Action>"#;
+
+    // A character folder with the given actions, and where to import it to
+    fn character(test: &str, actions: &[&str]) -> (PathBuf, PathBuf) {
+        let root = std::env::temp_dir().join(format!(
+            "desktop-fursona-shimeji-{}-{test}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&root);
+        let directory = root.join("img").join("Foxy");
+        fs::create_dir_all(directory.join("conf")).unwrap();
+        RgbaImage::new(8, 8)
+            .save(directory.join("shime1.png"))
+            .unwrap();
+        fs::write(
+            directory.join("conf").join("actions.xml"),
+            format!(
+                "<Mascot><ActionList>{}</ActionList></Mascot>",
+                actions.join("")
+            ),
+        )
+        .unwrap();
+
+        let settings = root.join("settings");
+        fs::create_dir_all(&settings).unwrap();
+        (directory, settings)
+    }
+
+    #[test]
+    fn sorts_behaviors_by_name() {
+        assert!(matches!(
+            behavior_for("WalkAlongIECeiling"),
+            Some(CurrentSettingsFileBehavior::Platform)
+        ));
+        assert!(matches!(
+            behavior_for("ClimbWall"),
+            Some(CurrentSettingsFileBehavior::Climb)
+        ));
+        assert!(matches!(
+            behavior_for("GrabCeiling"),
+            Some(CurrentSettingsFileBehavior::Climb)
+        ));
+        assert!(matches!(
+            behavior_for("Jumping"),
+            Some(CurrentSettingsFileBehavior::Jump)
+        ));
+        assert!(behavior_for("SitDown").is_none());
+    }
+
+    #[test]
+    fn chooses_the_most_picked_behavior_it_has_animations_for() {
+        let mut fursona = CurrentSettingsFileFursona::new(
+            "Foxy".to_owned(),
+            CurrentSettingsFileAnimations::default(),
+        );
+        let picked = vec![
+            ("ClimbWall".to_owned(), 100),
+            ("WalkAlongIECeiling".to_owned(), 50),
+            ("SitDown".to_owned(), 200),
+        ];
+        let mut untranslated = Vec::new();
+
+        // Without anything to climb with
+        assert!(matches!(
+            ShimejiImport::choose_behavior(&picked, &fursona, &mut untranslated),
+            CurrentSettingsFileBehavior::Platform
+        ));
+
+        fursona.animations.climb_left = Some(Vec::new());
+        assert!(matches!(
+            ShimejiImport::choose_behavior(&picked, &fursona, &mut untranslated),
+            CurrentSettingsFileBehavior::Climb
+        ));
+
+        // Nothing but sitting around and walking
+        let picked = vec![("SitDown".to_owned(), 200), ("Walk".to_owned(), 100)];
+        assert!(matches!(
+            ShimejiImport::choose_behavior(&picked, &fursona, &mut untranslated),
+            CurrentSettingsFileBehavior::Pace
+        ));
+    }
+
+    #[test]
+    fn imports_characters() {
+        let (directory, settings) = character("imports", &[WALK_ACTION, STAND_ACTION]);
+
+        let import = ShimejiImport::load(&directory, "Foxy", &settings).unwrap();
+        let walk_right = import.fursona.animations.walk_right.unwrap();
+        assert_eq!(walk_right[0].image, "Foxy/shime1-right.png");
+        assert_eq!(walk_right[0].duration_ms, 240);
+        assert_eq!(walk_right[0].anchor, Some([4.0, 8.0]));
+        assert!(import.fursona.animations.idle_left.is_some());
+        // Without a behaviors.xml
+        assert!(matches!(
+            import.fursona.behavior,
+            CurrentSettingsFileBehavior::Pace
+        ));
+        assert!(settings.join("Foxy").join("shime1-right.png").exists());
+    }
+
+    #[test]
+    fn copies_nothing_without_a_walk_action() {
+        let (directory, settings) = character("without-walk", &[STAND_ACTION]);
+
+        assert!(ShimejiImport::load(&directory, "Foxy", &settings).is_err());
+        assert!(!settings.join("Foxy").exists());
+    }
+
+    #[test]
+    fn wont_copy_over_an_existing_folder() {
+        let (directory, settings) = character("existing", &[WALK_ACTION]);
+        fs::create_dir_all(settings.join("Foxy")).unwrap();
+
+        assert!(ShimejiImport::load(&directory, "Foxy", &settings).is_err());
+        assert_eq!(fs::read_dir(settings.join("Foxy")).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn cleans_up_when_the_walk_images_are_missing() {
+        let (directory, settings) = character("missing-images", &[WALK_ACTION]);
+        fs::remove_file(directory.join("shime1.png")).unwrap();
+
+        assert!(ShimejiImport::load(&directory, "Foxy", &settings).is_err());
+        assert!(!settings.join("Foxy").exists());
+    }
+}
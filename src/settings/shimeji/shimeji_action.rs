@@ -0,0 +1,144 @@
+use super::shimeji_pose::ShimejiPose;
+use roxmltree::Node;
+
+// An action from actions.xml that has frames of its own, as opposed to one
+// made up of other actions
+pub struct ShimejiAction {
+    // From the first of its animations
+    pub poses: Vec<ShimejiPose>,
+    // Shimeji pick between an action's animations based on conditions, which
+    // we can't check
+    pub animation_count: usize,
+}
+
+impl ShimejiAction {
+    // Returns `None` for actions without animations
+    pub fn parse(node: Node) -> Result<Option<Self>, String> {
+        let name = node.attribute("Name").unwrap_or_default();
+        let animations = node
+            .children()
+            .filter(|child| child.has_tag_name("Animation"))
+            .collect::<Vec<Node>>();
+        let first = match animations.first() {
+            Some(first) => first,
+            None => return Ok(None),
+        };
+
+        let poses = first
+            .children()
+            .filter(|child| child.has_tag_name("Pose"))
+            .map(ShimejiPose::parse)
+            .collect::<Result<Vec<ShimejiPose>, String>>()
+            .map_err(|e| format!("{name}: {e}"))?;
+
+        Ok(Some(Self {
+            poses,
+            animation_count: animations.len(),
+        }))
+    }
+
+    // How fast the action moves along, on average, in pixels per second
+    pub fn speed(&self) -> f32 {
+        let distance: f32 = self
+            .poses
+            .iter()
+            .map(|pose| pose.velocity[0].abs() * pose.duration_ticks as f32)
+            .sum();
+        let duration_ms = self
+            .poses
+            .iter()
+            .map(ShimejiPose::duration_ms)
+            .fold(0, u32::saturating_add);
+
+        match duration_ms {
+            0 => 0.0,
+            duration_ms => distance / duration_ms as f32 * 1000.0,
+        }
+    }
+
+    pub fn is_moving(&self) -> bool {
+        self.poses.iter().any(|pose| pose.velocity != [0.0, 0.0])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use roxmltree::Document;
+
+    fn parse(action: &str) -> Result<Option<ShimejiAction>, String> {
+        let document = Document::parse(action).unwrap();
+        ShimejiAction::parse(document.root_element())
+    }
+
+    #[test]
+    fn uses_the_first_animation() {
+        let action = parse(
+            r#"<Action Name="Walk">
+                <Animation Condition="${mascot.anchor.x % 2 == 0}">
+                    <Pose Image="/shime1.png" Duration="6" />
+                    <Pose Image="/shime2.png" Duration="6" />
+                </Animation>
+                <Animation>
+                    <Pose Image="/shime3.png" Duration="6" />
+                </Animation>
+            </Action>"#,
+        )
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(action.poses.len(), 2);
+        assert_eq!(action.animation_count, 2);
+    }
+
+    #[test]
+    fn skips_actions_made_of_other_actions() {
+        let action = parse(
+            r#"<Action Name="Sequence" Type="Sequence">
+                <ActionReference Name="Walk" />
+            </Action>"#,
+        );
+        assert!(matches!(action, Ok(None)));
+    }
+
+    #[test]
+    fn names_the_action_with_a_malformed_pose() {
+        let action = parse(
+            r#"<Action Name="Walk">
+                <Animation><Pose Image="/shime1.png" Duration="soon" /></Animation>
+            </Action>"#,
+        );
+        assert!(matches!(action, Err(e) if e.starts_with("Walk: ")));
+    }
+
+    #[test]
+    fn averages_speed_over_the_poses() {
+        // 2 pixels a tick for 3 ticks, then standing still for 1 tick: 6
+        // pixels in 160ms
+        let action = parse(
+            r#"<Action Name="Walk"><Animation>
+                <Pose Image="/shime1.png" Velocity="-2,0" Duration="3" />
+                <Pose Image="/shime2.png" Velocity="0,0" Duration="1" />
+            </Animation></Action>"#,
+        )
+        .unwrap()
+        .unwrap();
+
+        assert!((action.speed() - 37.5).abs() < 0.001);
+        assert!(action.is_moving());
+    }
+
+    #[test]
+    fn standing_still_has_no_speed() {
+        let action = parse(
+            r#"<Action Name="Stand"><Animation>
+                <Pose Image="/shime1.png" Duration="250" />
+            </Animation></Action>"#,
+        )
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(action.speed(), 0.0);
+        assert!(!action.is_moving());
+    }
+}
@@ -0,0 +1,60 @@
+use image::imageops;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Copies a character's images in alongside the settings file, each one only
+// once. Shimeji art faces left, so facing right takes mirrored copies.
+pub struct ShimejiImages {
+    source: PathBuf,
+    // The folder the copies go in, relative to the settings file
+    folder: String,
+    destination: PathBuf,
+    // By image and whether it was mirrored, along with its width
+    copied: BTreeMap<(String, bool), (String, u32)>,
+}
+
+impl ShimejiImages {
+    pub fn new(source: &Path, asset_directory: &Path, folder: &str) -> Self {
+        Self {
+            source: source.to_owned(),
+            folder: folder.to_owned(),
+            destination: asset_directory.join(folder),
+            copied: BTreeMap::new(),
+        }
+    }
+
+    // Returns the path of the copy, as the settings file refers to it, and
+    // the width of the image
+    pub fn copy(&mut self, image: &str, mirrored: bool) -> Result<(String, u32), String> {
+        let key = (image.to_owned(), mirrored);
+        if let Some(copied) = self.copied.get(&key) {
+            return Ok(copied.clone());
+        }
+
+        let name = match (mirrored, image.rsplit_once('.')) {
+            (false, _) => image.to_owned(),
+            (true, Some((stem, extension))) => format!("{stem}-right.{extension}"),
+            (true, None) => format!("{image}-right"),
+        };
+        let from = self.source.join(image);
+        let to = self.destination.join(&name);
+
+        let picture =
+            image::open(&from).map_err(|e| format!("Couldn't read {}: {e}", from.display()))?;
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let written = match mirrored {
+            true => imageops::flip_horizontal(&picture)
+                .save(&to)
+                .map_err(|e| e.to_string()),
+            false => fs::copy(&from, &to).map(|_| ()).map_err(|e| e.to_string()),
+        };
+        written.map_err(|e| format!("Couldn't write {}: {e}", to.display()))?;
+
+        let copied = (format!("{}/{name}", self.folder), picture.width());
+        self.copied.insert(key, copied.clone());
+        Ok(copied)
+    }
+}
@@ -0,0 +1,122 @@
+use roxmltree::Node;
+
+// Shimeji count time in ticks of 40 milliseconds, 25 to the second
+pub const SHIMEJI_TICK_MS: u32 = 40;
+
+// Parses an attribute like `ImageAnchor="64,128"`
+fn parse_pair(value: &str) -> Option<[f32; 2]> {
+    let (x, y) = value.split_once(',')?;
+    Some([x.trim().parse().ok()?, y.trim().parse().ok()?])
+}
+
+// One frame of a Shimeji action, as written in actions.xml
+pub struct ShimejiPose {
+    // Relative to the character's image folder
+    pub image: String,
+    // Drawn instead of mirroring `image` when facing right, if set
+    pub image_right: Option<String>,
+    // In the image's pixels, from its top-left corner
+    pub anchor: Option<[f32; 2]>,
+    // How far the character moves on each tick, facing left
+    pub velocity: [f32; 2],
+    pub duration_ticks: u32,
+}
+
+impl ShimejiPose {
+    pub fn parse(node: Node) -> Result<Self, String> {
+        // Image paths are written as if from the root of the image folder
+        let image_path = |name| {
+            node.attribute(name)
+                .map(|image: &str| image.trim_start_matches('/').to_owned())
+        };
+        let image = image_path("Image").ok_or_else(|| "a pose has no image".to_owned())?;
+        let pair = |name| match node.attribute(name) {
+            Some(value) => parse_pair(value)
+                .map(Some)
+                .ok_or_else(|| format!("{name} \"{value}\" isn't a pair of numbers like \"1,2\"")),
+            None => Ok(None),
+        };
+
+        Ok(Self {
+            image,
+            image_right: image_path("ImageRight"),
+            anchor: pair("ImageAnchor")?,
+            velocity: pair("Velocity")?.unwrap_or([0.0, 0.0]),
+            duration_ticks: match node.attribute("Duration") {
+                Some(duration) => duration
+                    .trim()
+                    .parse()
+                    .map_err(|_| format!("Duration \"{duration}\" isn't a whole number"))?,
+                None => 1,
+            },
+        })
+    }
+
+    pub fn duration_ms(&self) -> u32 {
+        self.duration_ticks.saturating_mul(SHIMEJI_TICK_MS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use roxmltree::Document;
+
+    fn parse(pose: &str) -> Result<ShimejiPose, String> {
+        let document = Document::parse(pose).unwrap();
+        ShimejiPose::parse(document.root_element())
+    }
+
+    #[test]
+    fn parses_pairs() {
+        assert_eq!(parse_pair("64,128"), Some([64.0, 128.0]));
+        assert_eq!(parse_pair(" -2 , 0.5 "), Some([-2.0, 0.5]));
+        assert_eq!(parse_pair("64"), None);
+        assert_eq!(parse_pair("64,"), None);
+        assert_eq!(parse_pair("a,b"), None);
+    }
+
+    #[test]
+    fn parses_poses() {
+        let pose = parse(
+            r#"<Pose Image="/shime1.png" ImageRight="/right/shime1.png" ImageAnchor="64,128"
+                Velocity="-2,0" Duration="6" />"#,
+        )
+        .unwrap();
+
+        assert_eq!(pose.image, "shime1.png");
+        assert_eq!(pose.image_right.as_deref(), Some("right/shime1.png"));
+        assert_eq!(pose.anchor, Some([64.0, 128.0]));
+        assert_eq!(pose.velocity, [-2.0, 0.0]);
+        assert_eq!(pose.duration_ticks, 6);
+    }
+
+    #[test]
+    fn leaves_out_what_isnt_given() {
+        let pose = parse(r#"<Pose Image="/shime1.png" />"#).unwrap();
+
+        assert_eq!(pose.image_right, None);
+        assert_eq!(pose.anchor, None);
+        assert_eq!(pose.velocity, [0.0, 0.0]);
+        assert_eq!(pose.duration_ticks, 1);
+    }
+
+    #[test]
+    fn rejects_malformed_attributes() {
+        assert!(parse(r#"<Pose Duration="6" />"#).is_err());
+        assert!(parse(r#"<Pose Image="/shime1.png" ImageAnchor="64" />"#).is_err());
+        assert!(parse(r#"<Pose Image="/shime1.png" Velocity="fast" />"#).is_err());
+        assert!(parse(r#"<Pose Image="/shime1.png" Duration="-1" />"#).is_err());
+        assert!(parse(r#"<Pose Image="/shime1.png" Duration="1.5" />"#).is_err());
+    }
+
+    #[test]
+    fn converts_ticks_to_milliseconds() {
+        let pose = parse(r#"<Pose Image="/shime1.png" Duration="25" />"#).unwrap();
+        assert_eq!(pose.duration_ms(), 1000);
+
+        // Rather than overflowing
+        let pose = parse(r#"<Pose Image="/shime1.png" Duration="4294967295" />"#).unwrap();
+        assert_eq!(pose.duration_ms(), u32::MAX);
+    }
+}
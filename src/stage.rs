@@ -1,33 +1,48 @@
+mod monitor_area;
+mod physical_point;
+mod physical_rect;
+mod stage_error;
 mod viewport;
 mod viewport_point;
 mod viewport_rect;
+mod walkable_span;
 
-use glium::{Blend, Display, DrawParameters, Surface};
+use glium::{Blend, Display, DrawParameters, Surface as _};
 use glutin::config::{Config, ConfigTemplateBuilder};
 use glutin::context::{ContextApi, ContextAttributesBuilder, Version};
 
 use crate::event_loop::EventLoop;
 use crate::fursona::FursonaInstance;
-use crate::rendering::{Color, Renderer, RendererCoord};
+use crate::rendering::{Color, RenderError, Renderer};
+use crate::surfaces::Surface;
 use glutin::display::GetGlDisplay;
 use glutin::prelude::*;
 use glutin::surface::WindowSurface;
 use glutin_winit::{DisplayBuilder, GlWindow};
 use raw_window_handle::HasRawWindowHandle;
 use std::rc::Rc;
-#[cfg(target_os = "macos")]
-use winit::platform::macos::WindowExtMacOS;
+use std::time::{Duration, Instant};
+use tracing::{debug, instrument, trace, warn};
+use winit::dpi::{PhysicalPosition, PhysicalSize};
 use winit::window::{CursorIcon, Window, WindowBuilder, WindowLevel};
 
+pub use monitor_area::MonitorArea;
+pub use physical_point::PhysicalPoint;
+pub use physical_rect::PhysicalRect;
+pub use stage_error::StageError;
 pub use viewport::Viewport;
 pub use viewport_point::ViewportPoint;
 pub use viewport_rect::ViewportRect;
+pub use walkable_span::WalkableSpan;
 
 const DEBUG_COLORS: [Color; 3] = [
     Color(1.0, 0.0, 0.0, 1.0),
     Color(0.0, 1.0, 0.0, 1.0),
     Color(0.0, 0.0, 1.0, 1.0),
 ];
+const DEBUG_SURFACE_COLOR: Color = Color(1.0, 1.0, 1.0, 0.5);
+// In logical pixels
+const DEBUG_OUTLINE_THICKNESS: f32 = 4.0;
 
 pub struct Stage {
     pub display: Display<WindowSurface>,
@@ -37,46 +52,10 @@ pub struct Stage {
     debug_mode: bool,
 }
 
-struct Point(f32, f32);
-
-fn is_point_in_triangle(point: Point, triangle: (Point, Point, Point)) -> bool {
-    /*
-    """Returns True if the point is inside the triangle
-    and returns False if it falls outside.
-    - The argument *point* is a tuple with two elements
-    containing the X,Y coordinates respectively.
-    - The argument *triangle* is a tuple with three elements each
-    element consisting of a tuple of X,Y coordinates.
-
-    It works like this:
-    Walk clockwise or counterclockwise around the triangle
-    and project the point onto the segment we are crossing
-    by using the dot product.
-    Finally, check that the vector created is on the same side
-    for each of the triangle's segments.
-    """
-     */
-
-    let x = point.0;
-    let y = point.1;
-    let ax = triangle.0 .0;
-    let ay = triangle.0 .1;
-    let bx = triangle.1 .0;
-    let by = triangle.1 .1;
-    let cx = triangle.2 .0;
-    let cy = triangle.2 .1;
-
-    // # Segment A to B
-    let side_1 = (x - bx) * (ay - by) - (ax - bx) * (y - by);
-    // # Segment B to C
-    let side_2 = (x - cx) * (by - cy) - (bx - cx) * (y - cy);
-    // # Segment C to A
-    let side_3 = (x - ax) * (cy - ay) - (cx - ax) * (y - ay);
-    // # All the signs must be positive or all negative
-    ((side_1 < 0.0) == (side_2 < 0.0)) == (side_3 < 0.0)
-}
-
-fn init_opengl(event_loop: &EventLoop, window_builder: WindowBuilder) -> (Window, Config) {
+fn init_opengl(
+    event_loop: &EventLoop,
+    window_builder: WindowBuilder,
+) -> Result<(Window, Config), StageError> {
     // The template will match only the configurations supporting rendering
     // to windows.
     //
@@ -85,8 +64,11 @@ fn init_opengl(event_loop: &EventLoop, window_builder: WindowBuilder) -> (Window
     // that, because we can query only one config at a time on it, but all
     // normal platforms will return multiple configs, so we can find the config
     // with transparency ourselves inside the `reduce`.
+    //
+    // 3D fursona need a depth buffer to draw themselves in the right order.
     let template = ConfigTemplateBuilder::new()
         .with_alpha_size(8)
+        .with_depth_size(24)
         .with_transparency(true);
 
     let display_builder = DisplayBuilder::new().with_window_builder(Some(window_builder));
@@ -106,32 +88,78 @@ fn init_opengl(event_loop: &EventLoop, window_builder: WindowBuilder) -> (Window
                         accum
                     }
                 })
-                .unwrap()
+                // glutin reports an error rather than calling us when there
+                // aren't any configs, so this always has one to pick
+                .expect("glutin offered no configs to pick from")
         })
-        .unwrap();
+        .map_err(|e| StageError::Window(e.to_string()))?;
+
+    debug!("Picked a config with {} samples", gl_config.num_samples());
 
-    println!("Picked a config with {} samples", gl_config.num_samples());
+    match gl_config.supports_transparency() {
+        Some(true) => debug!("Picked a config with transparency"),
+        Some(false) => warn!("Picked a config without transparency"),
+        None => warn!("Couldn't tell whether the picked config supports transparency"),
+    }
+
+    let window = window.ok_or(StageError::NoWindow)?;
+    Ok((window, gl_config))
+}
 
-    if let Some(trans) = gl_config.supports_transparency() {
-        println!("picked transparent? {}", trans);
-    } else {
-        println!("oh no...");
+// Stretches the window over the bounding box of every monitor, so that
+// fursona can go wherever the mouse can. Some platforms (like Wayland) don't
+// let us position windows, in which case we stay wherever we were put.
+fn span_all_monitors(window: &Window) {
+    let mut monitors = window.available_monitors();
+    let first = match monitors.next() {
+        Some(first) => first,
+        None => {
+            warn!("Couldn't find any monitors to cover");
+            return;
+        }
+    };
+
+    let corners = |monitor: &winit::monitor::MonitorHandle| {
+        let position = monitor.position();
+        let size = monitor.size();
+        (
+            position,
+            PhysicalPosition::new(
+                position.x + size.width as i32,
+                position.y + size.height as i32,
+            ),
+        )
+    };
+
+    let (mut min, mut max) = corners(&first);
+    for monitor in monitors {
+        let (top_left, bottom_right) = corners(&monitor);
+        min.x = min.x.min(top_left.x);
+        min.y = min.y.min(top_left.y);
+        max.x = max.x.max(bottom_right.x);
+        max.y = max.y.max(bottom_right.y);
     }
 
-    (window.unwrap(), gl_config)
+    let size = PhysicalSize::new((max.x - min.x) as u32, (max.y - min.y) as u32);
+    debug!(
+        "Covering the desktop with a {}x{} window at ({}, {})",
+        size.width, size.height, min.x, min.y
+    );
+    window.set_outer_position(min);
+    // Any change of size comes through as a resize event later on
+    let _ = window.request_inner_size(size);
 }
 
 impl Stage {
-    pub fn new(event_loop: &EventLoop) -> Result<Stage, &'static str> {
+    pub fn new(event_loop: &EventLoop) -> Result<Stage, StageError> {
         let window_builder = WindowBuilder::new()
             .with_transparent(true)
             .with_decorations(false)
             .with_window_level(WindowLevel::AlwaysOnTop);
 
-        let (window, gl_config) = init_opengl(event_loop, window_builder);
+        let (window, gl_config) = init_opengl(event_loop, window_builder)?;
 
-        #[cfg(target_os = "macos")]
-        window.set_simple_fullscreen(true);
+        span_all_monitors(&window);
 
         let raw_window_handle = Some(window.raw_window_handle());
 
@@ -144,7 +172,7 @@ impl Stage {
             gl_config
                 .display()
                 .create_window_surface(&gl_config, &attrs)
-                .unwrap()
+                .map_err(StageError::Surface)?
         };
 
         // The context creation part. It can be created before surface and that's how
@@ -167,25 +195,23 @@ impl Stage {
         let not_current_gl_context = unsafe {
             gl_display
                 .create_context(&gl_config, &context_attributes)
-                .unwrap_or_else(|_| {
+                .or_else(|_| {
                     gl_display
                         .create_context(&gl_config, &fallback_context_attributes)
-                        .unwrap_or_else(|_| {
-                            gl_display
-                                .create_context(&gl_config, &legacy_context_attributes)
-                                .expect("failed to create context")
+                        .or_else(|_| {
+                            gl_display.create_context(&gl_config, &legacy_context_attributes)
                         })
                 })
+                .map_err(StageError::Context)?
         };
 
-        let display = glium::Display::new(
-            not_current_gl_context.make_current(&gl_surface).unwrap(),
-            gl_surface,
-        )
-        .unwrap();
+        let gl_context = not_current_gl_context
+            .make_current(&gl_surface)
+            .map_err(StageError::MakeCurrent)?;
+        let display = glium::Display::new(gl_context, gl_surface)?;
 
         let window = Rc::new(window);
-        let renderer = Renderer::new(&display);
+        let renderer = Renderer::new(&display)?;
 
         Ok(Self {
             display,
@@ -200,15 +226,21 @@ impl Stage {
         self.debug_mode = enabled;
     }
 
-    pub fn on_mouse_over(&self, point: ViewportPoint) {
-        let RendererCoord { x: rx, y: ry } = self.viewport.convert_point_to_renderer_coord(point);
-
-        let inside = is_point_in_triangle(
-            Point(rx, ry),
-            (Point(-0.5, -0.5), Point(0.0, 0.5), Point(0.5, -0.5)),
-        );
-
-        self.window.set_cursor_hittest(inside).unwrap();
+    // Lets clicks through to the windows below unless the mouse is over one
+    // of the fursona
+    pub fn on_mouse_over<'a, I: IntoIterator<Item = &'a FursonaInstance>>(
+        &self,
+        point: PhysicalPoint,
+        instances: I,
+    ) {
+        let point = self.viewport.convert_desktop_point(point);
+        let inside = instances
+            .into_iter()
+            .any(|instance| instance.hitbox().contains(&point));
+
+        if let Err(e) = self.window.set_cursor_hittest(inside) {
+            trace!("Couldn't set cursor hit testing: {e}");
+        }
         if inside {
             // We need to focus the window in order for the cursor change to
             // actually show up
@@ -220,14 +252,29 @@ impl Stage {
         }
     }
 
-    pub fn draw<'a, I: Iterator<Item = &'a FursonaInstance>>(&mut self, instances: I) {
+    // Returns how long drawing took, up until the frame is finished. That
+    // leaves out swapping buffers, which is where we'd wait for vsync.
+    #[instrument(level = "trace", skip_all)]
+    pub fn draw<'a, I: Iterator<Item = &'a FursonaInstance>>(
+        &mut self,
+        instances: I,
+        surfaces: &[Surface],
+    ) -> Result<Duration, RenderError> {
+        let draw_start = Instant::now();
         let mut frame = self.display.draw();
-        frame.clear_all((0.0, 0.0, 0.0, 0.0), 0.0, 0);
+        frame.clear_all((0.0, 0.0, 0.0, 0.0), 1.0, 0);
 
-        let draw_parameters = DrawParameters {
-            blend: Blend::alpha_blending(),
-            ..Default::default()
-        };
+        // Show which windows we think fursona can stand on
+        if self.debug_mode {
+            for surface in surfaces {
+                let rect = self.viewport.convert_rect(surface.rect.clone());
+                self.renderer.outline_rect(
+                    rect,
+                    DEBUG_SURFACE_COLOR,
+                    DEBUG_OUTLINE_THICKNESS * self.viewport.scale_factor(),
+                );
+            }
+        }
 
         for (index, instance) in instances.enumerate() {
             let rect = self.viewport.convert_rect(instance.bounding_box());
@@ -235,34 +282,50 @@ impl Stage {
             let debug_color = &DEBUG_COLORS[index % DEBUG_COLORS.len()];
 
             if self.debug_mode {
-                self.renderer.fill_rect(
-                    &mut frame,
-                    rect.clone(),
-                    debug_color.alpha(0.3),
-                    &draw_parameters,
-                );
+                self.renderer
+                    .fill_rect(rect.clone(), debug_color.alpha(0.3));
             }
 
-            instance
-                .renderer()
-                .draw(&mut frame, rect.clone(), &draw_parameters);
+            instance.renderer().draw(&mut self.renderer, rect.clone());
 
             if self.debug_mode {
                 self.renderer.outline_rect(
-                    &mut frame,
                     rect.clone(),
                     debug_color.alpha(0.8),
-                    4.0,
-                    &draw_parameters,
+                    DEBUG_OUTLINE_THICKNESS * self.viewport.scale_factor(),
                 );
             }
         }
 
+        let draw_parameters = DrawParameters {
+            blend: Blend::alpha_blending(),
+            ..Default::default()
+        };
+
+        // Everything above was only queued up, and gets drawn here in as few
+        // draw calls as possible. The frame has to be finished no matter
+        // what, so hang on to any error until after that.
+        let result = self
+            .renderer
+            .flush(&self.display, &mut frame, &draw_parameters);
+        let draw_time = draw_start.elapsed();
+
         self.window.request_redraw();
-        frame.finish().unwrap();
+        frame.finish()?;
+        result.map(|_| draw_time)
     }
 
     pub fn resize(&mut self, width: u32, height: u32) {
         self.display.resize((width, height));
+        self.viewport.refresh_monitors();
+    }
+
+    // Called when the window moves to a screen with a different pixel
+    // density, or the screen's density is changed. Winit follows this up with
+    // a resize if the window's physical size changes too.
+    pub fn set_scale_factor(&mut self, scale_factor: f64) {
+        debug!("Scale factor changed to {scale_factor}");
+        self.viewport.set_scale_factor(scale_factor);
+        self.window.request_redraw();
     }
 }
@@ -0,0 +1,37 @@
+use super::ViewportRect;
+
+// The part of a monitor that fursona are allowed to wander around, in the
+// viewport's logical pixels. This leaves out anything the OS keeps on top of
+// everything else, like the macOS menu bar.
+#[derive(Clone)]
+pub struct MonitorArea {
+    pub name: Option<String>,
+    pub rect: ViewportRect,
+}
+
+impl MonitorArea {
+    pub fn left(&self) -> f32 {
+        self.rect.x
+    }
+
+    pub fn right(&self) -> f32 {
+        self.rect.x + self.rect.width
+    }
+
+    pub fn top(&self) -> f32 {
+        self.rect.y
+    }
+
+    pub fn bottom(&self) -> f32 {
+        self.rect.y + self.rect.height
+    }
+
+    // Whether a fursona can walk straight off our right edge and onto the
+    // other monitor's left edge, without having to cross a gap.
+    pub fn touches_on_right(&self, other: &MonitorArea) -> bool {
+        // Allow for a pixel of rounding between the two
+        (other.left() - self.right()).abs() < 1.0
+            && other.top() < self.bottom()
+            && self.top() < other.bottom()
+    }
+}
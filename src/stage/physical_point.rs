@@ -0,0 +1,19 @@
+use super::ViewportPoint;
+
+// A point, measured in device pixels. On HiDPI screens there are several of
+// these for every `ViewportPoint` pixel, so these should be converted by the
+// `Viewport` as soon as they come in.
+#[derive(Clone)]
+pub struct PhysicalPoint {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl PhysicalPoint {
+    pub fn to_logical(&self, scale_factor: f32) -> ViewportPoint {
+        ViewportPoint {
+            x: self.x / scale_factor,
+            y: self.y / scale_factor,
+        }
+    }
+}
@@ -0,0 +1,21 @@
+// A rect, measured in device pixels, that is relative to the viewport. This is
+// what actually ends up on screen, so it's what drawing is done in terms of.
+pub struct PhysicalRect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl PhysicalRect {
+    // Lines the rect up with whole device pixels, so that sprites aren't
+    // smeared across pixel boundaries.
+    pub fn snapped(&self) -> Self {
+        Self {
+            x: self.x.round(),
+            y: self.y.round(),
+            width: self.width.round(),
+            height: self.height.round(),
+        }
+    }
+}
@@ -0,0 +1,22 @@
+use crate::rendering::RenderError;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum StageError {
+    // glutin-winit only hands back a boxed error here, which can't be kept as
+    // a source since it isn't Send + Sync
+    #[error("couldn't create a window with a suitable OpenGL config: {0}")]
+    Window(String),
+    #[error("no window was created")]
+    NoWindow,
+    #[error("couldn't create a surface for the window: {0}")]
+    Surface(glutin::error::Error),
+    #[error("couldn't create an OpenGL context: {0}")]
+    Context(glutin::error::Error),
+    #[error("couldn't make the OpenGL context current: {0}")]
+    MakeCurrent(glutin::error::Error),
+    #[error("couldn't create display: {0}")]
+    Display(#[from] glium::backend::glutin::DisplayCreationError),
+    #[error(transparent)]
+    Renderer(#[from] RenderError),
+}
@@ -1,46 +1,244 @@
-use super::{viewport_point::ViewportPoint, viewport_rect::ViewportRect};
-use crate::rendering::{RendererCoord, RendererRect};
+use super::{MonitorArea, PhysicalPoint, PhysicalRect, ViewportPoint, ViewportRect, WalkableSpan};
+use crate::rendering::RendererRect;
 use std::rc::Rc;
-use winit::{dpi::LogicalSize, window::Window};
+use tracing::debug;
+use winit::{
+    dpi::{LogicalSize, PhysicalPosition},
+    window::Window,
+};
 
+// Converts between the coordinate spaces used for the window. The rest of the
+// app works in logical pixels (`ViewportPoint`/`ViewportRect`), while input
+// comes in and drawing goes out in physical device pixels.
+//
+// The window spans every monitor, so the viewport also keeps track of where
+// each of them sits within it.
+//
 // TODO: This isn't consistent/safe to interact with prior to the Resumed event.
 // How do we want to express this, once I start handling errors correctly?
 pub struct Viewport {
     window: Rc<Window>,
+    // Kept up to date by the stage as the window moves between screens
+    scale_factor: f64,
+    // Where the window's top left corner is on the desktop, in device pixels
+    origin: PhysicalPosition<i32>,
+    // Ordered from left to right, then top to bottom. Never empty.
+    monitors: Vec<MonitorArea>,
 }
 
 impl Viewport {
     pub fn new(window: Rc<Window>) -> Viewport {
-        Self { window }
+        let scale_factor = window.scale_factor();
+        let mut viewport = Self {
+            window,
+            scale_factor,
+            origin: PhysicalPosition::new(0, 0),
+            monitors: Vec::new(),
+        };
+        viewport.refresh_monitors();
+        viewport
     }
 
-    pub fn convert_point_to_renderer_coord(&self, point: ViewportPoint) -> RendererCoord {
-        let scale_factor = self.window.scale_factor();
-        let size: LogicalSize<f32> = self.window.inner_size().to_logical(scale_factor);
-        let half_width = size.width / 2.0;
-        let half_height = size.height / 2.0;
+    pub fn scale_factor(&self) -> f32 {
+        self.scale_factor as f32
+    }
+
+    pub fn set_scale_factor(&mut self, scale_factor: f64) {
+        self.scale_factor = scale_factor;
+        self.refresh_monitors();
+    }
+
+    // Re-reads where the window and each monitor are. Needs to be called
+    // whenever the window is moved or resized.
+    pub fn refresh_monitors(&mut self) {
+        self.origin = self.window.inner_position().unwrap_or(self.origin);
+
+        // The whole window shares one scale factor, so monitors with a
+        // different pixel density than the window's end up slightly off. This
+        // is close enough to keep fursona on screen.
+        let scale_factor = self.scale_factor();
+        let top_inset = get_top_inset();
+        let mut monitors: Vec<MonitorArea> = self
+            .window
+            .available_monitors()
+            .map(|monitor| {
+                let position = monitor.position();
+                let size = monitor.size();
+                MonitorArea {
+                    name: monitor.name(),
+                    rect: ViewportRect {
+                        x: (position.x - self.origin.x) as f32 / scale_factor,
+                        y: (position.y - self.origin.y) as f32 / scale_factor + top_inset,
+                        width: size.width as f32 / scale_factor,
+                        height: size.height as f32 / scale_factor - top_inset,
+                    },
+                }
+            })
+            .collect();
+        monitors.sort_by(|a, b| {
+            a.left()
+                .total_cmp(&b.left())
+                .then(a.top().total_cmp(&b.top()))
+        });
+
+        // Not every platform can tell us about its monitors, in which case
+        // the best we can do is treat the window as one big monitor
+        if monitors.is_empty() {
+            let size = self.logical_size();
+            monitors.push(MonitorArea {
+                name: None,
+                rect: ViewportRect {
+                    x: 0.0,
+                    y: top_inset,
+                    width: size.width,
+                    height: size.height - top_inset,
+                },
+            });
+        }
+
+        for (index, monitor) in monitors.iter().enumerate() {
+            debug!(
+                "Monitor {} ({}): {}x{} at ({}, {})",
+                index + 1,
+                monitor.name.as_deref().unwrap_or("unnamed"),
+                monitor.rect.width,
+                monitor.rect.height,
+                monitor.rect.x,
+                monitor.rect.y
+            );
+        }
+        self.monitors = monitors;
+    }
+
+    pub fn monitors(&self) -> &[MonitorArea] {
+        &self.monitors
+    }
+
+    // The monitor the given point is on, or the closest one to it if it's
+    // somewhere off screen.
+    pub fn monitor_at(&self, point: &ViewportPoint) -> usize {
+        let distance_to = |monitor: &MonitorArea| {
+            let dx = (monitor.left() - point.x)
+                .max(point.x - monitor.right())
+                .max(0.0);
+            let dy = (monitor.top() - point.y)
+                .max(point.y - monitor.bottom())
+                .max(0.0);
+            dx * dx + dy * dy
+        };
+
+        self.monitors
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| distance_to(a).total_cmp(&distance_to(b)))
+            .map_or(0, |(index, _)| index)
+    }
+
+    // The stretch a fursona on the given monitor can walk along. Unless
+    // they're pinned to that monitor, this carries on through any monitors
+    // beside it, as long as there's no gap to cross.
+    pub fn walkable_span(&self, monitor: usize, pinned: bool) -> WalkableSpan {
+        walkable_span(&self.monitors, monitor, pinned)
+    }
+
+    fn logical_size(&self) -> LogicalSize<f32> {
+        self.window.inner_size().to_logical(self.scale_factor)
+    }
+
+    // Converts a point relative to the desktop, which is how the mouse is
+    // reported, into one relative to the viewport.
+    pub fn convert_desktop_point(&self, point: PhysicalPoint) -> ViewportPoint {
+        PhysicalPoint {
+            x: point.x - self.origin.x as f32,
+            y: point.y - self.origin.y as f32,
+        }
+        .to_logical(self.scale_factor())
+    }
 
-        RendererCoord {
-            x: (point.x as f32 - half_width) / half_width,
-            y: (half_height - point.y as f32) / half_height,
+    // Converts a rect relative to the desktop, which is how other windows are
+    // reported, into one relative to the viewport.
+    pub fn convert_desktop_rect(&self, rect: PhysicalRect) -> ViewportRect {
+        let scale_factor = self.scale_factor();
+        ViewportRect {
+            x: (rect.x - self.origin.x as f32) / scale_factor,
+            y: (rect.y - self.origin.y as f32) / scale_factor,
+            width: rect.width / scale_factor,
+            height: rect.height / scale_factor,
         }
     }
 
+    // The resulting rect's pixel size is in device pixels, since that's what
+    // the renderer actually draws.
     pub fn convert_rect(&self, rect: ViewportRect) -> RendererRect {
-        let scale_factor = self.window.scale_factor();
-        let size: LogicalSize<f32> = self.window.inner_size().to_logical(scale_factor);
-        let half_width = size.width / 2.0;
-        let half_height = size.height / 2.0;
+        let size = self.window.inner_size();
+        let width = size.width as f32;
+        let height = size.height as f32;
+        let half_width = width / 2.0;
+        let half_height = height / 2.0;
+
+        let rect = rect.to_physical(self.scale_factor()).snapped();
 
         RendererRect {
-            x: (rect.x as f32 - half_width) / half_width,
-            y: (half_height - rect.y as f32) / half_height,
-            width: (rect.width as f32) / size.width,
-            height: (rect.height as f32) / size.height,
-            pixel_width: rect.width as f32,
-            pixel_height: rect.height as f32,
+            x: (rect.x - half_width) / half_width,
+            y: (half_height - rect.y) / half_height,
+            width: rect.width / width,
+            height: rect.height / height,
+            pixel_width: rect.width,
+            pixel_height: rect.height,
+        }
+    }
+}
+
+// `Viewport::walkable_span`, for any set of monitors ordered from left to
+// right
+fn walkable_span(monitors: &[MonitorArea], monitor: usize, pinned: bool) -> WalkableSpan {
+    let start = match monitors.get(monitor) {
+        Some(start) => start,
+        None => return WalkableSpan::new(monitors[..1].to_vec()),
+    };
+    let mut span = vec![start.clone()];
+    if pinned {
+        return WalkableSpan::new(span);
+    }
+
+    // Each monitor can only appear once, which also stops us going round
+    // in circles if the layout is odd
+    while span.len() < monitors.len() {
+        let leftmost = &span[0];
+        match monitors
+            .iter()
+            .find(|monitor| monitor.touches_on_right(leftmost))
+        {
+            Some(monitor) => span.insert(0, monitor.clone()),
+            None => break,
+        }
+    }
+    while span.len() < monitors.len() {
+        let rightmost = &span[span.len() - 1];
+        match monitors
+            .iter()
+            .find(|monitor| rightmost.touches_on_right(monitor))
+        {
+            Some(monitor) => span.push(monitor.clone()),
+            None => break,
         }
     }
+
+    WalkableSpan::new(span)
+}
+
+// How much of the top of each monitor is kept clear for the OS.
+#[cfg(target_os = "macos")]
+fn get_top_inset() -> f32 {
+    // Each display gets its own menu bar, unless "Displays have separate
+    // Spaces" is turned off, in which case this leaves a little extra room on
+    // the secondary displays
+    get_menu_bar_height() as f32
+}
+
+#[cfg(not(target_os = "macos"))]
+fn get_top_inset() -> f32 {
+    0.0
 }
 
 #[cfg(target_os = "macos")]
@@ -50,23 +248,95 @@ fn get_menu_bar_height() -> f64 {
 
     let mtm = MainThreadMarker::new().expect("must be on the main thread");
     let app = NSApplication::sharedApplication(mtm);
-    let menu = unsafe { app.mainMenu().unwrap() };
-    let height = unsafe { menu.menuBarHeight() };
-    height
+    match unsafe { app.mainMenu() } {
+        Some(menu) => unsafe { menu.menuBarHeight() },
+        // Without a menu there's no menu bar to keep out of the way of
+        None => 0.0,
+    }
 }
 
-#[cfg(target_os = "macos")]
-impl Viewport {
-    pub fn left(&self) -> f32 {
-        0.0
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn monitor(x: f32, y: f32, width: f32, height: f32) -> MonitorArea {
+        MonitorArea {
+            name: None,
+            rect: ViewportRect {
+                x,
+                y,
+                width,
+                height,
+            },
+        }
     }
 
-    pub fn right(&self) -> f32 {
-        let scale_factor = self.window.scale_factor();
-        self.window.inner_size().to_logical(scale_factor).width
+    #[test]
+    fn spans_a_single_monitor() {
+        let monitors = [monitor(0.0, 0.0, 1280.0, 720.0)];
+
+        let span = walkable_span(&monitors, 0, false);
+        assert_eq!((span.left(), span.right()), (0.0, 1280.0));
+        assert_eq!((span.top_at(640.0), span.bottom_at(640.0)), (0.0, 720.0));
+    }
+
+    #[test]
+    fn spans_monitors_side_by_side_from_either_one() {
+        let monitors = [
+            monitor(0.0, 0.0, 1280.0, 720.0),
+            monitor(1280.0, 0.0, 1920.0, 1080.0),
+        ];
+
+        for start in [0, 1] {
+            let span = walkable_span(&monitors, start, false);
+            assert_eq!((span.left(), span.right()), (0.0, 3200.0));
+            assert_eq!(span.bottom_at(640.0), 720.0);
+            assert_eq!(span.bottom_at(2000.0), 1080.0);
+        }
+    }
+
+    #[test]
+    fn spans_monitors_at_different_heights_while_they_overlap() {
+        let monitors = [
+            monitor(0.0, 200.0, 1280.0, 720.0),
+            monitor(1280.0, 0.0, 1280.0, 720.0),
+            // Only its top edge meets the bottom of the one beside it
+            monitor(2560.0, 720.0, 1280.0, 720.0),
+        ];
+
+        let span = walkable_span(&monitors, 0, false);
+        assert_eq!((span.left(), span.right()), (0.0, 2560.0));
+        assert_eq!((span.top_at(640.0), span.top_at(1920.0)), (200.0, 0.0));
+
+        let span = walkable_span(&monitors, 2, false);
+        assert_eq!((span.left(), span.right()), (2560.0, 3840.0));
     }
 
-    pub fn top(&self) -> f32 {
-        get_menu_bar_height() as f32
+    #[test]
+    fn stops_at_gaps_and_when_pinned() {
+        let monitors = [
+            monitor(0.0, 0.0, 1280.0, 720.0),
+            monitor(1280.0, 0.0, 1280.0, 720.0),
+            monitor(2600.0, 0.0, 1280.0, 720.0),
+        ];
+
+        let span = walkable_span(&monitors, 2, false);
+        assert_eq!((span.left(), span.right()), (2600.0, 3880.0));
+
+        let span = walkable_span(&monitors, 1, true);
+        assert_eq!((span.left(), span.right()), (1280.0, 2560.0));
+    }
+
+    #[test]
+    fn touches_only_monitors_directly_to_the_right() {
+        let left = monitor(0.0, 0.0, 1280.0, 720.0);
+
+        assert!(left.touches_on_right(&monitor(1280.0, 0.0, 1280.0, 720.0)));
+        // Rounding between scale factors is close enough
+        assert!(left.touches_on_right(&monitor(1280.5, 100.0, 1280.0, 720.0)));
+        assert!(!left.touches_on_right(&monitor(1300.0, 0.0, 1280.0, 720.0)));
+        assert!(!left.touches_on_right(&monitor(1280.0, 720.0, 1280.0, 720.0)));
+        assert!(!left.touches_on_right(&monitor(-1280.0, 0.0, 1280.0, 720.0)));
+        assert!(!left.touches_on_right(&left));
     }
 }
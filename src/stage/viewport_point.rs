@@ -1,8 +1,9 @@
 use std::ops::Add;
 
-// A point, measured in pixels, that is relative to the viewport. These would
-// be pixel coordinates as experienced by the user.
-#[derive(Clone)]
+// A point, measured in logical pixels, that is relative to the viewport. These
+// would be pixel coordinates as experienced by the user, and are what
+// everything outside of the stage works in.
+#[derive(Clone, Debug)]
 pub struct ViewportPoint {
     pub x: f32,
     pub y: f32,
@@ -1,7 +1,8 @@
-use super::ViewportPoint;
+use super::{PhysicalRect, ViewportPoint};
 
-// A point, measured in pixels, that is relative to the viewport. These would
-// be pixel coordinates as experienced by the user.
+// A rect, measured in logical pixels, that is relative to the viewport. These
+// would be pixel coordinates as experienced by the user.
+#[derive(Clone)]
 pub struct ViewportRect {
     pub x: f32,
     pub y: f32,
@@ -10,10 +11,19 @@ pub struct ViewportRect {
 }
 
 impl ViewportRect {
-    pub fn position(&self) -> ViewportPoint {
-        ViewportPoint {
-            x: self.x,
-            y: self.y,
+    pub fn contains(&self, point: &ViewportPoint) -> bool {
+        point.x >= self.x
+            && point.x <= self.x + self.width
+            && point.y >= self.y
+            && point.y <= self.y + self.height
+    }
+
+    pub fn to_physical(&self, scale_factor: f32) -> PhysicalRect {
+        PhysicalRect {
+            x: self.x * scale_factor,
+            y: self.y * scale_factor,
+            width: self.width * scale_factor,
+            height: self.height * scale_factor,
         }
     }
 }
@@ -0,0 +1,39 @@
+use super::MonitorArea;
+
+// A run of monitors that sit side by side with no gaps between them, which a
+// fursona can walk along from one end to the other. Each monitor may be a
+// different height, so where the top is depends on where along it you are.
+pub struct WalkableSpan {
+    // Ordered from left to right
+    monitors: Vec<MonitorArea>,
+}
+
+impl WalkableSpan {
+    pub fn new(monitors: Vec<MonitorArea>) -> Self {
+        Self { monitors }
+    }
+
+    pub fn left(&self) -> f32 {
+        self.monitors.first().map_or(0.0, MonitorArea::left)
+    }
+
+    pub fn right(&self) -> f32 {
+        self.monitors.last().map_or(0.0, MonitorArea::right)
+    }
+
+    // The monitor the given x position is over
+    fn monitor_at(&self, x: f32) -> Option<&MonitorArea> {
+        self.monitors
+            .iter()
+            .find(|monitor| x < monitor.right())
+            .or(self.monitors.last())
+    }
+
+    pub fn top_at(&self, x: f32) -> f32 {
+        self.monitor_at(x).map_or(0.0, MonitorArea::top)
+    }
+
+    pub fn bottom_at(&self, x: f32) -> f32 {
+        self.monitor_at(x).map_or(0.0, MonitorArea::bottom)
+    }
+}
@@ -0,0 +1,118 @@
+use crate::fursona::behaviors::Facing;
+use crate::fursona::{FursonaInstance, FursonaInstanceState};
+use crate::stage::{Stage, ViewportPoint};
+use std::path::{Path, PathBuf};
+
+mod state_file;
+
+use state_file::{CurrentStateFile, StateFileFacing, StateFileInstance};
+use tracing::{error, info, warn};
+
+const STATE_DIRECTORY_NAME: &str = "desktop-fursona";
+const STATE_FILE_NAME: &str = "state.json";
+
+// What our fursona were up to when we last exited. Unlike Settings, this is
+// written by us rather than the user, and is only ever a best effort: if it's
+// missing or can't be read, everyone just starts from scratch.
+pub struct State {
+    instances: Vec<FursonaInstanceState>,
+}
+
+impl State {
+    fn from_state_file(file: CurrentStateFile) -> Self {
+        Self {
+            instances: file
+                .instances
+                .into_iter()
+                .map(|instance| FursonaInstanceState {
+                    name: instance.name,
+                    position: ViewportPoint {
+                        x: instance.x,
+                        y: instance.y,
+                    },
+                    behavior: instance.behavior,
+                    facing: match instance.facing {
+                        StateFileFacing::Left => Facing::Left,
+                        StateFileFacing::Right => Facing::Right,
+                    },
+                })
+                .collect(),
+        }
+    }
+
+    fn to_state_file(&self) -> CurrentStateFile {
+        CurrentStateFile {
+            instances: self
+                .instances
+                .iter()
+                .map(|instance| StateFileInstance {
+                    name: instance.name.to_owned(),
+                    x: instance.position.x,
+                    y: instance.position.y,
+                    behavior: instance.behavior.to_owned(),
+                    facing: match instance.facing {
+                        Facing::Left => StateFileFacing::Left,
+                        Facing::Right => StateFileFacing::Right,
+                    },
+                })
+                .collect(),
+        }
+    }
+
+    // The location of the state file when one isn't explicitly provided:
+    // the platform's state directory (`$XDG_STATE_HOME` on Linux), or its
+    // local data directory on platforms that don't have one.
+    pub fn default_path() -> PathBuf {
+        match dirs::state_dir().or_else(dirs::data_local_dir) {
+            Some(dir) => dir.join(STATE_DIRECTORY_NAME).join(STATE_FILE_NAME),
+            None => PathBuf::from(".").join(STATE_FILE_NAME),
+        }
+    }
+
+    pub fn load(filename: &Path) -> Self {
+        if filename.exists() {
+            match CurrentStateFile::load(filename) {
+                Ok(file) => return State::from_state_file(file),
+                Err(e) => warn!("Load of state file {} failed: {e}", filename.display()),
+            }
+        }
+
+        Self {
+            instances: Vec::new(),
+        }
+    }
+
+    pub fn save(&self, filename: &Path) {
+        info!("Saving state to {}", filename.display());
+        if let Err(msg) = self.to_state_file().save(filename) {
+            error!("Error saving state file: {msg}");
+        }
+    }
+
+    pub fn capture<'a, I: Iterator<Item = &'a FursonaInstance>>(instances: I) -> Self {
+        Self {
+            instances: instances.map(|instance| instance.state()).collect(),
+        }
+    }
+
+    // Hands the saved state back to the instances it came from. Instances
+    // are matched up by fursona name in order, so removing or reordering
+    // fursona in the settings doesn't give anyone the wrong state.
+    pub fn restore<'a, I: Iterator<Item = &'a mut FursonaInstance>>(
+        mut self,
+        instances: I,
+        stage: &Stage,
+    ) {
+        for instance in instances {
+            let index = self
+                .instances
+                .iter()
+                .position(|state| state.name == instance.name());
+
+            if let Some(index) = index {
+                let state = self.instances.remove(index);
+                instance.restore_state(&state, stage);
+            }
+        }
+    }
+}
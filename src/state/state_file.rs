@@ -0,0 +1,74 @@
+mod v1;
+
+use crate::atomic_write::write_atomically;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+pub use v1::{V1StateFileFacing as StateFileFacing, V1StateFileInstance as StateFileInstance};
+
+// Structure for the JSON representation of the runtime state. This is
+// versioned the same way as the settings file, so that older state files can
+// be migrated forward rather than discarded when the format changes.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "version")]
+enum StateFile {
+    #[serde(rename = "1")]
+    V1(v1::V1StateFile),
+}
+
+pub type CurrentStateFile = v1::V1StateFile;
+
+impl StateFile {
+    fn load(filename: &Path) -> Result<StateFile, String> {
+        // Read the file from the filesystem
+        let file_contents = match fs::read_to_string(filename) {
+            Ok(str) => str,
+            Err(e) => {
+                return Err(e.to_string());
+            }
+        };
+
+        // Parse the string into a JSON object
+        let parsed: Result<StateFile, serde_json::Error> = serde_json::from_str(&file_contents);
+        match parsed {
+            Ok(j) => Ok(j),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    fn save(&self, filename: &Path) -> Result<(), String> {
+        // Convert the structure to a JSON string
+        let file_contents = match serde_json::to_string_pretty(self) {
+            Ok(str) => str,
+            Err(msg) => {
+                return Err(msg.to_string());
+            }
+        };
+
+        if let Some(parent) = filename.parent() {
+            if let Err(e) = fs::create_dir_all(parent) {
+                return Err(e.to_string());
+            }
+        }
+
+        // Write the string to the filesystem
+        write_atomically(filename, file_contents.as_bytes())
+    }
+}
+
+impl CurrentStateFile {
+    pub fn load(filename: &Path) -> Result<CurrentStateFile, String> {
+        // Older versions get migrated forward here, one version at a time,
+        // the same way that CurrentSettingsFile::load does it. Unlike settings
+        // there's no need to save the migrated file, since state is written
+        // out again on exit anyway.
+        match StateFile::load(filename)? {
+            StateFile::V1(file) => Ok(file),
+        }
+    }
+
+    pub fn save(self, filename: &Path) -> Result<(), String> {
+        StateFile::V1(self).save(filename)
+    }
+}
@@ -0,0 +1,23 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+pub enum V1StateFileFacing {
+    #[serde(rename = "left")]
+    Left,
+    #[serde(rename = "right")]
+    Right,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct V1StateFileInstance {
+    pub name: String,
+    pub x: f32,
+    pub y: f32,
+    pub behavior: String,
+    pub facing: V1StateFileFacing,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct V1StateFile {
+    pub instances: Vec<V1StateFileInstance>,
+}
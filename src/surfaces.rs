@@ -0,0 +1,110 @@
+mod mock_surface_provider;
+mod surface;
+mod surface_error;
+mod surface_provider;
+#[cfg(all(unix, not(target_os = "macos")))]
+mod x11_surface_provider;
+
+use crate::stage::Viewport;
+use mock_surface_provider::MockSurfaceProvider;
+use tracing::{info, warn};
+#[cfg(all(unix, not(target_os = "macos")))]
+use x11_surface_provider::X11SurfaceProvider;
+
+pub use surface::{DesktopWindow, Surface};
+pub use surface_error::SurfaceError;
+pub use surface_provider::SurfaceProvider;
+
+// Asking for every window's geometry takes a round trip to the window system
+// per window, so we don't want to do it every frame
+const POLL_INTERVAL_MS: u32 = 200;
+
+// Keeps track of the other application windows on the desktop, so that
+// fursona can use their top edges as platforms.
+pub struct Surfaces {
+    provider: Option<Box<dyn SurfaceProvider>>,
+    surfaces: Vec<Surface>,
+    time_until_poll_ms: u32,
+}
+
+impl Surfaces {
+    // Picks the provider for the platform we're running on. When `mock` is set
+    // a fake set of windows is used instead, whatever the platform.
+    pub fn new(mock: bool) -> Self {
+        let provider = if mock {
+            info!("Using mock windows");
+            Some(Box::new(MockSurfaceProvider::new()) as Box<dyn SurfaceProvider>)
+        } else {
+            match Surfaces::platform_provider() {
+                Ok(provider) => Some(provider),
+                Err(e @ SurfaceError::Unsupported) => {
+                    info!("Can't walk on windows: {e}");
+                    None
+                }
+                Err(e) => {
+                    warn!("Can't walk on windows: {e}");
+                    None
+                }
+            }
+        };
+
+        Self {
+            provider,
+            surfaces: Vec::new(),
+            time_until_poll_ms: 0,
+        }
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    fn platform_provider() -> Result<Box<dyn SurfaceProvider>, SurfaceError> {
+        // Under Wayland we'd only see the windows running through XWayland
+        if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+            return Err(SurfaceError::Unsupported);
+        }
+
+        Ok(Box::new(X11SurfaceProvider::connect()?))
+    }
+
+    #[cfg(not(all(unix, not(target_os = "macos"))))]
+    fn platform_provider() -> Result<Box<dyn SurfaceProvider>, SurfaceError> {
+        Err(SurfaceError::Unsupported)
+    }
+
+    pub fn update(&mut self, delta_t_ms: u32, viewport: &Viewport) {
+        let provider = match &mut self.provider {
+            Some(provider) => provider,
+            None => return,
+        };
+        provider.advance(delta_t_ms);
+
+        if delta_t_ms < self.time_until_poll_ms {
+            self.time_until_poll_ms -= delta_t_ms;
+            return;
+        }
+        self.time_until_poll_ms = POLL_INTERVAL_MS;
+
+        match provider.windows() {
+            Ok(windows) => {
+                self.surfaces = windows
+                    .into_iter()
+                    .map(|window| Surface {
+                        id: window.id,
+                        rect: viewport.convert_desktop_rect(window.rect),
+                    })
+                    .collect();
+            }
+            Err(e) => {
+                // Whatever went wrong is unlikely to fix itself, so stop
+                // asking rather than filling the log
+                warn!("Couldn't list windows, so there's nothing to walk on: {e}");
+                self.provider = None;
+                self.surfaces.clear();
+            }
+        }
+    }
+
+    // From the bottom of the stack to the top
+    pub fn list(&self) -> &[Surface] {
+        &self.surfaces
+    }
+}
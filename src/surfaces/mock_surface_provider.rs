@@ -0,0 +1,106 @@
+use super::{DesktopWindow, SurfaceError, SurfaceProvider};
+use crate::stage::PhysicalRect;
+
+// How long each arrangement of windows lasts before moving on to the next
+const STEP_DURATION_MS: u64 = 5000;
+
+// (id, x, y, width, height) in device pixels, relative to the top left of the
+// primary monitor. Kept within 1280x720 so that they fit on any screen.
+type MockWindow = (u64, f32, f32, f32, f32);
+
+// A loop of window arrangements that exercises everything fursona should do
+// with windows: standing on them, jumping between them, and falling when one
+// moves or closes.
+const STEPS: &[&[MockWindow]] = &[
+    &[
+        (1, 80.0, 420.0, 420.0, 300.0),
+        (2, 560.0, 300.0, 380.0, 420.0),
+    ],
+    // The right-hand window moves down
+    &[
+        (1, 80.0, 420.0, 420.0, 300.0),
+        (2, 560.0, 380.0, 380.0, 340.0),
+    ],
+    // It closes, and another opens further along
+    &[
+        (1, 80.0, 420.0, 420.0, 300.0),
+        (3, 900.0, 240.0, 340.0, 480.0),
+    ],
+    // Everything closes
+    &[],
+];
+
+// Pretends that a handful of windows are open and shuffling around, so that
+// walking on windows can be tried out without an X server, or on platforms
+// that we can't list windows on yet.
+// Time only moves on as we're told it does, so that things play out the same
+// way however long frames take.
+pub struct MockSurfaceProvider {
+    elapsed_ms: u64,
+}
+
+impl MockSurfaceProvider {
+    pub fn new() -> Self {
+        Self { elapsed_ms: 0 }
+    }
+}
+
+impl SurfaceProvider for MockSurfaceProvider {
+    fn advance(&mut self, delta_t_ms: u32) {
+        self.elapsed_ms += delta_t_ms as u64;
+    }
+
+    fn windows(&mut self) -> Result<Vec<DesktopWindow>, SurfaceError> {
+        let step = (self.elapsed_ms / STEP_DURATION_MS) as usize;
+
+        Ok(STEPS[step % STEPS.len()]
+            .iter()
+            .map(|&(id, x, y, width, height)| DesktopWindow {
+                id,
+                rect: PhysicalRect {
+                    x,
+                    y,
+                    width,
+                    height,
+                },
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(provider: &mut MockSurfaceProvider) -> Vec<u64> {
+        let windows = provider.windows().unwrap();
+        windows.iter().map(|window| window.id).collect()
+    }
+
+    #[test]
+    fn moves_on_with_the_time_it_is_given() {
+        let mut provider = MockSurfaceProvider::new();
+        assert_eq!(ids(&mut provider), vec![1, 2]);
+
+        provider.advance(STEP_DURATION_MS as u32 - 1);
+        assert_eq!(ids(&mut provider), vec![1, 2]);
+
+        // The right-hand window moves down
+        provider.advance(1);
+        let windows = provider.windows().unwrap();
+        assert_eq!(windows[1].id, 2);
+        assert_eq!(windows[1].rect.y, 380.0);
+
+        provider.advance(STEP_DURATION_MS as u32);
+        assert_eq!(ids(&mut provider), vec![1, 3]);
+        provider.advance(STEP_DURATION_MS as u32);
+        assert_eq!(ids(&mut provider), Vec::<u64>::new());
+    }
+
+    #[test]
+    fn loops_back_to_the_start() {
+        let mut provider = MockSurfaceProvider::new();
+        provider.advance(STEP_DURATION_MS as u32 * STEPS.len() as u32);
+        assert_eq!(ids(&mut provider), vec![1, 2]);
+    }
+}
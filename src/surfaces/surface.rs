@@ -0,0 +1,30 @@
+use crate::stage::{PhysicalRect, ViewportRect};
+
+// Another application's window, as reported by a `SurfaceProvider`, in device
+// pixels relative to the desktop. This includes the window's decorations.
+pub struct DesktopWindow {
+    pub id: u64,
+    pub rect: PhysicalRect,
+}
+
+// The top edge of another application's window, which fursona can stand on.
+#[derive(Clone)]
+pub struct Surface {
+    // Stays the same for as long as the window is open
+    pub id: u64,
+    pub rect: ViewportRect,
+}
+
+impl Surface {
+    pub fn left(&self) -> f32 {
+        self.rect.x
+    }
+
+    pub fn right(&self) -> f32 {
+        self.rect.x + self.rect.width
+    }
+
+    pub fn top(&self) -> f32 {
+        self.rect.y
+    }
+}
@@ -0,0 +1,16 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum SurfaceError {
+    #[error("platform behavior needs X11")]
+    Unsupported,
+    #[cfg(all(unix, not(target_os = "macos")))]
+    #[error("couldn't connect to the X server: {0}")]
+    Connect(#[from] x11rb::errors::ConnectError),
+    #[cfg(all(unix, not(target_os = "macos")))]
+    #[error("lost the connection to the X server: {0}")]
+    Connection(#[from] x11rb::errors::ConnectionError),
+    #[cfg(all(unix, not(target_os = "macos")))]
+    #[error("the X server returned an error: {0}")]
+    Reply(#[from] x11rb::errors::ReplyError),
+}
@@ -0,0 +1,11 @@
+use super::{DesktopWindow, SurfaceError};
+
+pub trait SurfaceProvider {
+    // Called every frame. Only providers that make their windows up need to
+    // keep track of time.
+    fn advance(&mut self, _delta_t_ms: u32) {}
+
+    // Every visible window belonging to another application, ordered from the
+    // bottom of the stack to the top.
+    fn windows(&mut self) -> Result<Vec<DesktopWindow>, SurfaceError>;
+}
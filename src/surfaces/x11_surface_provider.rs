@@ -0,0 +1,118 @@
+use super::{DesktopWindow, SurfaceError, SurfaceProvider};
+use crate::stage::PhysicalRect;
+use tracing::trace;
+use x11rb::connection::Connection;
+use x11rb::protocol::xproto::{Atom, AtomEnum, ConnectionExt, Window};
+use x11rb::rust_connection::RustConnection;
+
+x11rb::atom_manager! {
+    Atoms: AtomsCookie {
+        _NET_CLIENT_LIST_STACKING,
+        _NET_FRAME_EXTENTS,
+        _NET_WM_PID,
+        _NET_WM_STATE,
+        _NET_WM_STATE_HIDDEN,
+        _NET_WM_WINDOW_TYPE,
+        _NET_WM_WINDOW_TYPE_DESKTOP,
+        _NET_WM_WINDOW_TYPE_DOCK,
+    }
+}
+
+// Lists windows through the window manager's EWMH hints, which every modern
+// X11 window manager provides.
+pub struct X11SurfaceProvider {
+    connection: RustConnection,
+    root: Window,
+    atoms: Atoms,
+}
+
+impl X11SurfaceProvider {
+    pub fn connect() -> Result<Self, SurfaceError> {
+        let (connection, screen_num) = x11rb::connect(None)?;
+        let root = connection.setup().roots[screen_num].root;
+        let atoms = Atoms::new(&connection)?.reply()?;
+
+        Ok(Self {
+            connection,
+            root,
+            atoms,
+        })
+    }
+
+    fn cardinals(&self, window: Window, property: Atom) -> Result<Vec<u32>, SurfaceError> {
+        let reply = self
+            .connection
+            .get_property(false, window, property, AtomEnum::ANY, 0, u32::MAX)?
+            .reply()?;
+
+        Ok(reply
+            .value32()
+            .map(|values| values.collect())
+            .unwrap_or_default())
+    }
+
+    fn window(&self, window: Window) -> Result<Option<DesktopWindow>, SurfaceError> {
+        // Our own window covers the whole desktop, so it'd make for a
+        // rather large platform
+        let pid = self.cardinals(window, self.atoms._NET_WM_PID)?;
+        if pid.first() == Some(&std::process::id()) {
+            return Ok(None);
+        }
+
+        let state = self.cardinals(window, self.atoms._NET_WM_STATE)?;
+        if state.contains(&self.atoms._NET_WM_STATE_HIDDEN) {
+            return Ok(None);
+        }
+
+        let window_type = self.cardinals(window, self.atoms._NET_WM_WINDOW_TYPE)?;
+        if window_type.contains(&self.atoms._NET_WM_WINDOW_TYPE_DESKTOP)
+            || window_type.contains(&self.atoms._NET_WM_WINDOW_TYPE_DOCK)
+        {
+            return Ok(None);
+        }
+
+        let geometry = self.connection.get_geometry(window)?.reply()?;
+        let origin = self
+            .connection
+            .translate_coordinates(window, self.root, 0, 0)?
+            .reply()?;
+
+        // The window manager's decorations sit outside of the window itself,
+        // and the title bar is what we actually want to stand on
+        let (left, right, top, bottom) =
+            match self.cardinals(window, self.atoms._NET_FRAME_EXTENTS)?[..] {
+                [left, right, top, bottom] => (left, right, top, bottom),
+                _ => (0, 0, 0, 0),
+            };
+
+        Ok(Some(DesktopWindow {
+            id: window as u64,
+            rect: PhysicalRect {
+                x: (origin.dst_x as i32 - left as i32) as f32,
+                y: (origin.dst_y as i32 - top as i32) as f32,
+                width: (geometry.width as u32 + left + right) as f32,
+                height: (geometry.height as u32 + top + bottom) as f32,
+            },
+        }))
+    }
+}
+
+impl SurfaceProvider for X11SurfaceProvider {
+    fn windows(&mut self) -> Result<Vec<DesktopWindow>, SurfaceError> {
+        let stacking = self.cardinals(self.root, self.atoms._NET_CLIENT_LIST_STACKING)?;
+
+        let mut windows = Vec::new();
+        for window in stacking {
+            match self.window(window) {
+                Ok(Some(window)) => windows.push(window),
+                Ok(None) => (),
+                // Windows can close while we're in the middle of looking at
+                // them, which is fine, but losing the connection isn't
+                Err(SurfaceError::Reply(e)) => trace!("Skipping window {window}: {e}"),
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(windows)
+    }
+}